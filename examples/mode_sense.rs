@@ -30,11 +30,8 @@ fn print_power_condition(interface: &Scsi) -> scsir::Result<()> {
         .allocation_length(2)
         .issue_10()?;
 
-    let page = PageWrapper::<PowerConditionPage>::from_bytes(
-        mode::HeaderType::Long,
-        mode::DescriptorType::Long,
-        &page_bytes,
-    );
+    let page =
+        PageWrapper::<PowerConditionPage>::from_mode_sense(mode::HeaderType::Long, &page_bytes);
 
     let page_bytes = interface
         .mode_sense()
@@ -43,11 +40,8 @@ fn print_power_condition(interface: &Scsi) -> scsir::Result<()> {
         .allocation_length(page.header.required_allocation_length() as u16)
         .issue_10()?;
 
-    let page = PageWrapper::<PowerConditionPage>::from_bytes(
-        mode::HeaderType::Long,
-        mode::DescriptorType::Long,
-        &page_bytes,
-    );
+    let page =
+        PageWrapper::<PowerConditionPage>::from_mode_sense(mode::HeaderType::Long, &page_bytes);
 
     println!("{:#?}", page);
 
@@ -64,11 +58,8 @@ fn print_all_modes(interface: &Scsi) -> scsir::Result<()> {
         .allocation_length(2)
         .issue_10()?;
 
-    let page = PageWrapper::<PageHeaderStorage>::from_bytes(
-        mode::HeaderType::Long,
-        mode::DescriptorType::Long,
-        &page_bytes,
-    );
+    let page =
+        PageWrapper::<PageHeaderStorage>::from_mode_sense(mode::HeaderType::Long, &page_bytes);
 
     let bytes = interface
         .mode_sense()
@@ -89,7 +80,7 @@ fn print_all_modes(interface: &Scsi) -> scsir::Result<()> {
     while !descriptor_bytes.is_empty() {
         let descriptor;
         (descriptor, descriptor_bytes) =
-            DescriptorStorage::from_bytes(mode::DescriptorType::Long, descriptor_bytes);
+            DescriptorStorage::from_bytes(mode_header.descriptor_type(), descriptor_bytes);
 
         println!("{:#?}", descriptor);
     }
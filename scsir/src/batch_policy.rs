@@ -0,0 +1,13 @@
+use crate::Scsi;
+
+/// What Scsi::batch does after a step of the batch fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchPolicy {
+    /// the failed step is the last one run
+    StopOnError,
+    /// the remaining steps are run anyway
+    Continue,
+}
+
+/// One step of Scsi::batch, issuing a command and mapping its result to the batch's type.
+pub type BatchStep<'a, R> = dyn FnMut(&Scsi) -> crate::Result<R> + 'a;
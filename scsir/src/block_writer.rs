@@ -0,0 +1,318 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+use crate::Scsi;
+
+/// `std::io::Write` and `std::io::Seek` over the logical blocks of a Scsi, for streaming
+/// bytes onto a raw logical unit with WRITE(16).
+///
+/// Writes covering whole blocks at a block boundary go to the device directly. Bytes that
+/// don't fill their block are buffered until the block is complete, or until flush or seek.
+/// A block that is still partial then is written with a read-modify-write: the block is read
+/// with READ(16), the buffered bytes are merged in and the block is written back. That costs
+/// an extra READ and a WRITE of a whole block for every partial block, so writers caring
+/// about throughput should write whole, aligned blocks. Like `BufWriter`, dropping the
+/// writer flushes it and ignores any error, call flush to see them.
+#[derive(Debug)]
+pub struct ScsiBlockWriter<'a> {
+    interface: &'a Scsi,
+    block_size: u64,
+    // byte offset of the next write
+    position: u64,
+    // bytes of the block at pending_lba not written yet, starting at pending_offset within
+    // the block and ending at position
+    pending: Vec<u8>,
+    pending_lba: u64,
+    pending_offset: usize,
+}
+
+impl<'a> ScsiBlockWriter<'a> {
+    /// Starts at byte 0, with the logical block size of Scsi::block_size.
+    pub fn new(interface: &'a Scsi) -> crate::Result<Self> {
+        let block_size = interface.block_size()?;
+        if block_size == 0 {
+            return Err(crate::Error::BadArgument(
+                "logical block size should not be 0.".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            interface,
+            block_size: block_size as u64,
+            position: 0,
+            pending: vec![],
+            pending_lba: 0,
+            pending_offset: 0,
+        })
+    }
+
+    fn write_blocks(&self, logical_block_address: u64, bytes: &[u8]) -> io::Result<()> {
+        self.interface
+            .write()
+            .logical_block_size(self.block_size as u32)
+            .logical_block_address(logical_block_address)
+            .parameter(bytes)
+            .issue_16()
+            .map_err(to_io_error)
+    }
+
+    // writes the pending bytes, merged into the current content of their block unless
+    // they cover it completely. they stay pending if that fails, to be retried later
+    fn write_pending(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        if self.pending.len() as u64 == self.block_size {
+            self.write_blocks(self.pending_lba, &self.pending)?;
+        } else {
+            let mut block = self
+                .interface
+                .read()
+                .logical_block_size(self.block_size as u32)
+                .logical_block_address(self.pending_lba)
+                .transfer_length(1)
+                .issue_16()
+                .map_err(to_io_error)?;
+
+            if block.len() as u64 != self.block_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "short read of a partially written block",
+                ));
+            }
+
+            block[self.pending_offset..self.pending_offset + self.pending.len()]
+                .copy_from_slice(&self.pending);
+            self.write_blocks(self.pending_lba, &block)?;
+        }
+
+        self.pending.clear();
+
+        Ok(())
+    }
+}
+
+impl Write for ScsiBlockWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let logical_block_address = self.position / self.block_size;
+        let offset = (self.position % self.block_size) as usize;
+
+        if self.pending.is_empty() && offset == 0 && buf.len() as u64 >= self.block_size {
+            let max_blocks = u64::max(MAX_TRANSFER_BYTES / self.block_size, 1);
+            let blocks = u64::min(buf.len() as u64 / self.block_size, max_blocks);
+            let length = (blocks * self.block_size) as usize;
+
+            self.write_blocks(logical_block_address, &buf[..length])?;
+            self.position += length as u64;
+
+            return Ok(length);
+        }
+
+        if self.pending.is_empty() {
+            self.pending_lba = logical_block_address;
+            self.pending_offset = offset;
+        }
+
+        let length = usize::min(self.block_size as usize - offset, buf.len());
+        let pending_length = self.pending.len();
+        self.pending.extend_from_slice(&buf[..length]);
+        self.position += length as u64;
+
+        if offset + length == self.block_size as usize {
+            if let Err(e) = self.write_pending() {
+                // nothing of buf was written, so a retried write starts from the same state
+                self.pending.truncate(pending_length);
+                self.position -= length as u64;
+                return Err(e);
+            }
+        }
+
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()
+    }
+}
+
+impl Seek for ScsiBlockWriter<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.write_pending()?;
+
+        let position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => {
+                let capacity = self
+                    .interface
+                    .read_capacity()
+                    .issue_16()
+                    .map_err(to_io_error)?;
+                let end = capacity
+                    .returned_logical_block_address
+                    .saturating_add(1)
+                    .saturating_mul(self.block_size);
+                end.checked_add_signed(delta)
+            }
+        };
+
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.position)
+    }
+}
+
+impl Drop for ScsiBlockWriter<'_> {
+    fn drop(&mut self) {
+        let _ = self.write_pending();
+    }
+}
+
+// upper bound of a single WRITE issued for aligned data
+const MAX_TRANSFER_BYTES: u64 = 1024 * 1024;
+
+fn to_io_error(error: crate::Error) -> io::Error {
+    match error {
+        crate::Error::IO(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_SIZE: usize = 512;
+
+    fn test_instance() -> Scsi {
+        let scsi = Scsi::test_instance();
+        scsi.set_cached_block_size(BLOCK_SIZE as u32);
+        scsi
+    }
+
+    fn logical_block_address(cdb: &[u8]) -> u64 {
+        u64::from_be_bytes(cdb[2..10].try_into().unwrap())
+    }
+
+    #[test]
+    fn aligned_test() {
+        let scsi = test_instance();
+        let data: Vec<u8> = (0..2 * BLOCK_SIZE).map(|i| i as u8).collect();
+
+        let mut writer = ScsiBlockWriter::new(&scsi).unwrap();
+        writer.seek(SeekFrom::Start(BLOCK_SIZE as u64)).unwrap();
+        writer.write_all(&data).unwrap();
+        writer.flush().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 1, "one write");
+        assert_eq!(issued[0].cdb[0], 0x8A, "write(16)");
+        assert_eq!(logical_block_address(&issued[0].cdb), 1, "lba");
+        assert_eq!(issued[0].cdb[10..14], [0, 0, 0, 2], "transfer length");
+        assert_eq!(issued[0].data_out, data, "data");
+    }
+
+    #[test]
+    fn unaligned_test() {
+        let scsi = test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_data(&[0xEE; BLOCK_SIZE]);
+
+        let mut writer = ScsiBlockWriter::new(&scsi).unwrap();
+        writer.write_all(&[0x11; 300]).unwrap();
+        assert!(scsi.mock().issued().is_empty(), "partial block buffered");
+
+        writer.write_all(&[0x22; 300]).unwrap();
+        writer.flush().unwrap();
+
+        let issued = scsi.mock().issued();
+        let commands: Vec<(u8, u64)> = issued
+            .iter()
+            .map(|command| (command.cdb[0], logical_block_address(&command.cdb)))
+            .collect();
+        assert_eq!(
+            commands,
+            [(0x8A, 0), (0x88, 1), (0x8A, 1)],
+            "write, then read-modify-write"
+        );
+
+        let mut first = vec![0x11; 300];
+        first.resize(BLOCK_SIZE, 0x22);
+        assert_eq!(issued[0].data_out, first, "completed block");
+
+        let mut second = vec![0x22; 88];
+        second.resize(BLOCK_SIZE, 0xEE);
+        assert_eq!(issued[2].data_out, second, "merged block");
+    }
+
+    #[test]
+    fn failed_write_test() {
+        let medium_error = [
+            0x70, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x11, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let scsi = test_instance();
+        scsi.mock().push_sense(&medium_error);
+        scsi.mock().push_data(&[]);
+
+        let mut writer = ScsiBlockWriter::new(&scsi).unwrap();
+        writer.write_all(&[0x11; 300]).unwrap();
+        assert!(writer.write(&[0x22; 300]).is_err(), "failed write");
+
+        writer.write_all(&[0x22; 300]).unwrap();
+
+        let mut block = vec![0x11; 300];
+        block.resize(BLOCK_SIZE, 0x22);
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "write retried");
+        assert_eq!(issued[0].data_out, block, "failed block");
+        assert_eq!(issued[1].data_out, block, "same block retried");
+        assert_eq!(logical_block_address(&issued[1].cdb), 0, "same lba");
+
+        writer.write_all(&[0x44; BLOCK_SIZE]).unwrap();
+        assert_eq!(
+            logical_block_address(&scsi.mock().issued()[2].cdb),
+            1,
+            "position advanced once"
+        );
+    }
+
+    #[test]
+    fn seek_test() {
+        let scsi = test_instance();
+        let mut capacity = vec![0; 32];
+        capacity[..8].copy_from_slice(&99u64.to_be_bytes());
+        capacity[8..12].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+        scsi.mock().push_data(&capacity);
+        scsi.mock().push_data(&[0xEE; BLOCK_SIZE]);
+
+        let mut writer = ScsiBlockWriter::new(&scsi).unwrap();
+        let end = writer.seek(SeekFrom::End(-10)).unwrap();
+        assert_eq!(end, 100 * BLOCK_SIZE as u64 - 10, "end");
+
+        writer.write_all(&[0x33; 10]).unwrap();
+        drop(writer);
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 3, "read capacity, read, write");
+        assert_eq!(logical_block_address(&issued[2].cdb), 99, "last block");
+        assert_eq!(
+            issued[2].data_out[BLOCK_SIZE - 10..],
+            [0x33; 10],
+            "flushed on drop"
+        );
+
+        let mut writer = ScsiBlockWriter::new(&scsi).unwrap();
+        assert!(writer.seek(SeekFrom::Current(-1)).is_err(), "negative");
+    }
+}
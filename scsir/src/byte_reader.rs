@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+
+/// Big-endian cursor over returned parameter data. Unlike get_array, running out of bytes
+/// is an error rather than zero padding.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub(crate) fn read_bytes(&mut self, length: usize) -> crate::Result<&'a [u8]> {
+        if length > self.bytes.len() {
+            return Err(crate::Error::BadArgument(format!(
+                "parameter data ended early, {} bytes were expected but only {} are left.",
+                length,
+                self.bytes.len()
+            )));
+        }
+
+        let (read, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(read)
+    }
+
+    pub(crate) fn skip(&mut self, length: usize) -> crate::Result<()> {
+        self.read_bytes(length).map(|_| ())
+    }
+
+    pub(crate) fn read_u8(&mut self) -> crate::Result<u8> {
+        Ok(self.read_be(1)? as u8)
+    }
+
+    pub(crate) fn read_u16(&mut self) -> crate::Result<u16> {
+        Ok(self.read_be(2)? as u16)
+    }
+
+    pub(crate) fn read_u24(&mut self) -> crate::Result<u32> {
+        Ok(self.read_be(3)? as u32)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> crate::Result<u32> {
+        Ok(self.read_be(4)? as u32)
+    }
+
+    pub(crate) fn read_u48(&mut self) -> crate::Result<u64> {
+        self.read_be(6)
+    }
+
+    pub(crate) fn read_u64(&mut self) -> crate::Result<u64> {
+        self.read_be(8)
+    }
+
+    fn read_be(&mut self, length: usize) -> crate::Result<u64> {
+        Ok(self
+            .read_bytes(length)?
+            .iter()
+            .fold(0, |value, byte| (value << 8) | *byte as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_test() {
+        let bytes = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+            0x0F, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,
+        ];
+        let mut reader = ByteReader::new(&bytes);
+
+        assert_eq!(reader.read_u8().unwrap(), 0x01, "u8");
+        assert_eq!(reader.read_u16().unwrap(), 0x0203, "u16");
+        assert_eq!(reader.read_u24().unwrap(), 0x04_0506, "u24");
+        assert_eq!(reader.read_u32().unwrap(), 0x0708_090A, "u32");
+        assert_eq!(reader.read_u48().unwrap(), 0x0B0C_0D0E_0F10, "u48");
+        assert_eq!(reader.remaining(), 8, "remaining");
+        assert_eq!(reader.read_u64().unwrap(), 0x1112_1314_1516_1718, "u64");
+        assert_eq!(reader.remaining(), 0, "remaining");
+    }
+
+    #[test]
+    fn underrun_test() {
+        let mut reader = ByteReader::new(&[0x01, 0x02, 0x03]);
+
+        assert!(
+            matches!(reader.read_u32(), Err(crate::Error::BadArgument(_))),
+            "u32 from 3 bytes"
+        );
+        assert_eq!(reader.remaining(), 3, "nothing consumed");
+        assert_eq!(reader.read_bytes(2).unwrap(), &[0x01, 0x02], "bytes");
+        assert!(
+            matches!(reader.skip(2), Err(crate::Error::BadArgument(_))),
+            "skip past the end"
+        );
+        assert_eq!(reader.read_u8().unwrap(), 0x03, "last byte");
+        assert!(
+            matches!(reader.read_u8(), Err(crate::Error::BadArgument(_))),
+            "empty"
+        );
+    }
+}
@@ -3,10 +3,13 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{
+        bitfield_bound_check,
+        sense::{Descriptor, SenseData},
+    },
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
-    Command, DataDirection, Scsi,
+    Command, CommandOutcome, DataDirection, Scsi,
 };
 
 /// Determines the data flow direction between SAT layer and ATA device.
@@ -64,6 +67,38 @@ pub enum AtaProtocol {
     ReturnResponseInformation = 0x0F,
 }
 
+/// ATA registers returned by the SATL in the ATA Status Return sense descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtaTaskFileResult {
+    pub extend: bool,
+    pub error: u8,
+    pub count: u16,
+    pub lba: u64,
+    pub device: u8,
+    pub status: u8,
+}
+
+impl AtaTaskFileResult {
+    /// Only descriptor format sense data carries the full registers.
+    pub fn from_sense(sense: &SenseData) -> Option<Self> {
+        let SenseData::Descriptor(sense) = sense else {
+            return None;
+        };
+
+        sense.descriptors.iter().find_map(|d| match d {
+            Descriptor::AtaReturn(raw) if raw.len() >= ATA_RETURN_DESCRIPTOR_LENGTH => Some(Self {
+                extend: raw[2] & 0b1 != 0,
+                error: raw[3],
+                count: u16::from_be_bytes([raw[4], raw[5]]),
+                lba: u64::from_le_bytes([raw[7], raw[9], raw[11], raw[6], raw[8], raw[10], 0, 0]),
+                device: raw[12],
+                status: raw[13],
+            }),
+            _ => None,
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AtaPassThroughCommand<'a> {
     interface: &'a Scsi,
@@ -75,6 +110,7 @@ pub struct AtaPassThroughCommand<'a> {
     device: u8,
     command: u8,
     control: u8,
+    check_condition: bool,
     data_buffer: Vec<u8>,
 }
 
@@ -89,7 +125,8 @@ impl<'a> AtaPassThroughCommand<'a> {
             count: None,
             device: 0,
             command: 0,
-            control: 0,
+            control: interface.default_control(),
+            check_condition: false,
             data_buffer: vec![],
         }
     }
@@ -111,16 +148,29 @@ impl<'a> AtaPassThroughCommand<'a> {
         self
     }
 
+    // asks the SATL to return the ATA registers even on success, see issue_12_with_task_file
+    pub fn check_condition(&mut self, value: bool) -> &mut Self {
+        self.check_condition = value;
+        self
+    }
+
+    // features must be less than 0x100 for ata pass-through(12).
+    // ata pass-through(16) sends it as features(15:8) then features(7:0)
     pub fn features(&mut self, features: u16) -> &mut Self {
         self.features = features;
         self
     }
 
+    // lba must be less than 0x100_0000, as EXTEND is never set.
+    // ata pass-through(12) sends LBA(7:0), LBA(15:8), LBA(23:16) in that order, ata pass-through(16)
+    // interleaves the six lba bytes as LBA(31:24), LBA(7:0), LBA(39:32), LBA(15:8), LBA(47:40), LBA(23:16)
     pub fn lba(&mut self, lba: u64) -> &mut Self {
         self.lba = lba;
         self
     }
 
+    // count is in bytes and must be a multiple of 512, defaults to the parameter length.
+    // it is sent as a number of 512 byte sectors, high byte first for ata pass-through(16)
     pub fn count(&mut self, count: u16) -> &mut Self {
         self.count = Some(count);
         self
@@ -132,7 +182,19 @@ impl<'a> AtaPassThroughCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     pub fn issue_12(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        self.issue_12_with_task_file().map(|(data, _)| data)
+    }
+
+    // the task file is only returned with check_condition set and descriptor format sense data
+    pub fn issue_12_with_task_file(
+        &mut self,
+    ) -> crate::Result<(Option<Vec<u8>>, Option<AtaTaskFileResult>)> {
         bitfield_bound_check!(self.features, 8, "features")?;
         bitfield_bound_check!(self.lba, 24, "lba")?;
         let count = self.count.unwrap_or(self.data_buffer.len() as u16);
@@ -148,6 +210,7 @@ impl<'a> AtaPassThroughCommand<'a> {
             .with_t_dir(self.dir as u8)
             .with_protocol(self.protocol as u8)
             // Tell SATL to take parameter length (in number of 512b-blocks) from count(0:7)
+            .with_ck_cond(self.check_condition.into())
             .with_byte_block(1)
             .with_t_type(0)
             .with_t_length(0b10)
@@ -164,11 +227,19 @@ impl<'a> AtaPassThroughCommand<'a> {
         self.interface.issue(&ThisCommand {
             command_buffer,
             dir: self.dir.to_data_direction(),
+            check_condition: self.check_condition,
             data_buffer: self.data_buffer.clone().into(),
         })
     }
 
     pub fn issue_16(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        self.issue_16_with_task_file().map(|(data, _)| data)
+    }
+
+    // the task file is only returned with check_condition set and descriptor format sense data
+    pub fn issue_16_with_task_file(
+        &mut self,
+    ) -> crate::Result<(Option<Vec<u8>>, Option<AtaTaskFileResult>)> {
         bitfield_bound_check!(self.features, 16, "features")?;
         bitfield_bound_check!(self.lba, 24, "lba")?;
         let count = self.count.unwrap_or(self.data_buffer.len() as u16);
@@ -186,6 +257,7 @@ impl<'a> AtaPassThroughCommand<'a> {
             .with_t_dir(self.dir as u8)
             .with_protocol(self.protocol as u8)
             // Tell SATL to take parameter length (in number of 512b-blocks) from count(0:7)
+            .with_ck_cond(self.check_condition.into())
             .with_byte_block(1)
             .with_t_type(0)
             .with_t_length(0b10)
@@ -207,6 +279,7 @@ impl<'a> AtaPassThroughCommand<'a> {
         self.interface.issue(&ThisCommand {
             command_buffer,
             dir: self.dir.to_data_direction(),
+            check_condition: self.check_condition,
             data_buffer: self.data_buffer.clone().into(),
         })
     }
@@ -220,6 +293,7 @@ impl Scsi {
 
 const OPERATION_CODE_12: u8 = 0xA1;
 const OPERATION_CODE_16: u8 = 0x85;
+const ATA_RETURN_DESCRIPTOR_LENGTH: usize = 14;
 
 #[bitfield]
 #[derive(Clone, Copy)]
@@ -276,6 +350,7 @@ struct CommandBuffer16 {
 struct ThisCommand<C> {
     command_buffer: C,
     dir: DataDirection,
+    check_condition: bool,
     data_buffer: VecBufferWrapper,
 }
 
@@ -283,7 +358,7 @@ impl<C: Copy> Command for ThisCommand<C> {
     type CommandBuffer = C;
     type DataBuffer = AnyType;
     type DataBufferWrapper = VecBufferWrapper;
-    type ReturnType = crate::Result<Option<Vec<u8>>>;
+    type ReturnType = crate::Result<(Option<Vec<u8>>, Option<AtaTaskFileResult>)>;
 
     fn direction(&self) -> DataDirection {
         self.dir
@@ -301,15 +376,32 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
-        result.check_common_error()?;
 
-        match self.dir {
-            DataDirection::ToDevice => Ok(None),
-            DataDirection::FromDevice => Ok(Some(std::mem::take(result.data).0)),
-            _ => unreachable!(),
+        let task_file = if self.check_condition {
+            AtaTaskFileResult::from_sense(result.sense_buffer())
+        } else {
+            None
+        };
+
+        // with CK_COND, a successful command ends in RECOVERED ERROR carrying the registers
+        match result.classify() {
+            CommandOutcome::Recovered(_) if task_file.is_some() => {}
+            _ => result.check_common_error()?,
         }
+
+        let data = match self.dir {
+            DataDirection::ToDevice => None,
+            DataDirection::FromDevice => Some(result.into_data()),
+            _ => unreachable!(),
+        };
+
+        Ok((data, task_file))
     }
 }
 
@@ -335,4 +427,100 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer16))
         );
     }
+
+    #[test]
+    fn cdb_12_test() {
+        let scsi = Scsi::test_instance();
+        scsi.ata_passthru()
+            .command(SatDirection::ToDevice, AtaProtocol::PioDataOut, 0x30)
+            .features(0xAB)
+            .lba(0x12_3456)
+            .device(0xE0)
+            .parameter(&[0; 1024])
+            .issue_12()
+            .unwrap();
+
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [0xA1, 0x0A, 0x06, 0xAB, 0x02, 0x56, 0x34, 0x12, 0xE0, 0x30, 0x00, 0x00],
+            "ata pass-through(12)"
+        );
+    }
+
+    #[test]
+    fn cdb_16_test() {
+        let scsi = Scsi::test_instance();
+        scsi.ata_passthru()
+            .command(SatDirection::FromDevice, AtaProtocol::PioDataIn, 0xEC)
+            .features(0xABCD)
+            .lba(0x12_3456)
+            .device(0xE0)
+            .count(512)
+            .issue_16()
+            .unwrap();
+
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [
+                0x85, 0x08, 0x0E, 0xAB, 0xCD, 0x00, 0x01, 0x00, 0x56, 0x00, 0x34, 0x00, 0x12, 0xE0,
+                0xEC, 0x00
+            ],
+            "ata pass-through(16)"
+        );
+    }
+
+    fn ata_return_sense() -> Vec<u8> {
+        vec![
+            0x72, 0x01, 0x00, 0x1D, 0x00, 0x00, 0x00,
+            0x0E, // recovered error, ata pass through information available
+            0x09, 0x0C, 0x01, 0x04, 0x00, 0x01, 0xCC, 0x56, 0xBB, 0x34, 0xAA, 0x12, 0xE0, 0x51,
+        ]
+    }
+
+    #[test]
+    fn task_file_test() {
+        let mut raw = [0; crate::command::sense::MAX_SENSE_BUFFER_LENGTH];
+        let sense = ata_return_sense();
+        raw[..sense.len()].copy_from_slice(&sense);
+
+        assert_eq!(
+            AtaTaskFileResult::from_sense(&SenseData::parse(&raw, sense.len())),
+            Some(AtaTaskFileResult {
+                extend: true,
+                error: 0x04,
+                count: 0x0001,
+                lba: 0xAABB_CC12_3456,
+                device: 0xE0,
+                status: 0x51,
+            }),
+            "ata status return"
+        );
+    }
+
+    #[test]
+    fn check_condition_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&ata_return_sense());
+
+        let (data, task_file) = scsi
+            .ata_passthru()
+            .command(SatDirection::ToDevice, AtaProtocol::NonData, 0xE5)
+            .check_condition(true)
+            .issue_16_with_task_file()
+            .unwrap();
+
+        assert_eq!(scsi.mock().issued()[0].cdb[2] & 0x20, 0x20, "ck_cond");
+        assert!(data.is_none(), "no data");
+        assert_eq!(task_file.map(|t| t.status), Some(0x51), "status register");
+
+        scsi.mock().push_sense(&ata_return_sense());
+        let result = scsi
+            .ata_passthru()
+            .command(SatDirection::ToDevice, AtaProtocol::NonData, 0xE5)
+            .issue_16();
+        assert!(
+            matches!(result, Err(crate::Error::CheckCondition(_))),
+            "without check_condition"
+        );
+    }
 }
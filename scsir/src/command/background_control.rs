@@ -18,7 +18,8 @@ impl<'a> BackgroundControlCommand<'a> {
             background_operation_control: 0,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use crate::{
+    command::sense::SenseData,
+    data_wrapper::{AnyType, VecBufferWrapper},
+    result_data::{CommandOutcome, ResultData, Status},
+    Command, DataDirection, Scsi,
+};
+
+/// Completion of Scsi::execute, a CHECK CONDITION is reported here rather than as an error.
+#[derive(Clone, Debug)]
+pub struct ExecOutcome {
+    pub status: Status,
+    pub transferred_length: usize,
+    pub sense: SenseData,
+}
+
+impl Scsi {
+    /// Sends cdb as is, transferring data in the given direction. Only a failed ioctl or
+    /// a transport error is returned as an error, the SCSI status and sense data are
+    /// left to the caller. Data read from the device is copied back into data.
+    ///
+    /// The cdb must be 6, 10, 12, 16 or 32 bytes long, and at most 16 on Windows.
+    pub fn execute(
+        &self,
+        cdb: &[u8],
+        direction: DataDirection,
+        data: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> crate::Result<ExecOutcome> {
+        // SCSI_PASS_THROUGH_DIRECT has room for 16 cdb bytes only, issue would panic
+        #[cfg(target_os = "windows")]
+        if cdb.len() > 16 {
+            return Err(crate::Error::BadArgument(format!(
+                "cdb length should be at most 16 on Windows, but {} was provided.",
+                cdb.len()
+            )));
+        }
+
+        match cdb.len() {
+            6 => self.execute_sized::<6>(cdb, direction, data, timeout),
+            10 => self.execute_sized::<10>(cdb, direction, data, timeout),
+            12 => self.execute_sized::<12>(cdb, direction, data, timeout),
+            16 => self.execute_sized::<16>(cdb, direction, data, timeout),
+            32 => self.execute_sized::<32>(cdb, direction, data, timeout),
+            length => Err(crate::Error::BadArgument(format!(
+                "cdb length should be 6, 10, 12, 16 or 32, but {} was provided.",
+                length
+            ))),
+        }
+    }
+
+    fn execute_sized<const N: usize>(
+        &self,
+        cdb: &[u8],
+        direction: DataDirection,
+        data: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> crate::Result<ExecOutcome> {
+        let data_buffer = match direction {
+            DataDirection::None => vec![],
+            _ => data.to_vec(),
+        };
+
+        let (outcome, data_in) = self.issue(&ThisCommand::<N> {
+            command_buffer: cdb.try_into().unwrap(),
+            direction,
+            data_buffer: data_buffer.into(),
+            timeout,
+        })?;
+
+        if matches!(
+            direction,
+            DataDirection::FromDevice | DataDirection::ToFromDevice
+        ) {
+            data.copy_from_slice(&data_in);
+        }
+
+        Ok(outcome)
+    }
+}
+
+struct ThisCommand<const N: usize> {
+    command_buffer: [u8; N],
+    direction: DataDirection,
+    data_buffer: VecBufferWrapper,
+    timeout: Option<Duration>,
+}
+
+impl<const N: usize> Command for ThisCommand<N> {
+    type CommandBuffer = [u8; N];
+
+    type DataBuffer = AnyType;
+
+    type DataBufferWrapper = VecBufferWrapper;
+
+    type ReturnType = crate::Result<(ExecOutcome, Vec<u8>)>;
+
+    fn direction(&self) -> DataDirection {
+        self.direction
+    }
+
+    fn command(&self) -> Self::CommandBuffer {
+        self.command_buffer
+    }
+
+    fn data(&self) -> Self::DataBufferWrapper {
+        self.data_buffer.clone()
+    }
+
+    fn data_size(&self) -> u32 {
+        self.data_buffer.len() as u32
+    }
+
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+        result.check_ioctl_error()?;
+
+        if let CommandOutcome::TransportError(_) = result.classify() {
+            result.check_common_error()?;
+        }
+
+        Ok((
+            ExecOutcome {
+                status: result.status,
+                transferred_length: result.transfered_data_length(),
+                sense: result.sense_buffer().clone(),
+            },
+            result.data().to_vec(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::sense::SenseKey;
+
+    #[test]
+    fn good_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x00, 0x06, 0x02, 0x1F]);
+
+        let mut data = [0xFF; 8];
+        let outcome = scsi
+            .execute(
+                &[0x12, 0x00, 0x00, 0x00, 0x08, 0x00],
+                DataDirection::FromDevice,
+                &mut data,
+                Some(Duration::from_secs(5)),
+            )
+            .unwrap();
+
+        assert!(matches!(outcome.status, Status::Good), "status");
+        assert_eq!(outcome.transferred_length, 5, "transferred length");
+        assert!(matches!(outcome.sense, SenseData::None), "sense");
+        assert_eq!(
+            data,
+            [0x00, 0x00, 0x06, 0x02, 0x1F, 0xFF, 0xFF, 0xFF],
+            "data"
+        );
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [0x12, 0x00, 0x00, 0x00, 0x08, 0x00],
+            "cdb"
+        );
+    }
+
+    #[test]
+    fn check_condition_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&[
+            0x70, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+
+        let outcome = scsi
+            .execute(&[0xFF; 10], DataDirection::None, &mut [], None)
+            .unwrap();
+
+        assert!(matches!(outcome.status, Status::CheckCondition), "status");
+        assert!(
+            matches!(outcome.sense.sense_key(), Some(SenseKey::IllegalRequest)),
+            "sense key"
+        );
+    }
+
+    #[test]
+    fn cdb_length_test() {
+        let scsi = Scsi::test_instance();
+
+        assert!(
+            scsi.execute(&[0; 7], DataDirection::None, &mut [], None)
+                .is_err(),
+            "7 byte cdb"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
+}
@@ -1,11 +1,17 @@
 #![allow(dead_code)]
 
-use std::mem::size_of_val;
+use std::{
+    mem::size_of_val,
+    time::{Duration, Instant},
+};
 
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{
+        bitfield_bound_check,
+        shortcut::mode::{build_block_descriptor, DescriptorType, HeaderType},
+    },
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -61,7 +67,9 @@ impl<'a> FormatUnitCommand<'a> {
             format_protection_information: 0,
             defect_list_format: 0,
             fast_format: 0,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             header_buffer: LongParameterListHeader::new(),
             initialization_pattern_descriptor_header: InitializationPatternDescriptorHeader::new(),
             initialization_pattern: vec![],
@@ -590,8 +598,66 @@ impl Scsi {
     pub fn format_unit(&self) -> FormatUnitCommand<'_> {
         FormatUnitCommand::new(self)
     }
+
+    /// Changes the logical block size by sending a block descriptor with MODE SELECT(10),
+    /// then FORMAT UNIT with IMMED, and polls until the unit is ready again.
+    /// progress receives the progress indication reported while formatting, the completed
+    /// fraction of 65536. Once timeout has passed without the unit becoming ready, Other is
+    /// returned while the format carries on in the device. All data on the medium is lost,
+    /// so confirm must be true.
+    pub fn reformat_block_size<F: FnMut(u16)>(
+        &self,
+        new_block_size: u32,
+        confirm: bool,
+        timeout: Duration,
+        mut progress: F,
+    ) -> crate::Result<()> {
+        if !confirm {
+            return Err(crate::Error::BadArgument(
+                "reformat_block_size erases the whole medium and has to be confirmed".to_owned(),
+            ));
+        }
+
+        let parameter =
+            build_block_descriptor(HeaderType::Long, DescriptorType::Short, 0, new_block_size)?;
+        self.mode_select()
+            .page_format(true)
+            .parameter(&parameter)
+            .issue_10()?;
+
+        let mut command = self.format_unit();
+        command
+            .format_data(true)
+            .parameter()
+            .short_parameter_list_header()
+            .format_options_valid(true)
+            .immediate(true)
+            .done()?
+            .done()?
+            .issue()?;
+
+        let deadline = Instant::now() + timeout;
+        while !self.is_ready()? {
+            if Instant::now() >= deadline {
+                return Err(crate::Error::Other(
+                    "format still in progress after the timeout.".to_owned(),
+                ));
+            }
+
+            if let Some(value) = self.request_sense().issue()?.progress() {
+                progress(value);
+            }
+            std::thread::sleep(FORMAT_POLL_INTERVAL);
+        }
+
+        self.set_cached_block_size(new_block_size);
+
+        Ok(())
+    }
 }
 
+const FORMAT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 const OPERATION_CODE: u8 = 0x04;
 
 #[bitfield]
@@ -745,6 +811,10 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -846,4 +916,67 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn reformat_block_size_test() {
+        let scsi = Scsi::test_instance();
+        assert!(
+            scsi.reformat_block_size(4096, false, Duration::from_secs(60), |_| {})
+                .is_err(),
+            "unconfirmed"
+        );
+
+        let not_ready = [
+            0x70, 0, 0x02, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x04, 0x04, 0, 0, 0, 0,
+        ];
+        let mut format_in_progress = not_ready;
+        format_in_progress[15..18].copy_from_slice(&[0x80, 0x40, 0x00]);
+
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&not_ready);
+        scsi.mock().push_data(&format_in_progress);
+
+        let mut reported = vec![];
+        scsi.reformat_block_size(4096, true, Duration::from_secs(60), |p| reported.push(p))
+            .unwrap();
+
+        assert_eq!(reported, [0x4000], "progress");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 5, "issued commands");
+        assert_eq!(issued[0].cdb[0], 0x55, "mode select(10)");
+        assert_eq!(issued[0].data_out[7], 8, "block descriptor length");
+        assert_eq!(
+            issued[0].data_out[13..16],
+            [0x00, 0x10, 0x00],
+            "block length"
+        );
+        assert_eq!(issued[1].cdb[0], 0x04, "format unit");
+        assert_eq!(issued[1].data_out[1], 0b1000_0010, "FOV and IMMED");
+        assert_eq!(issued[2].cdb[0], 0x00, "test unit ready");
+        assert_eq!(issued[3].cdb[0], 0x03, "request sense");
+        assert_eq!(issued[4].cdb[0], 0x00, "test unit ready");
+        assert_eq!(scsi.block_size().unwrap(), 4096, "cached block size");
+    }
+
+    #[test]
+    fn reformat_block_size_timeout_test() {
+        let not_ready = [
+            0x70, 0, 0x02, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x04, 0x04, 0, 0, 0, 0,
+        ];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&not_ready);
+
+        let mut reported = vec![];
+        let result = scsi.reformat_block_size(4096, true, Duration::ZERO, |p| reported.push(p));
+
+        assert!(matches!(result, Err(crate::Error::Other(_))), "timed out");
+        assert!(reported.is_empty(), "no progress");
+        assert_eq!(scsi.mock().issued().len(), 3, "polled once");
+        assert_eq!(scsi.cached_block_size(), None, "block size not cached");
+    }
 }
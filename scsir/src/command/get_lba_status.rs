@@ -5,6 +5,7 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    command::bitfield_bound_check,
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -15,6 +16,8 @@ pub struct GetLbaStatusCommand<'a> {
     interface: &'a Scsi,
     command_buffer: CommandBuffer,
     descriptor_length: u32,
+    report_type: u8,
+    starting_element: u32,
 }
 
 #[derive(Debug)]
@@ -31,6 +34,7 @@ pub struct LbaStatusDescriptor {
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ProvisioningStatus {
     MappedOrUnknown,
     Deallocated,
@@ -43,9 +47,12 @@ impl<'a> GetLbaStatusCommand<'a> {
         Self {
             interface,
             descriptor_length: 0,
+            report_type: 0,
+            starting_element: 0,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -66,7 +73,59 @@ impl<'a> GetLbaStatusCommand<'a> {
         self
     }
 
+    // report_type must be less than 0x08, only used by get lba status(32)
+    pub fn report_type(&mut self, value: u8) -> &mut Self {
+        self.report_type = value;
+        self
+    }
+
+    // only used by get lba status(32)
+    pub fn starting_element(&mut self, value: u32) -> &mut Self {
+        self.starting_element = value;
+        self
+    }
+
     pub fn issue(&mut self) -> crate::Result<CommandResult> {
+        self.error_check()?;
+
+        let temp = ThisCommand {
+            command_buffer: self
+                .command_buffer
+                .with_allocation_length(self.allocation_length()),
+            max_descriptor_length: self.descriptor_length,
+        };
+
+        self.interface.issue(&temp)
+    }
+
+    pub fn issue_32(&mut self) -> crate::Result<CommandResult> {
+        self.error_check()?;
+        bitfield_bound_check!(self.report_type, 3, "report type")?;
+
+        let temp = ThisCommand {
+            command_buffer: CommandBuffer32::new()
+                .with_operation_code(OPERATION_CODE_32)
+                .with_control(self.command_buffer.control())
+                .with_additional_cdb_length(0x18)
+                .with_service_action(SERVICE_ACTION_32)
+                .with_report_type(self.report_type)
+                .with_starting_logical_block_address(
+                    self.command_buffer.starting_logical_block_address(),
+                )
+                .with_allocation_length(self.allocation_length())
+                .with_starting_element(self.starting_element),
+            max_descriptor_length: self.descriptor_length,
+        };
+
+        self.interface.issue(&temp)
+    }
+
+    fn allocation_length(&self) -> u32 {
+        size_of::<ParameterHeader>() as u32
+            + self.descriptor_length * size_of::<Descriptor>() as u32
+    }
+
+    fn error_check(&self) -> crate::Result<()> {
         const MAX_DESCRIPTOR_LENGTH: usize =
             (u32::MAX as usize - size_of::<ParameterHeader>()) / size_of::<Descriptor>();
         if self.descriptor_length > MAX_DESCRIPTOR_LENGTH as u32 {
@@ -78,15 +137,7 @@ impl<'a> GetLbaStatusCommand<'a> {
                         self.descriptor_length)));
         }
 
-        let temp = ThisCommand {
-            command_buffer: self.command_buffer.with_allocation_length(
-                size_of::<ParameterHeader>() as u32
-                    + self.descriptor_length * size_of::<Descriptor>() as u32,
-            ),
-            max_descriptor_length: self.descriptor_length,
-        };
-
-        self.interface.issue(&temp)
+        Ok(())
     }
 }
 
@@ -98,6 +149,8 @@ impl Scsi {
 
 const OPERATION_CODE: u8 = 0x9E;
 const SERVICE_ACTION: u8 = 0x12;
+const OPERATION_CODE_32: u8 = 0x7F;
+const SERVICE_ACTION_32: u16 = 0x0012;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -111,6 +164,24 @@ struct CommandBuffer {
     control: B8,
 }
 
+#[bitfield]
+#[derive(Clone, Copy, Debug)]
+struct CommandBuffer32 {
+    operation_code: B8,
+    control: B8,
+    reserved_0: B32,
+    reserved_1: B8,
+    additional_cdb_length: B8,
+    service_action: B16,
+    reserved_2: B8,
+    reserved_3: B5,
+    report_type: B3,
+    starting_logical_block_address: B64,
+    allocation_length: B32,
+    starting_element: B32,
+    reserved_4: B32,
+}
+
 #[bitfield]
 #[derive(Clone, Copy)]
 struct ParameterHeader {
@@ -127,13 +198,13 @@ struct Descriptor {
     reserved: B24,
 }
 
-struct ThisCommand {
-    command_buffer: CommandBuffer,
+struct ThisCommand<C> {
+    command_buffer: C,
     max_descriptor_length: u32,
 }
 
-impl Command for ThisCommand {
-    type CommandBuffer = CommandBuffer;
+impl<C: Copy> Command for ThisCommand<C> {
+    type CommandBuffer = C;
 
     type DataBuffer = AnyType;
 
@@ -158,6 +229,10 @@ impl Command for ThisCommand {
             + size_of::<ParameterHeader>() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -196,6 +271,7 @@ mod tests {
     use std::mem::size_of;
 
     const COMMAND_LENGTH: usize = 16;
+    const COMMAND_LENGTH_32: usize = 32;
     const PARAMETER_HEADER_LENGTH: usize = 8;
     const DESCRIPTOR_LENGTH: usize = 16;
 
@@ -207,6 +283,12 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
 
+        assert_eq!(
+            size_of::<CommandBuffer32>(),
+            COMMAND_LENGTH_32,
+            concat!("Size of: ", stringify!(CommandBuffer32))
+        );
+
         assert_eq!(
             size_of::<ParameterHeader>(),
             PARAMETER_HEADER_LENGTH,
@@ -219,4 +301,71 @@ mod tests {
             concat!("Size of: ", stringify!(Descriptor))
         );
     }
+
+    #[test]
+    fn issue_32_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[
+            0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x80, 0x02, 0x00, 0x00, 0x00,
+        ]);
+
+        let result = scsi
+            .get_lba_status()
+            .starting_logical_block_address(0x1000)
+            .report_type(0x02)
+            .starting_element(3)
+            .descriptor_length(2)
+            .issue_32()
+            .unwrap();
+
+        let cdb = &scsi.mock().issued()[0].cdb;
+        assert_eq!(cdb.len(), COMMAND_LENGTH_32, "cdb length");
+        assert_eq!(&cdb[..2], &[0x7F, 0x00], "operation code and control");
+        assert_eq!(&cdb[7..10], &[0x18, 0x00, 0x12], "service action");
+        assert_eq!(cdb[11], 0x02, "report type");
+        assert_eq!(&cdb[12..20], &0x1000u64.to_be_bytes(), "starting lba");
+        assert_eq!(&cdb[20..24], &[0, 0, 0, 40], "allocation length");
+        assert_eq!(&cdb[24..28], &[0, 0, 0, 3], "starting element");
+
+        assert_eq!(result.lba_status_descriptors.len(), 1, "descriptors");
+        let descriptor = &result.lba_status_descriptors[0];
+        assert_eq!(descriptor.logical_block_address, 0x1000, "lba");
+        assert_eq!(descriptor.number_of_logical_blocks, 0x80, "blocks");
+        assert!(
+            matches!(descriptor.provisioning_status, ProvisioningStatus::Anchored),
+            "provisioning status"
+        );
+    }
+
+    #[test]
+    fn unknown_provisioning_status_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[
+            0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x80, 0x07, 0x00, 0x00, 0x00,
+        ]);
+
+        let result = scsi.get_lba_status().descriptor_length(1).issue().unwrap();
+
+        assert!(
+            matches!(
+                result.lba_status_descriptors[0].provisioning_status,
+                ProvisioningStatus::Other(0x07)
+            ),
+            "provisioning status"
+        );
+    }
+
+    #[test]
+    fn report_type_bound_test() {
+        let scsi = Scsi::test_instance();
+        let result = scsi.get_lba_status().report_type(0x08).issue_32();
+
+        assert!(
+            matches!(result, Err(crate::Error::ArgumentOutOfBounds(_))),
+            "report type"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
 }
@@ -31,7 +31,8 @@ impl<'a> GetStreamStatusCommand<'a> {
             descriptor_length: 0,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -145,6 +146,10 @@ impl Command for ThisCommand {
             + size_of::<ParameterHeader>() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
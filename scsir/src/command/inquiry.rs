@@ -8,6 +8,7 @@ use std::{
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    command::{check_allocation_length, clear_reserved_fields, reserved_fields, SizedDataIn},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -19,18 +20,38 @@ pub struct InquiryCommand<'a> {
     command_buffer: CommandBuffer,
 }
 
+/// Command support data returned with the obsolete CmdDt bit, the predecessor of
+/// REPORT SUPPORTED OPERATION CODES with one command.
+#[derive(Clone, Debug)]
+pub struct CommandSupportData {
+    pub peripheral_qualifier: u8,
+    pub peripheral_device_type: u8,
+    // 0b001: not supported, 0b011: supported by standard, 0b101: vendor specific
+    pub support: u8,
+    pub version: u8,
+    pub cdb_usage_data: Vec<u8>,
+}
+
 impl<'a> InquiryCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
         }
     }
 
+    pub fn control(&mut self, value: u8) -> &mut Self {
+        self.command_buffer.set_control(value);
+        self
+    }
+
     pub fn page_code(&mut self, value: Option<u8>) -> &mut Self {
         self.command_buffer.set_page_code(value.unwrap_or(0));
         self.command_buffer
             .set_enable_vital_product_data(value.is_some() as u8);
+        self.command_buffer.set_obsolete_command_support_data(0);
         self
     }
 
@@ -39,13 +60,78 @@ impl<'a> InquiryCommand<'a> {
         self
     }
 
+    // runs the checks of issue and returns the cdb it would send, without sending anything
+    pub fn build_cdb(&self) -> crate::Result<[u8; 6]> {
+        check_allocation_length(self.command_buffer.allocation_length().into())?;
+
+        Ok(clear_reserved_fields(self.command_buffer).into_bytes())
+    }
+
     pub fn issue(&mut self) -> crate::Result<Vec<u8>> {
+        check_allocation_length(self.command_buffer.allocation_length().into())?;
+
         let result: FlexibleStruct<(), u8> =
             self.issue_flex(self.command_buffer.allocation_length().into())?;
 
         unsafe { Ok(result.elements_as_slice().to_vec()) }
     }
 
+    // issues a vpd page, re-reading it if the allocation length was too short for the page
+    pub fn issue_vpd(&mut self, page_code: u8) -> crate::Result<Vec<u8>> {
+        self.page_code(Some(page_code));
+        if self.command_buffer.allocation_length() < VPD_PAGE_HEADER_LENGTH as u16 {
+            self.allocation_length(DEFAULT_VPD_ALLOCATION_LENGTH);
+        }
+
+        let mut bytes = self.issue()?;
+        if bytes.len() < VPD_PAGE_HEADER_LENGTH {
+            return Ok(bytes);
+        }
+
+        let page_length =
+            u16::from_be_bytes([bytes[2], bytes[3]]) as usize + VPD_PAGE_HEADER_LENGTH;
+        if page_length > bytes.len() {
+            self.allocation_length(page_length.try_into().unwrap_or(u16::MAX));
+            bytes = self.issue()?;
+        }
+
+        bytes.truncate(page_length);
+        Ok(bytes)
+    }
+
+    // sets CMDDT with the operation code in the page code field. CmdDt is obsolete since
+    // SPC-3 and newer devices reject it, prefer Scsi::supports_operation and
+    // REPORT SUPPORTED OPERATION CODES, this is for older devices that only know CmdDt
+    pub fn issue_command_support(
+        &mut self,
+        operation_code: u8,
+    ) -> crate::Result<CommandSupportData> {
+        self.command_buffer = self
+            .command_buffer
+            .with_enable_vital_product_data(0)
+            .with_obsolete_command_support_data(1)
+            .with_page_code(operation_code)
+            .with_allocation_length(COMMAND_SUPPORT_ALLOCATION_LENGTH);
+
+        let bytes = self.issue()?;
+        if bytes.len() < COMMAND_SUPPORT_HEADER_LENGTH {
+            return Err(crate::Error::BadArgument(
+                "short response, the command support data header is incomplete.".to_owned(),
+            ));
+        }
+
+        let cdb_size = bytes[COMMAND_SUPPORT_HEADER_LENGTH - 1] as usize;
+        let end = usize::min(COMMAND_SUPPORT_HEADER_LENGTH + cdb_size, bytes.len());
+
+        Ok(CommandSupportData {
+            peripheral_qualifier: bytes[0] >> 5,
+            peripheral_device_type: bytes[0] & 0x1F,
+            support: bytes[1] & 0x07,
+            version: bytes[2],
+            cdb_usage_data: bytes[COMMAND_SUPPORT_HEADER_LENGTH..end].to_vec(),
+        })
+    }
+
     pub fn issue_generic<Body: Copy, Element: Copy>(
         &mut self,
         element_length: usize,
@@ -82,13 +168,53 @@ impl<'a> InquiryCommand<'a> {
     }
 }
 
+impl SizedDataIn for InquiryCommand<'_> {
+    fn probe_length(&self) -> usize {
+        if self.command_buffer.enable_vital_product_data() == 0 {
+            STANDARD_HEADER_LENGTH
+        } else {
+            VPD_PAGE_HEADER_LENGTH
+        }
+    }
+
+    fn response_length(&self, header: &[u8]) -> usize {
+        if self.command_buffer.enable_vital_product_data() == 0 {
+            header[4] as usize + STANDARD_HEADER_LENGTH
+        } else {
+            u16::from_be_bytes([header[2], header[3]]) as usize + VPD_PAGE_HEADER_LENGTH
+        }
+    }
+
+    fn issue_with_allocation_length(&mut self, allocation_length: usize) -> crate::Result<Vec<u8>> {
+        self.allocation_length(allocation_length.try_into().unwrap_or(u16::MAX))
+            .issue()
+    }
+}
+
 impl Scsi {
     pub fn inquiry(&self) -> InquiryCommand<'_> {
         InquiryCommand::new(self)
     }
 }
 
+// builds the cdb alone, without needing a Scsi
+pub fn build_cdb(page_code: Option<u8>, allocation_length: u16) -> [u8; 6] {
+    CommandBuffer::new()
+        .with_operation_code(OPERATION_CODE)
+        .with_enable_vital_product_data(page_code.is_some() as u8)
+        .with_page_code(page_code.unwrap_or(0))
+        .with_allocation_length(allocation_length)
+        .into_bytes()
+}
+
 const OPERATION_CODE: u8 = 0x12;
+const VPD_PAGE_HEADER_LENGTH: usize = 4;
+// bytes up to and including ADDITIONAL LENGTH of the standard inquiry data
+const STANDARD_HEADER_LENGTH: usize = 5;
+const DEFAULT_VPD_ALLOCATION_LENGTH: u16 = 0xFF;
+// bytes up to and including CDB SIZE of the command support data
+const COMMAND_SUPPORT_HEADER_LENGTH: usize = 5;
+const COMMAND_SUPPORT_ALLOCATION_LENGTH: u16 = COMMAND_SUPPORT_HEADER_LENGTH as u16 + 0xFF;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -102,6 +228,9 @@ struct CommandBuffer {
     control: B8,
 }
 
+// CMDDT is obsolete but still set by issue_command_support, it is not cleared here
+reserved_fields!(CommandBuffer, reserved / with_reserved);
+
 struct ThisCommand<Body, Element> {
     command_buffer: CommandBuffer,
     element_length: usize,
@@ -123,7 +252,7 @@ impl<Body: Copy, Element: Copy> Command for ThisCommand<Body, Element> {
     }
 
     fn command(&self) -> Self::CommandBuffer {
-        self.command_buffer
+        clear_reserved_fields(self.command_buffer)
             .with_allocation_length(self.data_size().try_into().unwrap_or(u16::MAX))
     }
 
@@ -135,17 +264,28 @@ impl<Body: Copy, Element: Copy> Command for ThisCommand<Body, Element> {
         (size_of::<Body>() + self.element_length * size_of::<Element>()) as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(result.data.clone())
+        let length = result.transferred_data().len();
+        let mut data = result.data.clone();
+        data.truncate(
+            length.saturating_sub(size_of::<Body>()) / usize::max(size_of::<Element>(), 1),
+        );
+
+        Ok(data)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Control;
     use std::mem::size_of;
 
     const COMMAND_LENGTH: usize = 6;
@@ -158,4 +298,194 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn build_cdb_test() {
+        assert_eq!(
+            build_cdb(None, 96),
+            [0x12, 0x00, 0x00, 0x00, 0x60, 0x00],
+            "standard inquiry"
+        );
+
+        assert_eq!(
+            build_cdb(Some(0x80), 0x0100),
+            [0x12, 0x01, 0x80, 0x01, 0x00, 0x00],
+            "unit serial number page"
+        );
+    }
+
+    #[test]
+    fn issue_vpd_test() {
+        let scsi = Scsi::test_instance();
+        let mut page = vec![0x00, 0xC0, 0x00, 0x08];
+        page.extend(1..=8);
+        scsi.mock().push_data(&page[..6]);
+        scsi.mock().push_data(&page);
+
+        let bytes = scsi.inquiry().allocation_length(6).issue_vpd(0xC0).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "issue count");
+        assert_eq!(
+            issued[0].cdb,
+            [0x12, 0x01, 0xC0, 0x00, 0x06, 0x00],
+            "short allocation"
+        );
+        assert_eq!(
+            issued[1].cdb,
+            [0x12, 0x01, 0xC0, 0x00, 0x0C, 0x00],
+            "full allocation"
+        );
+        assert_eq!(bytes, page, "page bytes");
+    }
+
+    #[test]
+    fn issue_vpd_trim_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x80, 0x00, 0x02, b'A', b'B']);
+
+        let bytes = scsi.inquiry().issue_vpd(0x80).unwrap();
+
+        assert_eq!(scsi.mock().issued().len(), 1, "issue count");
+        assert_eq!(bytes, [0x00, 0x80, 0x00, 0x02, b'A', b'B'], "page bytes");
+    }
+
+    #[test]
+    fn transferred_length_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00; 36]);
+
+        let bytes = scsi.inquiry().allocation_length(96).issue().unwrap();
+
+        assert_eq!(bytes.len(), 36, "transferred length");
+    }
+
+    #[test]
+    fn issue_sized_test() {
+        let mut data = vec![0x00, 0x00, 0x06, 0x02, 0x1F];
+        data.resize(36, b' ');
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&data[..5]);
+        scsi.mock().push_data(&data);
+
+        let bytes = scsi.inquiry().issue_sized().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[3..5], [0x00, 0x05], "probe allocation length");
+        assert_eq!(issued[1].cdb[3..5], [0x00, 0x24], "full allocation length");
+        assert_eq!(bytes, data, "standard inquiry data");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x80, 0x00, 0x00]);
+
+        let bytes = scsi.inquiry().page_code(Some(0x80)).issue_sized().unwrap();
+
+        assert_eq!(
+            scsi.mock().issued().len(),
+            1,
+            "empty page needs no second read"
+        );
+        assert_eq!(bytes, [0x00, 0x80, 0x00, 0x00], "vpd page");
+    }
+
+    #[test]
+    fn zero_allocation_length_test() {
+        let scsi = Scsi::test_instance();
+
+        assert!(
+            matches!(scsi.inquiry().issue(), Err(crate::Error::BadArgument(_))),
+            "zero allocation length"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
+
+    #[test]
+    fn issue_command_support_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[
+            0x00, 0x03, 0x05, 0x00, 0x0A, 0x28, 0xFA, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F, 0xFF, 0xFF,
+            0x07,
+        ]);
+        scsi.mock().push_data(&[0x00, 0x01, 0x05, 0x00, 0x00]);
+
+        let mut command = scsi.inquiry();
+        let data = command.issue_command_support(0x28).unwrap();
+        assert_eq!(data.support, 0b011, "supported");
+        assert_eq!(data.version, 0x05, "version");
+        assert_eq!(
+            data.cdb_usage_data,
+            [0x28, 0xFA, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F, 0xFF, 0xFF, 0x07],
+            "cdb usage data"
+        );
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [0x12, 0x02, 0x28, 0x01, 0x04, 0x00],
+            "cmddt cdb"
+        );
+
+        let data = command.issue_command_support(0xC0).unwrap();
+        assert_eq!(data.support, 0b001, "not supported");
+        assert!(data.cdb_usage_data.is_empty(), "no cdb usage data");
+
+        command.page_code(Some(0x80)).allocation_length(0xFF);
+        scsi.mock().push_data(&[]);
+        command.issue().unwrap();
+        assert_eq!(
+            scsi.mock().issued()[2].cdb[1..3],
+            [0x01, 0x80],
+            "cmddt cleared"
+        );
+    }
+
+    #[test]
+    fn reserved_fields_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x80, 0x00, 0x00]);
+
+        scsi.inquiry().issue_vpd(0x80).unwrap();
+
+        assert_eq!(scsi.mock().issued()[0].cdb[1] & 0xFC, 0, "reserved");
+    }
+
+    #[test]
+    fn builder_build_cdb_test() {
+        let scsi = Scsi::test_instance();
+
+        assert_eq!(
+            scsi.inquiry()
+                .page_code(Some(0x80))
+                .allocation_length(0xFF)
+                .build_cdb()
+                .unwrap(),
+            [0x12, 0x01, 0x80, 0x00, 0xFF, 0x00],
+            "unit serial number page"
+        );
+        assert!(
+            scsi.inquiry().allocation_length(0).build_cdb().is_err(),
+            "zero allocation length"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
+
+    #[test]
+    fn control_test() {
+        let mut scsi = Scsi::test_instance();
+        scsi.set_default_control(*Control::new().naca(true));
+
+        assert_eq!(
+            scsi.inquiry().allocation_length(0xFF).build_cdb().unwrap()[5],
+            0x04,
+            "inherited"
+        );
+        assert_eq!(
+            scsi.inquiry()
+                .allocation_length(0xFF)
+                .control(0x00)
+                .build_cdb()
+                .unwrap()[5],
+            0x00,
+            "overridden"
+        );
+    }
 }
@@ -24,7 +24,9 @@ impl<'a> LogSelectCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             page_control: 0,
             page_code: 0,
             data_buffer: vec![],
@@ -70,6 +72,11 @@ impl<'a> LogSelectCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.page_control, 2, "page control")?;
         bitfield_bound_check!(self.page_code, 6, "page code")?;
@@ -151,6 +158,10 @@ impl Command for ThisCommand {
         self.parameter.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -8,9 +8,10 @@ use std::{
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, check_allocation_length, SizedDataIn},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
+    shortcut::log::PageHeader,
     Command, DataDirection, Scsi,
 };
 
@@ -26,7 +27,9 @@ impl<'a> LogSenseCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             page_control: 0,
             page_code: 0,
         }
@@ -49,6 +52,18 @@ impl<'a> LogSenseCommand<'a> {
         self
     }
 
+    // page_control must be less than 0x04
+    pub fn page_control_checked(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 2, "page control")?;
+        Ok(self.page_control(value))
+    }
+
+    // page_code must be less than 0x40
+    pub fn page_code_checked(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 6, "page code")?;
+        Ok(self.page_code(value))
+    }
+
     pub fn subpage_code(&mut self, value: u8) -> &mut Self {
         self.command_buffer.set_subpage_code(value);
         self
@@ -70,12 +85,50 @@ impl<'a> LogSenseCommand<'a> {
     }
 
     pub fn issue(&mut self) -> crate::Result<Vec<u8>> {
+        check_allocation_length(self.command_buffer.allocation_length().into())?;
+
         let result: FlexibleStruct<(), u8> =
             self.issue_flex(self.command_buffer.allocation_length() as usize)?;
 
         unsafe { Ok(result.elements_as_slice().to_vec()) }
     }
 
+    // reads the page header first, then the whole page, ignoring allocation_length
+    pub fn issue_full(&mut self) -> crate::Result<Vec<u8>> {
+        self.issue_sized()
+    }
+
+    // reads the whole page like issue_full, then checks that the returned page header is
+    // the one asked for: the page code, SPF set exactly when subpage_code is not 0, and the
+    // subpage code. the CDB has no SPF bit, the subpage code field alone selects the subpage
+    pub fn issue_page(&mut self, page_code: u8, subpage_code: u8) -> crate::Result<Vec<u8>> {
+        self.page_code_checked(page_code)?
+            .subpage_code(subpage_code);
+
+        let bytes = self.issue_full()?;
+        let header = PageHeader::from_slice(&bytes);
+        let returned_subpage_code = if header.subpage_format() != 0 {
+            header.subpage_code()
+        } else {
+            0
+        };
+
+        if header.page_code() != page_code
+            || (header.subpage_format() != 0) != (subpage_code != 0)
+            || returned_subpage_code != subpage_code
+        {
+            return Err(crate::Error::BadArgument(format!(
+                "requested log page {:#04X}/{:#04X}, but the device returned page {:#04X}/{:#04X}.",
+                page_code,
+                subpage_code,
+                header.page_code(),
+                returned_subpage_code
+            )));
+        }
+
+        Ok(bytes)
+    }
+
     pub fn issue_generic<Body: Copy, Element: Copy>(
         &mut self,
         element_length: usize,
@@ -88,6 +141,25 @@ impl<'a> LogSenseCommand<'a> {
         ))
     }
 
+    /// Like issue_generic, without MaybeUninit. The buffer is zeroed before the device
+    /// overwrites it, and only the elements the device transferred completely are returned.
+    /// A response shorter than Body leaves its tail zeroed.
+    ///
+    /// # Safety
+    ///
+    /// Body and Element are filled with whatever bytes the device returns, so every byte
+    /// pattern, including all zeroes, must be a valid value of both, as it is for
+    /// #[bitfield] structs and integer arrays. Types with references, enums, bools or
+    /// other invalid bit patterns must not be used.
+    pub unsafe fn issue_typed<Body: Copy, Element: Copy>(
+        &mut self,
+        element_length: usize,
+    ) -> crate::Result<(Body, Vec<Element>)> {
+        let result: FlexibleStruct<Body, Element> = self.issue_flex(element_length)?;
+
+        Ok((result.get_body(), result.iter_clone().collect()))
+    }
+
     pub(crate) fn issue_flex<B: Copy, E: Copy>(
         &mut self,
         element_length: usize,
@@ -118,6 +190,23 @@ impl<'a> LogSenseCommand<'a> {
     }
 }
 
+impl SizedDataIn for LogSenseCommand<'_> {
+    fn probe_length(&self) -> usize {
+        PAGE_HEADER_LENGTH
+    }
+
+    fn response_length(&self, header: &[u8]) -> usize {
+        u16::from_be_bytes([header[2], header[3]]) as usize + PAGE_HEADER_LENGTH
+    }
+
+    fn issue_with_allocation_length(&mut self, allocation_length: usize) -> crate::Result<Vec<u8>> {
+        let result: FlexibleStruct<(), u8> =
+            self.issue_flex(usize::min(allocation_length, u16::MAX as usize))?;
+
+        unsafe { Ok(result.elements_as_slice().to_vec()) }
+    }
+}
+
 impl Scsi {
     pub fn log_sense(&self) -> LogSenseCommand<'_> {
         LogSenseCommand::new(self)
@@ -125,6 +214,7 @@ impl Scsi {
 }
 
 const OPERATION_CODE: u8 = 0x4D;
+const PAGE_HEADER_LENGTH: usize = 4;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -175,11 +265,21 @@ impl<Body: Copy, Element: Copy> Command for ThisCommand<Body, Element> {
         (size_of::<Body>() + self.element_length * size_of::<Element>()) as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(result.data.clone())
+        let length = result.transferred_data().len();
+        let mut data = result.data.clone();
+        data.truncate(
+            length.saturating_sub(size_of::<Body>()) / usize::max(size_of::<Element>(), 1),
+        );
+
+        Ok(data)
     }
 }
 
@@ -198,4 +298,150 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn checked_setter_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.log_sense();
+
+        assert!(command.page_control_checked(0x03).is_ok(), "page control");
+        assert!(command.page_control_checked(0x04).is_err(), "page control");
+        assert!(command.page_code_checked(0x3F).is_ok(), "page code");
+        assert!(command.page_code_checked(0x40).is_err(), "page code");
+    }
+
+    #[test]
+    fn issue_full_test() {
+        let scsi = Scsi::test_instance();
+        let page = [0x02, 0x00, 0x00, 0x04, 0x00, 0x00, 0x03, 0x00];
+        scsi.mock().push_data(&page[..4]);
+        scsi.mock().push_data(&page);
+
+        let bytes = scsi.log_sense().page_code(0x02).issue_full().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "issue count");
+        assert_eq!(
+            &issued[0].cdb[7..9],
+            &[0x00, 0x04],
+            "header allocation length"
+        );
+        assert_eq!(
+            &issued[1].cdb[7..9],
+            &[0x00, 0x08],
+            "page allocation length"
+        );
+        assert_eq!(bytes, page, "page bytes");
+    }
+
+    #[test]
+    fn issue_full_overflow_test() {
+        let scsi = Scsi::test_instance();
+        let mut page = vec![0; u16::MAX as usize];
+        page[..4].copy_from_slice(&[0x02, 0x00, 0xFF, 0xFF]);
+        scsi.mock().push_data(&page[..4]);
+        scsi.mock().push_data(&page);
+
+        let bytes = scsi.log_sense().page_code(0x02).issue_full().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            &issued[1].cdb[7..9],
+            &[0xFF, 0xFF],
+            "clamped allocation length"
+        );
+        assert_eq!(bytes.len(), u16::MAX as usize, "page bytes");
+    }
+
+    #[test]
+    fn issue_page_test() {
+        let scsi = Scsi::test_instance();
+        let page = [0x4D, 0x01, 0x00, 0x04, 0x00, 0x00, 0x03, 0x00];
+        scsi.mock().push_data(&page[..4]);
+        scsi.mock().push_data(&page);
+
+        let bytes = scsi.log_sense().issue_page(0x0D, 0x01).unwrap();
+        assert_eq!(bytes, page, "page bytes");
+        assert_eq!(
+            &scsi.mock().issued()[0].cdb[2..4],
+            &[0x0D, 0x01],
+            "page and subpage code"
+        );
+
+        let page = [0x0D, 0x00, 0x00, 0x00];
+        scsi.mock().push_data(&page);
+        assert!(
+            matches!(
+                scsi.log_sense().issue_page(0x0D, 0x01),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "subpage 0 returned for subpage 1"
+        );
+
+        let page = [0x4D, 0x01, 0x00, 0x00];
+        scsi.mock().push_data(&page);
+        assert!(
+            matches!(
+                scsi.log_sense().issue_page(0x0D, 0x00),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "subpage 1 returned for page 0"
+        );
+
+        let page = [0x2F, 0x00, 0x00, 0x00];
+        scsi.mock().push_data(&page);
+        assert!(
+            matches!(
+                scsi.log_sense().issue_page(0x0D, 0x00),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "different page code"
+        );
+
+        assert!(
+            scsi.log_sense().issue_page(0x40, 0x00).is_err(),
+            "page code out of bounds"
+        );
+    }
+
+    #[bitfield]
+    #[derive(Clone, Copy, Debug)]
+    struct Counter {
+        parameter_code: B16,
+        parameter_control_byte: B8,
+        parameter_length: B8,
+        value: B32,
+    }
+
+    #[test]
+    fn issue_typed_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[
+            0x02, 0x00, 0x00, 0x10, // page header
+            0x00, 0x01, 0x00, 0x04, 0x00, 0x00, 0x01, 0x00, // parameter 1
+            0x00, 0x02, 0x00, 0x04, 0x12, 0x34, 0x56, 0x78, // parameter 2
+            0xFF, 0xFF, // partial parameter
+        ]);
+
+        // both are #[bitfield] byte arrays, valid for any bytes
+        let (header, counters) = unsafe {
+            scsi.log_sense()
+                .page_code(0x02)
+                .issue_typed::<PageHeader, Counter>(8)
+        }
+        .unwrap();
+
+        assert_eq!(header.page_code(), 0x02, "page code");
+        assert_eq!(header.page_length(), 0x10, "page length");
+        assert_eq!(counters.len(), 2, "transferred elements only");
+        assert_eq!(counters[0].parameter_code(), 0x0001, "parameter code");
+        assert_eq!(counters[0].value(), 0x100, "value");
+        assert_eq!(counters[1].parameter_code(), 0x0002, "parameter code");
+        assert_eq!(counters[1].value(), 0x1234_5678, "value");
+        assert_eq!(
+            scsi.mock().issued()[0].cdb[7..9],
+            [0x00, 0x44],
+            "allocation length"
+        );
+    }
 }
@@ -1,5 +1,6 @@
 pub mod ata;
 pub mod background_control;
+pub mod execute;
 pub mod format_unit;
 pub mod get_lba_status;
 pub mod get_stream_status;
@@ -30,6 +31,8 @@ pub mod sense;
 pub mod set_identifying_information;
 pub mod set_timestamp;
 pub mod shortcut;
+mod sized_data_in;
+pub mod space;
 pub mod start_stop_unit;
 pub mod stream_control;
 pub mod synchronize_cache;
@@ -43,11 +46,21 @@ pub mod write_buffer;
 pub mod write_long;
 pub mod write_same;
 pub mod write_stream;
+pub mod xdwrite;
+pub mod xor_write;
+pub mod xpwrite;
 
-use std::{borrow::BorrowMut, mem::size_of};
+use std::{
+    borrow::BorrowMut,
+    mem::{size_of, size_of_val},
+    slice,
+    time::Duration,
+};
 
 use crate::{result_data::ResultData, DataDirection};
 
+pub use sized_data_in::SizedDataIn;
+
 pub trait Command {
     type CommandBuffer;
     type DataBuffer;
@@ -64,7 +77,74 @@ pub trait Command {
         size_of::<Self::DataBuffer>() as u32
     }
 
+    /// whether the command may go out as data-in with a data_size of 0, which transfers
+    /// nothing. Commands that always expect a response return false and reject a zero
+    /// allocation length with BadArgument before they are issued
+    fn allows_empty_data_in(&self) -> bool {
+        true
+    }
+
+    /// byte length behind the data wrapper, needed when DataBuffer doesn't describe it, e.g. a Vec.
+    /// None means size_of DataBuffer
+    fn data_length(&self, _data: &Self::DataBufferWrapper) -> Option<usize> {
+        None
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType;
+
+    /// overrides the timeout of the Scsi for this command
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// (first lba, number of blocks) written by this command, used by range locking
+    fn lba_range(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// the cdb exactly as it is sent to the device
+    fn cdb_bytes(&self) -> Vec<u8> {
+        let command_buffer = self.command();
+        unsafe {
+            slice::from_raw_parts(
+                &command_buffer as *const _ as *const u8,
+                size_of_val(&command_buffer),
+            )
+        }
+        .to_vec()
+    }
+}
+
+// data_size larger than the buffer lets the kernel access memory past it
+pub(crate) fn debug_check_data_size<T: Command>(command: &T, data: &T::DataBufferWrapper) {
+    let data_length = command
+        .data_length(data)
+        .unwrap_or(size_of::<T::DataBuffer>());
+
+    debug_assert!(
+        command.data_size() as usize <= data_length,
+        "data size {} exceeds the data buffer length {}",
+        command.data_size(),
+        data_length
+    );
+
+    debug_assert!(
+        command.data_size() != 0
+            || command.allows_empty_data_in()
+            || !matches!(command.direction(), DataDirection::FromDevice),
+        "data-in command issued with a zero allocation length"
+    );
+}
+
+// for commands where a zero allocation length is always a caller bug
+pub(crate) fn check_allocation_length(allocation_length: u32) -> crate::Result<()> {
+    if allocation_length == 0 {
+        return Err(crate::Error::BadArgument(
+            "zero allocation length, the device would return no data.".to_owned(),
+        ));
+    }
+
+    Ok(())
 }
 
 pub(crate) fn get_array<const N: usize>(bytes: &[u8]) -> ([u8; N], &[u8]) {
@@ -75,6 +155,20 @@ pub(crate) fn get_array<const N: usize>(bytes: &[u8]) -> ([u8; N], &[u8]) {
     (array, &bytes[min_len..])
 }
 
+// like get_array, but a short input is an error instead of being zero padded
+pub(crate) fn try_get_array<const N: usize>(bytes: &[u8]) -> crate::Result<([u8; N], &[u8])> {
+    if bytes.len() < N {
+        return Err(crate::Error::BadArgument(format!(
+            "short response, {} bytes were expected but only {} are left.",
+            N,
+            bytes.len()
+        )));
+    }
+
+    let (array, rest) = bytes.split_at(N);
+    Ok((array.try_into().unwrap(), rest))
+}
+
 macro_rules! bitfield_bound_check {
     ( $num:expr, $bit_count:expr, $name:literal ) => {
         if std::mem::size_of_val(&$num) as u32 * 8 - $num.leading_zeros() > $bit_count {
@@ -93,3 +187,62 @@ macro_rules! bitfield_bound_check {
 }
 
 pub(crate) use bitfield_bound_check;
+
+/// Cdbs whose reserved and obsolete fields must go out as zero, strict targets reject a
+/// command with CHECK CONDITION otherwise. Implemented with reserved_fields.
+pub(crate) trait ReservedFields: Copy {
+    fn reserved_fields_clear(&self) -> bool;
+
+    fn with_reserved_fields_cleared(self) -> Self;
+}
+
+// zeroes the reserved fields of a cdb right before it is sent. They can only become nonzero
+// through a bug in a builder, debug builds assert instead of hiding it
+pub(crate) fn clear_reserved_fields<C: ReservedFields>(command_buffer: C) -> C {
+    debug_assert!(
+        command_buffer.reserved_fields_clear(),
+        "reserved cdb fields should be zero"
+    );
+    command_buffer.with_reserved_fields_cleared()
+}
+
+// implements ReservedFields for a bitfield cdb from its reserved fields' getters and setters
+macro_rules! reserved_fields {
+    ( $buffer:ty, $( $getter:ident / $setter:ident ),+ ) => {
+        impl crate::command::ReservedFields for $buffer {
+            fn reserved_fields_clear(&self) -> bool {
+                true $( && self.$getter() == 0 )+
+            }
+
+            fn with_reserved_fields_cleared(self) -> Self {
+                self $( .$setter(0) )+
+            }
+        }
+    };
+}
+
+pub(crate) use reserved_fields;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_array_test() {
+        let (array, rest) = get_array::<4>(&[0x01, 0x02]);
+        assert_eq!(array, [0x01, 0x02, 0x00, 0x00], "zero padded");
+        assert!(rest.is_empty(), "rest");
+
+        let (array, rest) = try_get_array::<2>(&[0x01, 0x02, 0x03]).unwrap();
+        assert_eq!(array, [0x01, 0x02], "array");
+        assert_eq!(rest, &[0x03], "rest");
+
+        assert!(
+            matches!(
+                try_get_array::<4>(&[0x01, 0x02]),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short response"
+        );
+    }
+}
@@ -26,7 +26,7 @@ impl<'a> ModeSelectCommand<'a> {
             page_format: false,
             revert_to_defaults: false,
             saved_pages: false,
-            control: 0,
+            control: interface.default_control(),
             data_buffer: vec![],
         }
     }
@@ -57,6 +57,11 @@ impl<'a> ModeSelectCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(
         &self,
         parameter_length_bits: u32,
@@ -177,6 +182,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -207,4 +216,21 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer10))
         );
     }
+
+    #[test]
+    fn parameter_vec_test() {
+        let scsi = Scsi::test_instance();
+        let data = vec![0x00, 0x00, 0x00, 0x00, 0x08, 0x0A, 0x04, 0x00];
+
+        scsi.mode_select().parameter(&data).issue_10().unwrap();
+        scsi.mode_select()
+            .parameter_vec(data.clone())
+            .issue_10()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb, issued[1].cdb, "cdb");
+        assert_eq!(issued[0].data_out, data, "parameter");
+        assert_eq!(issued[1].data_out, data, "parameter_vec");
+    }
 }
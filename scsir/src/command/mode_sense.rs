@@ -3,7 +3,10 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{
+        bitfield_bound_check, check_allocation_length, clear_reserved_fields, reserved_fields,
+        ReservedFields, SizedDataIn,
+    },
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -38,7 +41,7 @@ impl<'a> ModeSenseCommand<'a> {
             page_code: 0,
             subpage_code: 0,
             allocation_length: 0,
-            control: 0,
+            control: interface.default_control(),
         }
     }
 
@@ -68,6 +71,12 @@ impl<'a> ModeSenseCommand<'a> {
         self
     }
 
+    // page_code must be less than 0x40
+    pub fn page_code_checked(&mut self, page_code: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(page_code, 6, "page code")?;
+        Ok(self.page_code(page_code))
+    }
+
     pub fn subpage_code(&mut self, subpage_code: u8) -> &mut Self {
         self.subpage_code = subpage_code;
         self
@@ -95,6 +104,7 @@ impl<'a> ModeSenseCommand<'a> {
             allocation_length_bits,
             "allocation length"
         )?;
+        check_allocation_length(self.allocation_length.into())?;
 
         if !allow_long_lba_accepted && self.long_lba_accepted {
             return Err(crate::Error::BadArgument(
@@ -106,29 +116,50 @@ impl<'a> ModeSenseCommand<'a> {
     }
 
     pub fn issue_6(&mut self) -> crate::Result<Vec<u8>> {
+        let command_buffer = self.command_buffer_6()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    pub fn issue_10(&mut self) -> crate::Result<Vec<u8>> {
+        let command_buffer = self.command_buffer_10()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    // the build_cdb methods run the checks of the matching issue method and return the cdb
+    // it would send, without sending anything
+
+    pub fn build_cdb_6(&self) -> crate::Result<[u8; 6]> {
+        Ok(self.command_buffer_6()?.into_bytes())
+    }
+
+    pub fn build_cdb_10(&self) -> crate::Result<[u8; 10]> {
+        Ok(self.command_buffer_10()?.into_bytes())
+    }
+
+    fn issue_buffer<C: ReservedFields>(&self, command_buffer: C) -> crate::Result<Vec<u8>> {
+        self.interface.issue(&ThisCommand {
+            command_buffer,
+            allocation_length: self.allocation_length.into(),
+        })
+    }
+
+    fn command_buffer_6(&self) -> crate::Result<CommandBuffer6> {
         self.error_check(8, false)?;
 
-        let command_buffer = CommandBuffer6::new()
+        Ok(CommandBuffer6::new()
             .with_operation_code(OPERATION_CODE_6)
             .with_disable_block_descriptors(self.disable_block_descriptors.into())
             .with_page_control(self.page_control)
             .with_page_code(self.page_code)
             .with_subpage_code(self.subpage_code)
             .with_allocation_length(self.allocation_length as u8)
-            .with_control(self.control);
-
-        let temp = ThisCommand {
-            command_buffer,
-            allocation_length: self.allocation_length.into(),
-        };
-
-        self.interface.issue(&temp)
+            .with_control(self.control))
     }
 
-    pub fn issue_10(&mut self) -> crate::Result<Vec<u8>> {
+    fn command_buffer_10(&self) -> crate::Result<CommandBuffer10> {
         self.error_check(16, true)?;
 
-        let command_buffer = CommandBuffer10::new()
+        Ok(CommandBuffer10::new()
             .with_operation_code(OPERATION_CODE_10)
             .with_long_lba_accepted(self.long_lba_accepted.into())
             .with_disable_block_descriptors(self.disable_block_descriptors.into())
@@ -136,14 +167,23 @@ impl<'a> ModeSenseCommand<'a> {
             .with_page_code(self.page_code)
             .with_subpage_code(self.subpage_code)
             .with_allocation_length(self.allocation_length)
-            .with_control(self.control);
+            .with_control(self.control))
+    }
+}
 
-        let temp = ThisCommand {
-            command_buffer,
-            allocation_length: self.allocation_length.into(),
-        };
+// uses MODE SENSE(10)
+impl SizedDataIn for ModeSenseCommand<'_> {
+    fn probe_length(&self) -> usize {
+        MODE_DATA_LENGTH_10_LENGTH
+    }
+
+    fn response_length(&self, header: &[u8]) -> usize {
+        u16::from_be_bytes([header[0], header[1]]) as usize + MODE_DATA_LENGTH_10_LENGTH
+    }
 
-        self.interface.issue(&temp)
+    fn issue_with_allocation_length(&mut self, allocation_length: usize) -> crate::Result<Vec<u8>> {
+        self.allocation_length(allocation_length.try_into().unwrap_or(u16::MAX))
+            .issue_10()
     }
 }
 
@@ -155,6 +195,8 @@ impl Scsi {
 
 const OPERATION_CODE_6: u8 = 0x1A;
 const OPERATION_CODE_10: u8 = 0x5A;
+// the MODE DATA LENGTH field itself does not count towards the mode data length
+const MODE_DATA_LENGTH_10_LENGTH: usize = 2;
 
 #[bitfield]
 #[derive(Clone, Copy)]
@@ -186,12 +228,24 @@ struct CommandBuffer10 {
     control: B8,
 }
 
+reserved_fields!(
+    CommandBuffer6,
+    reserved_0 / with_reserved_0,
+    reserved_1 / with_reserved_1
+);
+reserved_fields!(
+    CommandBuffer10,
+    reserved_0 / with_reserved_0,
+    reserved_1 / with_reserved_1,
+    reserved_2 / with_reserved_2
+);
+
 struct ThisCommand<C> {
     command_buffer: C,
     allocation_length: usize,
 }
 
-impl<C: Copy> Command for ThisCommand<C> {
+impl<C: ReservedFields> Command for ThisCommand<C> {
     type CommandBuffer = C;
 
     type DataBuffer = AnyType;
@@ -205,7 +259,7 @@ impl<C: Copy> Command for ThisCommand<C> {
     }
 
     fn command(&self) -> Self::CommandBuffer {
-        self.command_buffer
+        clear_reserved_fields(self.command_buffer)
     }
 
     fn data(&self) -> Self::DataBufferWrapper {
@@ -216,11 +270,23 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.allocation_length as u32
     }
 
+    fn allows_empty_data_in(&self) -> bool {
+        false
+    }
+
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(std::mem::take(result.data))
+        let length = result.transferred_data().len();
+        let mut data = result.into_data();
+        data.truncate(length);
+
+        Ok(data)
     }
 }
 
@@ -246,4 +312,107 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer10))
         );
     }
+
+    #[test]
+    fn checked_setter_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.mode_sense();
+
+        assert!(command.page_code_checked(0x3F).is_ok(), "page code");
+        assert!(command.page_code_checked(0x40).is_err(), "page code");
+    }
+
+    #[test]
+    fn issue_sized_test() {
+        let data = [
+            0x00, 0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0A, 0x04, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&data[..2]);
+        scsi.mock().push_data(&data);
+
+        let bytes = scsi.mode_sense().page_code(0x08).issue_sized().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[0], 0x5A, "mode sense(10)");
+        assert_eq!(issued[0].cdb[7..9], [0x00, 0x02], "probe allocation length");
+        assert_eq!(issued[1].cdb[7..9], [0x00, 0x10], "full allocation length");
+        assert_eq!(bytes, data, "mode data");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00]);
+
+        assert!(
+            matches!(
+                scsi.mode_sense().issue_sized(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short probe"
+        );
+    }
+
+    #[test]
+    fn zero_allocation_length_test() {
+        let scsi = Scsi::test_instance();
+
+        assert!(
+            matches!(
+                scsi.mode_sense().page_code(0x08).issue_10(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "mode sense(10)"
+        );
+        assert!(
+            matches!(
+                scsi.mode_sense().page_code(0x08).issue_6(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "mode sense(6)"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
+
+    #[test]
+    fn reserved_fields_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x03, 0x00, 0x00, 0x00]);
+        scsi.mock()
+            .push_data(&[0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        let mut command = scsi.mode_sense();
+        command
+            .disable_block_descriptors(true)
+            .page_code(0x3F)
+            .subpage_code(0xFF)
+            .allocation_length(0xFF);
+        command.issue_6().unwrap();
+        command.long_lba_accepted(true).issue_10().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[1] & 0xF7, 0, "mode sense(6) reserved");
+        assert_eq!(issued[1].cdb[1] & 0xE7, 0, "mode sense(10) reserved");
+        assert_eq!(issued[1].cdb[4..7], [0, 0, 0], "mode sense(10) reserved");
+    }
+
+    #[test]
+    fn builder_build_cdb_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.mode_sense();
+        command.page_code(0x08).allocation_length(0xFC);
+
+        assert_eq!(
+            command.build_cdb_6().unwrap(),
+            [0x1A, 0x00, 0x08, 0x00, 0xFC, 0x00],
+            "mode sense(6)"
+        );
+        assert_eq!(
+            command.long_lba_accepted(true).build_cdb_10().unwrap(),
+            [0x5A, 0x10, 0x08, 0x00, 0, 0, 0, 0x00, 0xFC, 0x00],
+            "mode sense(10)"
+        );
+        assert!(command.build_cdb_6().is_err(), "long lba accepted");
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
 }
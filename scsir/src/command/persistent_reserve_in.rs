@@ -5,6 +5,7 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    byte_reader::ByteReader,
     command::{bitfield_bound_check, get_array},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
@@ -19,6 +20,7 @@ pub struct PersistentReserveInCommand<'a> {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
 pub enum ServiceAction {
     ReadKeys,
     ReadReservation,
@@ -39,6 +41,9 @@ pub struct ReadKeysData {
     pub persistent_reservations_generation: u32,
     pub required_length: u32,
     pub reservation_keys: Vec<u64>,
+    // the allocation length cut the list short, reservation_keys only holds the whole ones,
+    // allocate required_length to read them all
+    pub truncated: bool,
 }
 
 pub struct ReadReservationData {
@@ -69,6 +74,9 @@ pub struct ReadFullStatusData {
     pub persistent_reservations_generation: u32,
     pub required_length: u32,
     pub descriptors: Vec<ReadFullStatusDescriptor>,
+    // the allocation length cut the list short, descriptors only holds the whole ones,
+    // allocate required_length to read them all
+    pub truncated: bool,
 }
 
 pub struct ReadFullStatusDescriptor {
@@ -86,7 +94,9 @@ impl<'a> PersistentReserveInCommand<'a> {
         Self {
             interface,
             service_action: ServiceAction::ReadKeys,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -117,6 +127,23 @@ impl<'a> PersistentReserveInCommand<'a> {
         };
         self.interface.issue(&temp)
     }
+
+    // like issue, but a READ KEYS or READ FULL STATUS list that did not fit the allocation
+    // length is an AllocationTooSmall error instead of being returned cut short
+    pub fn issue_strict(&mut self) -> crate::Result<CommandResult> {
+        let result = self.issue()?;
+        let required = match &result {
+            CommandResult::ReadKeys(data) => data.required_length,
+            CommandResult::ReadFullStatus(data) => data.required_length,
+            _ => 0,
+        };
+
+        if required > self.command_buffer.allocation_length() as u32 {
+            return Err(crate::Error::AllocationTooSmall { required });
+        }
+
+        Ok(result)
+    }
 }
 
 impl Scsi {
@@ -126,48 +153,66 @@ impl Scsi {
 }
 
 impl ReadKeysData {
-    fn from_bytes(bytes: &[u8]) -> Self {
-        let (array, bytes) = get_array(bytes);
-        let persistent_reservations_generation = u32::from_be_bytes(array);
-
-        let (array, bytes) = get_array(bytes);
-        let additional_length = u32::from_be_bytes(array);
+    fn from_bytes(bytes: &[u8]) -> crate::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let persistent_reservations_generation = reader.read_u32()?;
+        let additional_length = reader.read_u32()?;
         let required_length = additional_length.saturating_add(8);
 
-        let mut reservation_keys = vec![];
+        // the list is cut short when the allocation length was too small
+        let listed = usize::min(additional_length as usize, reader.remaining());
+        let mut list = ByteReader::new(reader.read_bytes(listed)?);
 
-        for chunk in bytes.chunks(size_of::<u64>()) {
-            reservation_keys.push(u64::from_be_bytes(get_array(chunk).0))
+        let mut reservation_keys = vec![];
+        while list.remaining() >= size_of::<u64>() {
+            reservation_keys.push(list.read_u64()?);
         }
+        let truncated = reservation_keys.len() * size_of::<u64>() < additional_length as usize;
 
-        Self {
+        Ok(Self {
             persistent_reservations_generation,
             required_length,
             reservation_keys,
-        }
+            truncated,
+        })
     }
 }
 
 impl ReadReservationData {
-    fn from_bytes(bytes: &[u8]) -> Self {
-        let (array, _) = get_array(bytes);
-        let data = ReadReservationBitfield::from_bytes(array);
-
-        Self {
-            persistent_reservations_generation: data.persistent_reservations_generation(),
-            reservation_key: data.reservation_key(),
-            reservation_scope: data.reservation_scope(),
-            reservation_type: data.reservation_type(),
+    fn from_bytes(bytes: &[u8]) -> crate::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let persistent_reservations_generation = reader.read_u32()?;
+
+        // no reservation is held when the additional length is 0
+        if reader.read_u32()? == 0 {
+            return Ok(Self {
+                persistent_reservations_generation,
+                reservation_key: 0,
+                reservation_scope: 0,
+                reservation_type: 0,
+            });
         }
+
+        let reservation_key = reader.read_u64()?;
+        reader.skip(5)?;
+        let scope_and_type = reader.read_u8()?;
+
+        Ok(Self {
+            persistent_reservations_generation,
+            reservation_key,
+            reservation_scope: scope_and_type >> 4,
+            reservation_type: scope_and_type & 0x0F,
+        })
     }
 }
 
 impl ReportCapabilitiesData {
-    fn from_bytes(bytes: &[u8]) -> Self {
+    fn from_bytes(bytes: &[u8]) -> crate::Result<Self> {
+        let bytes = ByteReader::new(bytes).read_bytes(size_of::<ReportCapabilitiesBitfield>())?;
         let (array, _) = get_array(bytes);
         let data = ReportCapabilitiesBitfield::from_bytes(array);
 
-        Self {
+        Ok(Self {
             replace_lost_reservation_capable: data.replace_lost_reservation_capable() != 0,
             compatible_reservation_handling: data.compatible_reservation_handling() != 0,
             specify_initiator_ports_capable: data.specify_initiator_ports_capable() != 0,
@@ -182,46 +227,99 @@ impl ReportCapabilitiesData {
             exclusive_access: data.exclusive_access() != 0,
             write_exclusive: data.write_exclusive() != 0,
             exclusive_access_all_registrants: data.exclusive_access_all_registrants() != 0,
-        }
+        })
     }
 }
 
 impl ReadFullStatusData {
-    fn from_bytes(bytes: &[u8]) -> Self {
-        let (array, mut bytes) = get_array(bytes);
-        let header = ReadFullsstatusHeaderBitfield::from_bytes(array);
+    fn from_bytes(bytes: &[u8]) -> crate::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let persistent_reservations_generation = reader.read_u32()?;
+        let additional_length = reader.read_u32()?;
+
+        // the list is cut short when the allocation length was too small
+        let listed = usize::min(additional_length as usize, reader.remaining());
 
         let mut descriptors = vec![];
+        let mut truncated = listed < additional_length as usize;
 
-        while !bytes.is_empty() {
-            let (array, left_bytes) = get_array(bytes);
-            let descriptor_header = ReadFullsstatusDescriptorHeaderBitfield::from_bytes(array);
-            let additional_descriptor_length = usize::min(
-                descriptor_header.additional_descriptor_length() as usize,
-                left_bytes.len(),
-            );
-            let transportid = Vec::from(&left_bytes[..additional_descriptor_length]);
-
-            let descriptor = ReadFullStatusDescriptor {
-                reservation_key: descriptor_header.reservation_key(),
-                all_target_ports: descriptor_header.all_target_ports() != 0,
-                reservation_holder: descriptor_header.reservation_holder() != 0,
-                reservation_scope: descriptor_header.reservation_scope(),
-                reservation_type: descriptor_header.reservation_type(),
-                relative_target_port_identifier: descriptor_header
-                    .relative_target_port_identifier(),
-                transportid,
+        let mut rest = reader.read_bytes(listed)?;
+        while !rest.is_empty() {
+            let Ok(descriptor) = ReadFullStatusDescriptor::try_from(rest) else {
+                truncated = true;
+                break;
             };
 
+            rest =
+                &rest[READ_FULL_STATUS_DESCRIPTOR_HEADER_LENGTH + descriptor.transportid.len()..];
             descriptors.push(descriptor);
-            bytes = &left_bytes[additional_descriptor_length..];
         }
 
-        Self {
-            persistent_reservations_generation: header.persistent_reservations_generation(),
-            required_length: header.additional_length().saturating_add(8),
+        Ok(Self {
+            persistent_reservations_generation,
+            required_length: additional_length.saturating_add(8),
             descriptors,
-        }
+            truncated,
+        })
+    }
+}
+
+// the TryFrom impls decode parameter data captured elsewhere, as issue does with the
+// response of the matching service action
+
+impl TryFrom<&[u8]> for ReadKeysData {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for ReadReservationData {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for ReportCapabilitiesData {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for ReadFullStatusData {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+// decodes one descriptor from the start of bytes, an incomplete one is an error
+impl TryFrom<&[u8]> for ReadFullStatusDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let (array, _) = get_array(reader.read_bytes(READ_FULL_STATUS_DESCRIPTOR_HEADER_LENGTH)?);
+        let header = ReadFullsstatusDescriptorHeaderBitfield::from_bytes(array);
+        let transportid = reader
+            .read_bytes(header.additional_descriptor_length() as usize)?
+            .to_vec();
+
+        Ok(Self {
+            reservation_key: header.reservation_key(),
+            all_target_ports: header.all_target_ports() != 0,
+            reservation_holder: header.reservation_holder() != 0,
+            reservation_scope: header.reservation_scope(),
+            reservation_type: header.reservation_type(),
+            relative_target_port_identifier: header.relative_target_port_identifier(),
+            transportid,
+        })
     }
 }
 
@@ -301,6 +399,8 @@ struct ReadFullsstatusDescriptorHeaderBitfield {
 }
 
 const OPERATION_CODE: u8 = 0x5E;
+const READ_FULL_STATUS_DESCRIPTOR_HEADER_LENGTH: usize =
+    size_of::<ReadFullsstatusDescriptorHeaderBitfield>();
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -343,22 +443,26 @@ impl Command for ThisCommand {
         self.command_buffer.allocation_length() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        let bytes = &result.data()[..];
+        let bytes = result.transferred_data();
 
         Ok(match self.service_action {
-            ServiceAction::ReadKeys => CommandResult::ReadKeys(ReadKeysData::from_bytes(bytes)),
+            ServiceAction::ReadKeys => CommandResult::ReadKeys(ReadKeysData::from_bytes(bytes)?),
             ServiceAction::ReadReservation => {
-                CommandResult::ReadReservation(ReadReservationData::from_bytes(bytes))
+                CommandResult::ReadReservation(ReadReservationData::from_bytes(bytes)?)
             }
             ServiceAction::ReportCapabilities => {
-                CommandResult::ReportCapabilities(ReportCapabilitiesData::from_bytes(bytes))
+                CommandResult::ReportCapabilities(ReportCapabilitiesData::from_bytes(bytes)?)
             }
             ServiceAction::ReadFullStatus => {
-                CommandResult::ReadFullStatus(ReadFullStatusData::from_bytes(bytes))
+                CommandResult::ReadFullStatus(ReadFullStatusData::from_bytes(bytes)?)
             }
             ServiceAction::Other(_) => CommandResult::Raw(Vec::from(bytes)),
         })
@@ -411,4 +515,222 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn read_keys_test() {
+        let keys = ReadKeysData::from_bytes(&[
+            0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ])
+        .unwrap();
+
+        assert_eq!(keys.persistent_reservations_generation, 7, "generation");
+        assert_eq!(keys.required_length, 24, "required length");
+        assert_eq!(keys.reservation_keys, [1], "only whole keys");
+        assert!(keys.truncated, "truncated");
+
+        let keys = ReadKeysData::from_bytes(&[
+            0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01,
+        ])
+        .unwrap();
+        assert_eq!(keys.reservation_keys, [1], "whole list");
+        assert!(!keys.truncated, "not truncated");
+
+        assert!(
+            matches!(
+                ReadKeysData::from_bytes(&[0x00, 0x00, 0x00, 0x07, 0x00]),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short header"
+        );
+    }
+
+    #[test]
+    fn read_reservation_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock()
+            .push_data(&[0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00]);
+
+        let result = scsi
+            .persistent_reserve_in()
+            .service_action(ServiceAction::ReadReservation)
+            .allocation_length(24)
+            .issue()
+            .unwrap();
+
+        match result {
+            CommandResult::ReadReservation(data) => {
+                assert_eq!(data.persistent_reservations_generation, 3, "generation");
+                assert_eq!(data.reservation_key, 0, "no reservation");
+            }
+            _ => panic!("unexpected result"),
+        }
+
+        let data = ReadReservationData::from_bytes(&[
+            0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x12, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00,
+        ])
+        .unwrap();
+        assert_eq!(data.reservation_key, 0x1234, "reservation key");
+        assert_eq!(data.reservation_type, 0x05, "reservation type");
+
+        assert!(
+            matches!(
+                ReadReservationData::from_bytes(&[0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x10]),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "missing reservation"
+        );
+    }
+
+    #[test]
+    fn read_full_status_test() {
+        let mut descriptor = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2A];
+        descriptor.extend_from_slice(&[0x00; 4]);
+        descriptor.extend_from_slice(&[0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        descriptor.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0xA0, 0xA1, 0xA2, 0xA3]);
+
+        let mut data = vec![0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x38];
+        data.extend_from_slice(&descriptor);
+        data.extend_from_slice(&descriptor);
+
+        let full = ReadFullStatusData::from_bytes(&data).unwrap();
+        assert_eq!(full.persistent_reservations_generation, 9, "generation");
+        assert_eq!(full.required_length, 64, "required length");
+        assert_eq!(full.descriptors.len(), 2, "descriptors");
+        assert!(!full.truncated, "whole list");
+        assert_eq!(full.descriptors[0].reservation_key, 0x2A, "reservation key");
+        assert!(full.descriptors[0].reservation_holder, "reservation holder");
+        assert_eq!(
+            full.descriptors[0].reservation_type, 0x05,
+            "reservation type"
+        );
+        assert_eq!(
+            full.descriptors[0].relative_target_port_identifier, 0x02,
+            "relative target port identifier"
+        );
+        assert_eq!(
+            full.descriptors[0].transportid,
+            [0xA0, 0xA1, 0xA2, 0xA3],
+            "transport id"
+        );
+
+        for (length, whole) in [(8 + 28 + 10, 1), (8 + 28 + 26, 1), (8 + 20, 0)] {
+            let partial = ReadFullStatusData::from_bytes(&data[..length]).unwrap();
+            assert_eq!(
+                partial.descriptors.len(),
+                whole,
+                "whole descriptors of {length}"
+            );
+            assert!(partial.truncated, "truncated at {length}");
+            assert_eq!(partial.required_length, 64, "required length at {length}");
+        }
+    }
+
+    #[test]
+    fn issue_strict_test() {
+        let keys = [
+            0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+        ];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&keys);
+        scsi.mock().push_data(&keys);
+
+        let result = scsi
+            .persistent_reserve_in()
+            .service_action(ServiceAction::ReadKeys)
+            .allocation_length(16)
+            .issue_strict();
+        assert!(
+            matches!(
+                result,
+                Err(crate::Error::AllocationTooSmall { required: 24 })
+            ),
+            "required length"
+        );
+
+        let result = scsi
+            .persistent_reserve_in()
+            .service_action(ServiceAction::ReadKeys)
+            .allocation_length(24)
+            .issue_strict()
+            .unwrap();
+        match result {
+            CommandResult::ReadKeys(data) => {
+                assert_eq!(data.reservation_keys, [1, 2], "reservation keys")
+            }
+            _ => panic!("unexpected result"),
+        }
+    }
+
+    #[test]
+    fn try_from_test() {
+        let mut bytes = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2A];
+        bytes.extend_from_slice(&[0x00; 4]);
+        bytes.extend_from_slice(&[0x03, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05]);
+        bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x02, 0xB0, 0xB1]);
+
+        let descriptor = ReadFullStatusDescriptor::try_from(&bytes[..]).unwrap();
+        assert_eq!(descriptor.reservation_key, 0x2A, "reservation key");
+        assert!(descriptor.all_target_ports, "all target ports");
+        assert!(descriptor.reservation_holder, "reservation holder");
+        assert_eq!(descriptor.reservation_scope, 0x01, "reservation scope");
+        assert_eq!(descriptor.reservation_type, 0x03, "reservation type");
+        assert_eq!(
+            descriptor.relative_target_port_identifier, 0x05,
+            "relative target port identifier"
+        );
+        assert_eq!(descriptor.transportid, [0xB0, 0xB1], "transport id");
+
+        assert!(
+            matches!(
+                ReadFullStatusDescriptor::try_from(&bytes[..25]),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "incomplete transport id"
+        );
+
+        let keys = ReadKeysData::try_from(
+            &[
+                0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x11,
+            ][..],
+        )
+        .unwrap();
+        assert_eq!(keys.persistent_reservations_generation, 3, "generation");
+        assert_eq!(keys.reservation_keys, [0x11], "reservation keys");
+
+        let short = &[0x00, 0x00, 0x00][..];
+        assert!(
+            matches!(
+                ReadKeysData::try_from(short),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short read keys"
+        );
+        assert!(
+            matches!(
+                ReadReservationData::try_from(short),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short read reservation"
+        );
+        assert!(
+            matches!(
+                ReportCapabilitiesData::try_from(short),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short report capabilities"
+        );
+        assert!(
+            matches!(
+                ReadFullStatusData::try_from(short),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short read full status"
+        );
+    }
 }
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use std::fmt::Debug;
+
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
@@ -9,7 +11,7 @@ use crate::{
     Command, DataDirection, Scsi,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct PersistentReserveOutCommand<'a> {
     interface: &'a Scsi,
     service_action: ServiceAction,
@@ -20,6 +22,7 @@ pub struct PersistentReserveOutCommand<'a> {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
 pub enum ServiceAction {
     Register,
     Reserve,
@@ -33,6 +36,15 @@ pub enum ServiceAction {
     Other(u8),
 }
 
+// TransportID of an initiator port, see SPC-4 7.6.4
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransportId {
+    FibreChannel { n_port_name: u64 },
+    Sas { sas_address: u64 },
+    // isid must be less than 0x1_0000_0000_0000
+    Iscsi { name: String, isid: Option<u64> },
+}
+
 pub struct ParameterBuilder<'a> {
     parent: &'a mut PersistentReserveOutCommand<'a>,
     data_buffer: Vec<u8>,
@@ -57,7 +69,9 @@ impl<'a> PersistentReserveOutCommand<'a> {
             service_action: ServiceAction::Register,
             reservation_scope: 0,
             reservation_type: 0,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -163,7 +177,13 @@ impl<'a> BasicParameterData<'a> {
         self
     }
 
-    pub fn transport_id_list(&mut self, value: &[u8]) -> &mut Self {
+    pub fn transport_id_list(&mut self, value: &[TransportId]) -> crate::Result<&mut Self> {
+        self.transport_id = encode_transport_id_list(value)?;
+        Ok(self)
+    }
+
+    // already encoded TransportIDs
+    pub fn transport_id_list_raw(&mut self, value: &[u8]) -> &mut Self {
         self.transport_id.clear();
         self.transport_id.extend_from_slice(value);
         self
@@ -220,7 +240,13 @@ impl<'a> RegisterAndMoveParameterData<'a> {
         self
     }
 
-    pub fn transport_id_list(&mut self, value: &[u8]) -> &mut Self {
+    pub fn transport_id_list(&mut self, value: &[TransportId]) -> crate::Result<&mut Self> {
+        self.transport_id = encode_transport_id_list(value)?;
+        Ok(self)
+    }
+
+    // already encoded TransportIDs
+    pub fn transport_id_list_raw(&mut self, value: &[u8]) -> &mut Self {
         self.transport_id.clear();
         self.transport_id.extend_from_slice(value);
         self
@@ -239,6 +265,79 @@ impl<'a> RegisterAndMoveParameterData<'a> {
     }
 }
 
+impl TransportId {
+    pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        match self {
+            TransportId::FibreChannel { n_port_name } => {
+                let mut bytes = vec![0; FIXED_TRANSPORT_ID_LENGTH];
+                bytes[0] = FIBRE_CHANNEL_PROTOCOL_IDENTIFIER;
+                bytes[8..16].copy_from_slice(&n_port_name.to_be_bytes());
+                Ok(bytes)
+            }
+            TransportId::Sas { sas_address } => {
+                let mut bytes = vec![0; FIXED_TRANSPORT_ID_LENGTH];
+                bytes[0] = SAS_PROTOCOL_IDENTIFIER;
+                bytes[4..12].copy_from_slice(&sas_address.to_be_bytes());
+                Ok(bytes)
+            }
+            TransportId::Iscsi { name, isid } => {
+                if name.is_empty() || name.contains('\0') {
+                    return Err(crate::Error::BadArgument(
+                        "iscsi name should be non-empty and contain no NUL.".to_owned(),
+                    ));
+                }
+
+                let mut name = name.clone().into_bytes();
+                let format_code = match isid {
+                    Some(isid) => {
+                        bitfield_bound_check!(*isid, 48, "isid")?;
+                        name.extend_from_slice(format!(",i,0x{:012x}", isid).as_bytes());
+                        ISCSI_PORT_FORMAT_CODE
+                    }
+                    None => ISCSI_DEVICE_FORMAT_CODE,
+                };
+                name.push(0);
+
+                // padded to a multiple of four, with at least 20 bytes after the header
+                let padded_length = usize::max(name.len().next_multiple_of(4), 20);
+                bitfield_bound_check!(padded_length, 16, "iscsi name length")?;
+                name.resize(padded_length, 0);
+
+                let mut bytes = vec![format_code << 6 | ISCSI_PROTOCOL_IDENTIFIER, 0];
+                bytes.extend_from_slice(&(padded_length as u16).to_be_bytes());
+                bytes.append(&mut name);
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+fn encode_transport_id_list(value: &[TransportId]) -> crate::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    for transport_id in value {
+        bytes.append(&mut transport_id.to_bytes()?);
+    }
+
+    Ok(bytes)
+}
+
+// the parameter carries reservation keys and transport ids, only its length is printed
+impl Debug for PersistentReserveOutCommand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PersistentReserveOutCommand")
+            .field("interface", &self.interface)
+            .field("service_action", &self.service_action)
+            .field("reservation_scope", &self.reservation_scope)
+            .field("reservation_type", &self.reservation_type)
+            .field("command_buffer", &self.command_buffer)
+            .field(
+                "data_buffer",
+                &format_args!("[redacted; {}]", self.data_buffer.len()),
+            )
+            .finish()
+    }
+}
+
 impl Scsi {
     pub fn persistent_reserve_out(&self) -> PersistentReserveOutCommand<'_> {
         PersistentReserveOutCommand::new(self)
@@ -292,6 +391,13 @@ struct RegisterAndMoveParameterHeader {
 
 const OPERATION_CODE: u8 = 0x5F;
 
+const FIXED_TRANSPORT_ID_LENGTH: usize = 24;
+const FIBRE_CHANNEL_PROTOCOL_IDENTIFIER: u8 = 0x0;
+const ISCSI_PROTOCOL_IDENTIFIER: u8 = 0x5;
+const SAS_PROTOCOL_IDENTIFIER: u8 = 0x6;
+const ISCSI_DEVICE_FORMAT_CODE: u8 = 0b00;
+const ISCSI_PORT_FORMAT_CODE: u8 = 0b01;
+
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
 struct CommandBuffer {
@@ -331,6 +437,10 @@ impl Command for ThisCommand {
         self.data_buffer.clone()
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -368,4 +478,121 @@ mod tests {
             concat!("Size of: ", stringify!(RegisterAndMoveParameterHeader))
         );
     }
+
+    #[test]
+    fn debug_redaction_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.persistent_reserve_out();
+        command.data_buffer = 0x0123_4567_89AB_CDEFu64.to_be_bytes().to_vec();
+
+        let output = format!("{:?}", command);
+        assert!(output.contains("[redacted; 8]"), "{}", output);
+        assert!(!output.contains("137, 171, 205"), "{}", output);
+    }
+
+    #[test]
+    fn transport_id_test() {
+        let fibre_channel = TransportId::FibreChannel {
+            n_port_name: 0x2100_0024_FF12_3456,
+        }
+        .to_bytes()
+        .unwrap();
+        assert_eq!(fibre_channel.len(), 24, "fibre channel length");
+        assert_eq!(fibre_channel[0], 0x00, "fibre channel protocol");
+        assert_eq!(
+            fibre_channel[8..16],
+            [0x21, 0x00, 0x00, 0x24, 0xFF, 0x12, 0x34, 0x56],
+            "n port name"
+        );
+
+        let sas = TransportId::Sas {
+            sas_address: 0x5000_C500_1234_5678,
+        }
+        .to_bytes()
+        .unwrap();
+        assert_eq!(sas.len(), 24, "sas length");
+        assert_eq!(sas[0], 0x06, "sas protocol");
+        assert_eq!(
+            sas[4..12],
+            [0x50, 0x00, 0xC5, 0x00, 0x12, 0x34, 0x56, 0x78],
+            "sas address"
+        );
+        assert!(sas[12..].iter().all(|byte| *byte == 0), "sas reserved");
+
+        let device = TransportId::Iscsi {
+            name: "iqn.2001-04.com.example:storage".to_owned(),
+            isid: None,
+        }
+        .to_bytes()
+        .unwrap();
+        assert_eq!(device[0], 0x05, "iscsi device format");
+        assert_eq!(device[2..4], [0x00, 0x20], "iscsi device additional length");
+        assert_eq!(&device[4..35], b"iqn.2001-04.com.example:storage", "name");
+        assert_eq!(device.len(), 36, "iscsi device length");
+        assert_eq!(device[35], 0x00, "null terminated");
+
+        let port = TransportId::Iscsi {
+            name: "iqn.a".to_owned(),
+            isid: Some(0x0023_3D00_0001),
+        }
+        .to_bytes()
+        .unwrap();
+        assert_eq!(port[0], 0x45, "iscsi port format");
+        assert_eq!(port[2..4], [0x00, 0x18], "iscsi port additional length");
+        assert_eq!(&port[4..26], b"iqn.a,i,0x00233d000001", "name and isid");
+        assert!(port[26..].iter().all(|byte| *byte == 0), "padding");
+
+        let short = TransportId::Iscsi {
+            name: "iqn.a".to_owned(),
+            isid: None,
+        }
+        .to_bytes()
+        .unwrap();
+        assert_eq!(short.len(), 24, "minimum iscsi length");
+
+        assert!(
+            TransportId::Iscsi {
+                name: "iqn.a".to_owned(),
+                isid: Some(1 << 48),
+            }
+            .to_bytes()
+            .is_err(),
+            "isid bound"
+        );
+        assert!(
+            TransportId::Iscsi {
+                name: String::new(),
+                isid: None,
+            }
+            .to_bytes()
+            .is_err(),
+            "empty name"
+        );
+    }
+
+    #[test]
+    fn transport_id_list_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.persistent_reserve_out();
+        let mut parameter = command.parameter();
+        let mut basic = parameter.basic_parameter();
+        basic
+            .reservation_key(0x1234)
+            .specify_initiator_ports(true)
+            .transport_id_list(&[
+                TransportId::Sas { sas_address: 1 },
+                TransportId::FibreChannel { n_port_name: 2 },
+            ])
+            .unwrap();
+        let data = basic.done().done().data_buffer.clone();
+
+        assert_eq!(data.len(), 24 + 4 + 48, "parameter length");
+        assert_eq!(
+            data[24..28],
+            [0x00, 0x00, 0x00, 0x30],
+            "transport id length"
+        );
+        assert_eq!(data[28], 0x06, "first transport id");
+        assert_eq!(data[52], 0x00, "second transport id");
+    }
 }
@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, clear_reserved_fields, reserved_fields, ReservedFields},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -17,6 +17,7 @@ pub struct ReadCommand<'a> {
     read_protect: u8,
     disable_page_out: bool,
     force_unit_access: bool,
+    verify_dpo_fua_support: bool,
     rebuild_assist_recovery_control: bool,
     logical_block_address: u64,
     expected_initial_logical_block_reference_tag: u32,
@@ -33,11 +34,12 @@ impl<'a> ReadCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             group_number: 0,
             read_protect: 0,
             disable_page_out: false,
             force_unit_access: false,
+            verify_dpo_fua_support: false,
             rebuild_assist_recovery_control: false,
             logical_block_address: 0,
             expected_initial_logical_block_reference_tag: 0,
@@ -47,7 +49,7 @@ impl<'a> ReadCommand<'a> {
             dld_0: false,
             dld_1: false,
             dld_2: false,
-            logical_block_size: 512,
+            logical_block_size: interface.default_logical_block_size(),
         }
     }
 
@@ -68,16 +70,43 @@ impl<'a> ReadCommand<'a> {
         self
     }
 
+    // group_number must be less than 0x40, issue_10, issue_12 and issue_32 further require less than 0x20
+    pub fn group_number_checked(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 6, "group number")?;
+        Ok(self.group_number(value))
+    }
+
+    // read_protect must be less than 0x08
+    pub fn read_protect_checked(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 3, "read protect")?;
+        Ok(self.read_protect(value))
+    }
+
+    // DPO asks the device not to keep the blocks read in its cache
     pub fn disable_page_out(&mut self, value: bool) -> &mut Self {
         self.disable_page_out = value;
         self
     }
 
+    // FUA makes the device read from the medium, not from its cache
     pub fn force_unit_access(&mut self, value: bool) -> &mut Self {
         self.force_unit_access = value;
         self
     }
 
+    // sets DPO and FUA together, the device cache is neither relied on nor filled
+    pub fn cache_bypass(&mut self) -> &mut Self {
+        self.disable_page_out(true).force_unit_access(true)
+    }
+
+    // when DPO or FUA is set, issue first checks with Scsi::dpo_fua_supported that the
+    // device honours them and fails with BadArgument otherwise, instead of the device
+    // silently ignoring them; this costs a MODE SENSE per issue
+    pub fn verify_dpo_fua_support(&mut self, value: bool) -> &mut Self {
+        self.verify_dpo_fua_support = value;
+        self
+    }
+
     pub fn rebuild_assist_recovery_control(&mut self, value: bool) -> &mut Self {
         self.rebuild_assist_recovery_control = value;
         self
@@ -173,10 +202,83 @@ impl<'a> ReadCommand<'a> {
         Ok(())
     }
 
+    // queries the device, so it runs from the issue methods only and not from error_check
+    fn check_dpo_fua_support(&self) -> crate::Result<()> {
+        if self.verify_dpo_fua_support
+            && (self.disable_page_out || self.force_unit_access)
+            && !self.interface.dpo_fua_supported()?
+        {
+            return Err(crate::Error::BadArgument(
+                "DPO and FUA are not supported by the device.".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn issue_10(&mut self) -> crate::Result<Vec<u8>> {
+        let command_buffer = self.command_buffer_10()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    pub fn issue_12(&mut self) -> crate::Result<Vec<u8>> {
+        let command_buffer = self.command_buffer_12()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    pub fn issue_16(&mut self) -> crate::Result<Vec<u8>> {
+        self.prepare_16()?.issue()
+    }
+
+    // checks the command once and builds its READ(16) cdb, see PreparedRead16
+    pub fn prepare_16(&mut self) -> crate::Result<PreparedRead16<'a>> {
+        let command_buffer = self.command_buffer_16()?;
+        self.check_dpo_fua_support()?;
+        Ok(PreparedRead16 {
+            interface: self.interface,
+            command_buffer,
+            logical_block_size: self.logical_block_size,
+        })
+    }
+
+    pub fn issue_32(&mut self) -> crate::Result<Vec<u8>> {
+        let command_buffer = self.command_buffer_32()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    // the build_cdb methods run the checks of the matching issue method and return the cdb
+    // it would send, without sending anything
+
+    pub fn build_cdb_10(&self) -> crate::Result<[u8; 10]> {
+        Ok(self.command_buffer_10()?.into_bytes())
+    }
+
+    pub fn build_cdb_12(&self) -> crate::Result<[u8; 12]> {
+        Ok(self.command_buffer_12()?.into_bytes())
+    }
+
+    pub fn build_cdb_16(&self) -> crate::Result<[u8; 16]> {
+        Ok(self.command_buffer_16()?.into_bytes())
+    }
+
+    pub fn build_cdb_32(&self) -> crate::Result<[u8; 32]> {
+        Ok(self.command_buffer_32()?.into_bytes())
+    }
+
+    fn issue_buffer<C: ReservedFields>(&self, command_buffer: C) -> crate::Result<Vec<u8>> {
+        self.interface.issue(&ThisCommand {
+            command_buffer,
+            allocation_length: self.logical_block_size.saturating_mul(self.transfer_length),
+        })
+    }
+
+    fn command_buffer_10(&self) -> crate::Result<CommandBuffer10> {
         self.common_check(5, 32, 16, false, false)?;
 
-        let command_buffer = CommandBuffer10::new()
+        Ok(CommandBuffer10::new()
             .with_operation_code(OPERATION_CODE_10)
             .with_read_protect(self.read_protect)
             .with_disable_page_out(self.disable_page_out.into())
@@ -185,20 +287,13 @@ impl<'a> ReadCommand<'a> {
             .with_logical_block_address(self.logical_block_address as u32)
             .with_group_number(self.group_number)
             .with_transfer_length(self.transfer_length as u16)
-            .with_control(self.control);
-
-        let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            allocation_length,
-        })
+            .with_control(self.control))
     }
 
-    pub fn issue_12(&mut self) -> crate::Result<Vec<u8>> {
+    fn command_buffer_12(&self) -> crate::Result<CommandBuffer12> {
         self.common_check(5, 32, 32, false, false)?;
 
-        let command_buffer = CommandBuffer12::new()
+        Ok(CommandBuffer12::new()
             .with_operation_code(OPERATION_CODE_12)
             .with_read_protect(self.read_protect)
             .with_disable_page_out(self.disable_page_out.into())
@@ -207,20 +302,13 @@ impl<'a> ReadCommand<'a> {
             .with_logical_block_address(self.logical_block_address as u32)
             .with_group_number(self.group_number)
             .with_transfer_length(self.transfer_length)
-            .with_control(self.control);
-
-        let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            allocation_length,
-        })
+            .with_control(self.control))
     }
 
-    pub fn issue_16(&mut self) -> crate::Result<Vec<u8>> {
+    fn command_buffer_16(&self) -> crate::Result<CommandBuffer16> {
         self.common_check(6, 64, 32, true, false)?;
 
-        let command_buffer = CommandBuffer16::new()
+        Ok(CommandBuffer16::new()
             .with_operation_code(OPERATION_CODE_16)
             .with_read_protect(self.read_protect)
             .with_disable_page_out(self.disable_page_out.into())
@@ -232,20 +320,13 @@ impl<'a> ReadCommand<'a> {
             .with_dld_0(self.dld_0.into())
             .with_dld_1(self.dld_1.into())
             .with_dld_2(self.dld_2.into())
-            .with_control(self.control);
-
-        let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            allocation_length,
-        })
+            .with_control(self.control))
     }
 
-    pub fn issue_32(&mut self) -> crate::Result<Vec<u8>> {
+    fn command_buffer_32(&self) -> crate::Result<CommandBuffer32> {
         self.common_check(5, 64, 32, false, true)?;
 
-        let command_buffer = CommandBuffer32::new()
+        Ok(CommandBuffer32::new()
             .with_operation_code(OPERATION_CODE_32)
             .with_control(self.control)
             .with_group_number(self.group_number)
@@ -263,13 +344,40 @@ impl<'a> ReadCommand<'a> {
                 self.expected_logical_block_application_tag,
             )
             .with_logical_block_application_tag_mask(self.logical_block_application_tag_mask)
-            .with_transfer_length(self.transfer_length);
+            .with_transfer_length(self.transfer_length))
+    }
+}
 
-        let allocation_length = self.logical_block_size.saturating_mul(self.transfer_length);
+/// A READ(16) whose cdb is built once by ReadCommand::prepare_16, for loops issuing the same
+/// command many times. Only the logical block address and the transfer length can change,
+/// they are written into the cdb in place. The checks of ReadCommand ran once in prepare_16,
+/// afterwards only the total transfer bytes are checked, nothing else is validated again.
+#[derive(Clone, Debug)]
+pub struct PreparedRead16<'a> {
+    interface: &'a Scsi,
+    command_buffer: CommandBuffer16,
+    logical_block_size: u32,
+}
+
+impl PreparedRead16<'_> {
+    pub fn logical_block_address(&mut self, value: u64) -> &mut Self {
+        self.command_buffer.set_logical_block_address(value);
+        self
+    }
+
+    pub fn transfer_length(&mut self, value: u32) -> &mut Self {
+        self.command_buffer.set_transfer_length(value);
+        self
+    }
+
+    pub fn issue(&self) -> crate::Result<Vec<u8>> {
+        let allocation_length = (self.command_buffer.transfer_length() as u64)
+            .saturating_mul(self.logical_block_size as u64);
+        bitfield_bound_check!(allocation_length, 32, "total transfer bytes")?;
 
         self.interface.issue(&ThisCommand {
-            command_buffer,
-            allocation_length,
+            command_buffer: self.command_buffer,
+            allocation_length: allocation_length as u32,
         })
     }
 }
@@ -280,6 +388,33 @@ impl Scsi {
     }
 }
 
+// builds the cdb alone, without needing a Scsi, all other fields are zero
+pub fn build_cdb_10(logical_block_address: u32, transfer_length: u16) -> [u8; 10] {
+    CommandBuffer10::new()
+        .with_operation_code(OPERATION_CODE_10)
+        .with_logical_block_address(logical_block_address)
+        .with_transfer_length(transfer_length)
+        .into_bytes()
+}
+
+// builds the cdb alone, without needing a Scsi, all other fields are zero
+pub fn build_cdb_12(logical_block_address: u32, transfer_length: u32) -> [u8; 12] {
+    CommandBuffer12::new()
+        .with_operation_code(OPERATION_CODE_12)
+        .with_logical_block_address(logical_block_address)
+        .with_transfer_length(transfer_length)
+        .into_bytes()
+}
+
+// builds the cdb alone, without needing a Scsi, all other fields are zero
+pub fn build_cdb_16(logical_block_address: u64, transfer_length: u32) -> [u8; 16] {
+    CommandBuffer16::new()
+        .with_operation_code(OPERATION_CODE_16)
+        .with_logical_block_address(logical_block_address)
+        .with_transfer_length(transfer_length)
+        .into_bytes()
+}
+
 const OPERATION_CODE_10: u8 = 0x28;
 const OPERATION_CODE_12: u8 = 0xA8;
 const OPERATION_CODE_16: u8 = 0x88;
@@ -319,7 +454,7 @@ struct CommandBuffer12 {
 }
 
 #[bitfield]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 struct CommandBuffer16 {
     operation_code: B8,
     read_protect: B3,
@@ -360,12 +495,32 @@ struct CommandBuffer32 {
     transfer_length: B32,
 }
 
+reserved_fields!(
+    CommandBuffer10,
+    obsolete / with_obsolete,
+    reserved / with_reserved
+);
+reserved_fields!(
+    CommandBuffer12,
+    obsolete / with_obsolete,
+    reserved / with_reserved
+);
+reserved_fields!(CommandBuffer16, obsolete / with_obsolete);
+reserved_fields!(
+    CommandBuffer32,
+    reserved_0 / with_reserved_0,
+    reserved_1 / with_reserved_1,
+    obsolete / with_obsolete,
+    reserved_2 / with_reserved_2,
+    reserved_3 / with_reserved_3
+);
+
 struct ThisCommand<C> {
     command_buffer: C,
     allocation_length: u32,
 }
 
-impl<C: Copy> Command for ThisCommand<C> {
+impl<C: ReservedFields> Command for ThisCommand<C> {
     type CommandBuffer = C;
 
     type DataBuffer = AnyType;
@@ -379,7 +534,7 @@ impl<C: Copy> Command for ThisCommand<C> {
     }
 
     fn command(&self) -> Self::CommandBuffer {
-        self.command_buffer
+        clear_reserved_fields(self.command_buffer)
     }
 
     fn data(&self) -> Self::DataBufferWrapper {
@@ -390,11 +545,19 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.allocation_length
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(std::mem::take(result.data).0)
+        let length = result.transferred_data().len();
+        let mut data = result.into_data();
+        data.truncate(length);
+
+        Ok(data)
     }
 }
 
@@ -434,4 +597,246 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn build_cdb_test() {
+        assert_eq!(
+            build_cdb_10(0x12345678, 0x0008),
+            [0x28, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x08, 0x00],
+            "read(10)"
+        );
+
+        assert_eq!(
+            build_cdb_12(0x12345678, 0x00010000),
+            [0xA8, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00],
+            "read(12)"
+        );
+
+        assert_eq!(
+            build_cdb_16(0x0102030405060708, 0x20),
+            [
+                0x88, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x00, 0x00, 0x00, 0x20,
+                0x00, 0x00
+            ],
+            "read(16)"
+        );
+    }
+
+    #[test]
+    fn checked_setter_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.read();
+
+        assert!(command.read_protect_checked(0x07).is_ok(), "read protect");
+        assert!(command.read_protect_checked(0x08).is_err(), "read protect");
+        assert!(command.group_number_checked(0x3F).is_ok(), "group number");
+        assert!(command.group_number_checked(0x40).is_err(), "group number");
+    }
+
+    #[test]
+    fn transferred_length_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0xA5; 512]);
+
+        let data = scsi
+            .read()
+            .logical_block_size(512)
+            .transfer_length(2)
+            .issue_16()
+            .unwrap();
+
+        assert_eq!(data, [0xA5; 512], "only the transferred block");
+    }
+
+    #[test]
+    fn cache_bypass_test() {
+        let unsupported = [0x00, 0x1A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let supported = [0x00, 0x1A, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00];
+
+        let scsi = Scsi::test_instance();
+        scsi.read()
+            .transfer_length(1)
+            .cache_bypass()
+            .issue_10()
+            .unwrap();
+        scsi.read()
+            .transfer_length(1)
+            .disable_page_out(true)
+            .issue_10()
+            .unwrap();
+        scsi.read()
+            .transfer_length(1)
+            .force_unit_access(true)
+            .issue_10()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[1], 0x18, "DPO and FUA");
+        assert_eq!(issued[1].cdb[1], 0x10, "DPO");
+        assert_eq!(issued[2].cdb[1], 0x08, "FUA");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&unsupported);
+        let result = scsi
+            .read()
+            .transfer_length(1)
+            .cache_bypass()
+            .verify_dpo_fua_support(true)
+            .issue_10();
+
+        assert!(
+            matches!(result, Err(crate::Error::BadArgument(_))),
+            "unsupported"
+        );
+        assert_eq!(scsi.mock().issued().len(), 1, "only mode sense issued");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&supported);
+        scsi.read()
+            .transfer_length(1)
+            .cache_bypass()
+            .verify_dpo_fua_support(true)
+            .issue_10()
+            .unwrap();
+        scsi.read()
+            .transfer_length(1)
+            .verify_dpo_fua_support(true)
+            .issue_10()
+            .unwrap();
+
+        let operation_codes: Vec<u8> = scsi
+            .mock()
+            .issued()
+            .iter()
+            .map(|command| command.cdb[0])
+            .collect();
+        assert_eq!(
+            operation_codes,
+            [0x5A, 0x28, 0x28],
+            "no check without DPO and FUA"
+        );
+
+        let scsi = Scsi::test_instance();
+        scsi.read()
+            .transfer_length(1)
+            .cache_bypass()
+            .verify_dpo_fua_support(true)
+            .build_cdb_10()
+            .unwrap();
+        assert!(scsi.mock().issued().is_empty(), "build_cdb sends nothing");
+    }
+
+    #[test]
+    fn prepare_16_test() {
+        let scsi = Scsi::test_instance();
+        let mut prepared = scsi
+            .read()
+            .force_unit_access(true)
+            .transfer_length(1)
+            .prepare_16()
+            .unwrap();
+
+        scsi.mock().push_data(&[0xA5; 512]);
+        scsi.mock().push_data(&[0x5A; 1024]);
+        assert_eq!(
+            prepared.logical_block_address(0x10).issue().unwrap(),
+            [0xA5; 512],
+            "first read"
+        );
+        assert_eq!(
+            prepared
+                .logical_block_address(0x1_0000_0000)
+                .transfer_length(2)
+                .issue()
+                .unwrap(),
+            [0x5A; 1024],
+            "second read"
+        );
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [0x88, 0x08, 0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 1, 0, 0],
+            "first cdb"
+        );
+        assert_eq!(
+            issued[1].cdb,
+            [0x88, 0x08, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0],
+            "patched cdb"
+        );
+
+        assert!(
+            prepared.transfer_length(u32::MAX).issue().is_err(),
+            "total transfer bytes"
+        );
+        assert!(
+            scsi.read().read_protect(0x08).prepare_16().is_err(),
+            "checked once"
+        );
+    }
+
+    #[test]
+    fn reserved_fields_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0; 512]);
+        scsi.mock().push_data(&[0; 512]);
+
+        let mut command = scsi.read();
+        command
+            .read_protect(0x07)
+            .disable_page_out(true)
+            .force_unit_access(true)
+            .rebuild_assist_recovery_control(true)
+            .group_number(0x1F)
+            .logical_block_address(u32::MAX.into())
+            .transfer_length(1)
+            .logical_block_size(512);
+        command.issue_10().unwrap();
+        command
+            .dld_0(true)
+            .dld_1(true)
+            .dld_2(true)
+            .issue_16()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[1] & 0x03, 0, "read(10) obsolete");
+        assert_eq!(issued[0].cdb[6] & 0xE0, 0, "read(10) reserved");
+        assert_eq!(issued[1].cdb[1] & 0x02, 0, "read(16) obsolete");
+    }
+
+    #[test]
+    #[should_panic(expected = "reserved cdb fields should be zero")]
+    fn reserved_fields_assert_test() {
+        clear_reserved_fields(CommandBuffer10::new().with_obsolete(0x03));
+    }
+
+    #[test]
+    fn builder_build_cdb_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.read();
+        command
+            .logical_block_address(0x12345678)
+            .group_number(0x05)
+            .transfer_length(2);
+
+        assert_eq!(
+            command.build_cdb_10().unwrap(),
+            [0x28, 0x00, 0x12, 0x34, 0x56, 0x78, 0x05, 0x00, 0x02, 0x00],
+            "read(10)"
+        );
+        assert_eq!(
+            command.build_cdb_16().unwrap(),
+            [0x88, 0x00, 0, 0, 0, 0, 0x12, 0x34, 0x56, 0x78, 0, 0, 0, 0x02, 0x05, 0x00],
+            "read(16)"
+        );
+        assert!(
+            matches!(
+                command.group_number(0x40).build_cdb_10(),
+                Err(crate::Error::ArgumentOutOfBounds(_))
+            ),
+            "checks still run"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
 }
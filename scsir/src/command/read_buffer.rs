@@ -29,7 +29,7 @@ impl<'a> ReadBufferCommand<'a> {
             buffer_offset: 0,
             allocation_length: 0,
             buffer_id: 0,
-            control: 0,
+            control: interface.default_control(),
         }
     }
 
@@ -127,6 +127,9 @@ impl Scsi {
     }
 }
 
+pub const ECHO_BUFFER_MODE: u8 = 0x0A;
+pub const ECHO_BUFFER_DESCRIPTOR_MODE: u8 = 0x0B;
+
 const OPERATION_CODE_10: u8 = 0x3C;
 const OPERATION_CODE_16: u8 = 0x9B;
 
@@ -184,11 +187,15 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.allocation_length
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(std::mem::take(result.data).0)
+        Ok(result.into_data())
     }
 }
 
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use std::{marker::PhantomData, mem::size_of};
+use std::{fmt::Display, marker::PhantomData, mem::size_of};
 
 use modular_bitfield_msb::prelude::*;
 
@@ -22,6 +22,7 @@ pub struct ReadCapacity10Result {
 pub struct ReadCapacity16Result {
     pub returned_logical_block_address: u64,
     pub logical_block_length_in_bytes: u32,
+    // see capacity_basis
     pub read_capacity_basis: u8,
     pub protection_type: u8,
     pub protection_enabled: bool,
@@ -32,11 +33,115 @@ pub struct ReadCapacity16Result {
     pub lowest_aligned_logical_block_address: u16,
 }
 
+// what RETURNED LOGICAL BLOCK ADDRESS of READ CAPACITY(16) covers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CapacityBasis {
+    // last lba of the conventional zones at the start of a host managed zoned device,
+    // the sequential write required zones behind them are not counted
+    ConventionalZones,
+    // last lba of the whole logical unit
+    LogicalUnit,
+    Reserved(u8),
+}
+
+impl ReadCapacity16Result {
+    pub fn capacity_basis(&self) -> CapacityBasis {
+        match self.read_capacity_basis {
+            0b00 => CapacityBasis::ConventionalZones,
+            0b01 => CapacityBasis::LogicalUnit,
+            other => CapacityBasis::Reserved(other),
+        }
+    }
+
+    pub fn physical_block_size(&self) -> u32 {
+        self.logical_block_length_in_bytes
+            .checked_shl(self.logical_blocks_per_physical_block_exponent.into())
+            .unwrap_or(0)
+    }
+
+    // offset of the first physical block boundary from logical block 0
+    pub fn alignment_offset_bytes(&self) -> u32 {
+        (self.lowest_aligned_logical_block_address as u32)
+            .saturating_mul(self.logical_block_length_in_bytes)
+    }
+}
+
+// laid out like the output of sg_readcap
+impl Display for ReadCapacity10Result {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Read Capacity results:")?;
+        write_capacity(
+            f,
+            self.returned_logical_block_address as u64,
+            self.block_length_in_bytes,
+        )
+    }
+}
+
+// laid out like the output of sg_readcap --16
+impl Display for ReadCapacity16Result {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Read Capacity results:")?;
+        writeln!(
+            f,
+            "   Protection: prot_en={}, p_type={}, p_i_exponent={}",
+            self.protection_enabled as u8, self.protection_type, self.p_i_exponent
+        )?;
+        writeln!(
+            f,
+            "   Logical block provisioning: lbpme={}, lbprz={}",
+            self.logical_block_provisioning_management_enabled as u8,
+            self.logical_block_provisioning_read_zeros as u8
+        )?;
+        writeln!(
+            f,
+            "   Logical blocks per physical block exponent={} [so physical block length={} bytes]",
+            self.logical_blocks_per_physical_block_exponent,
+            self.physical_block_size()
+        )?;
+        writeln!(
+            f,
+            "   Lowest aligned LBA={}",
+            self.lowest_aligned_logical_block_address
+        )?;
+        write_capacity(
+            f,
+            self.returned_logical_block_address,
+            self.logical_block_length_in_bytes,
+        )
+    }
+}
+
+fn write_capacity(
+    f: &mut std::fmt::Formatter<'_>,
+    last_logical_block_address: u64,
+    logical_block_length: u32,
+) -> std::fmt::Result {
+    let blocks = last_logical_block_address as u128 + 1;
+    let bytes = blocks * logical_block_length as u128;
+
+    writeln!(
+        f,
+        "   Last LBA={} (0x{:x}), Number of logical blocks={}",
+        last_logical_block_address, last_logical_block_address, blocks
+    )?;
+    writeln!(f, "   Logical block length={} bytes", logical_block_length)?;
+    writeln!(f, "Hence:")?;
+    write!(
+        f,
+        "   Device size: {} bytes, {:.1} MiB, {:.2} GB",
+        bytes,
+        bytes as f64 / (1024.0 * 1024.0),
+        bytes as f64 / 1_000_000_000.0
+    )
+}
+
 impl<'a> ReadCapacityCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
         }
     }
 
@@ -96,6 +201,43 @@ impl Scsi {
     pub fn read_capacity(&self) -> ReadCapacityCommand<'_> {
         ReadCapacityCommand::new(self)
     }
+
+    /// Issues READ CAPACITY(16) and remembers the logical block length,
+    /// which the block command builders then use unless set_logical_block_size is set.
+    pub fn cache_block_size(&self) -> crate::Result<u32> {
+        let block_size = self
+            .read_capacity()
+            .issue_16()?
+            .logical_block_length_in_bytes;
+        self.set_cached_block_size(block_size);
+
+        Ok(block_size)
+    }
+
+    /// Returns the cached logical block length, querying the device with
+    /// detect_block_size on first use.
+    pub fn block_size(&self) -> crate::Result<u32> {
+        match self.cached_block_size() {
+            Some(block_size) => Ok(block_size),
+            None => self.detect_block_size(),
+        }
+    }
+}
+
+// builds the cdb alone, without needing a Scsi
+pub fn build_cdb_10() -> [u8; 10] {
+    CommandBuffer10::new()
+        .with_operation_code(OPERATION_CODE_10)
+        .into_bytes()
+}
+
+// builds the cdb alone, without needing a Scsi, the returned data is 32 bytes long
+pub fn build_cdb_16() -> [u8; 16] {
+    CommandBuffer16::new()
+        .with_operation_code(OPERATION_CODE_16)
+        .with_service_action(SERVICE_ACTION_16)
+        .with_allocation_length(size_of::<DataBuffer16>() as u32)
+        .into_bytes()
 }
 
 const OPERATION_CODE_10: u8 = 0x25;
@@ -223,4 +365,150 @@ mod tests {
             concat!("Size of: ", stringify!(DataBuffer16))
         );
     }
+
+    #[test]
+    fn build_cdb_test() {
+        assert_eq!(
+            build_cdb_10(),
+            [0x25, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            "read capacity(10)"
+        );
+
+        assert_eq!(
+            build_cdb_16(),
+            [0x9E, 0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x20, 0, 0],
+            "read capacity(16)"
+        );
+    }
+
+    #[test]
+    fn block_size_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock()
+            .push_data(&[0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0x00, 0x00, 0x10, 0x00]);
+
+        assert_eq!(scsi.block_size().unwrap(), 4096, "queried block size");
+        assert_eq!(scsi.block_size().unwrap(), 4096, "cached block size");
+        assert_eq!(scsi.mock().issued().len(), 1, "issued commands");
+
+        scsi.mock().push_data(&[0xAA; 8192]);
+        let data = scsi.read().transfer_length(2).issue_16().unwrap();
+        assert_eq!(data.len(), 8192, "read allocation length");
+    }
+
+    #[test]
+    fn derived_size_test() {
+        let mut result = ReadCapacity16Result {
+            returned_logical_block_address: 0,
+            logical_block_length_in_bytes: 512,
+            read_capacity_basis: 0,
+            protection_type: 0,
+            protection_enabled: false,
+            p_i_exponent: 0,
+            logical_blocks_per_physical_block_exponent: 0,
+            logical_block_provisioning_management_enabled: false,
+            logical_block_provisioning_read_zeros: false,
+            lowest_aligned_logical_block_address: 0,
+        };
+
+        assert_eq!(result.physical_block_size(), 512, "exponent zero");
+        assert_eq!(result.alignment_offset_bytes(), 0, "aligned");
+
+        result.logical_blocks_per_physical_block_exponent = 3;
+        result.lowest_aligned_logical_block_address = 7;
+        assert_eq!(result.physical_block_size(), 4096, "exponent three");
+        assert_eq!(
+            result.alignment_offset_bytes(),
+            3584,
+            "offset by seven blocks"
+        );
+    }
+
+    #[test]
+    fn capacity_basis_test() {
+        let mut capacity = ReadCapacity16Result {
+            returned_logical_block_address: 0x3FF,
+            logical_block_length_in_bytes: 512,
+            read_capacity_basis: 0b01,
+            protection_type: 0,
+            protection_enabled: false,
+            p_i_exponent: 0,
+            logical_blocks_per_physical_block_exponent: 0,
+            logical_block_provisioning_management_enabled: false,
+            logical_block_provisioning_read_zeros: false,
+            lowest_aligned_logical_block_address: 0,
+        };
+        assert_eq!(
+            capacity.capacity_basis(),
+            CapacityBasis::LogicalUnit,
+            "logical unit"
+        );
+
+        capacity.read_capacity_basis = 0b00;
+        assert_eq!(
+            capacity.capacity_basis(),
+            CapacityBasis::ConventionalZones,
+            "conventional zones"
+        );
+
+        capacity.read_capacity_basis = 0b10;
+        assert_eq!(
+            capacity.capacity_basis(),
+            CapacityBasis::Reserved(0b10),
+            "reserved"
+        );
+    }
+
+    #[test]
+    fn display_test() {
+        let capacity = ReadCapacity10Result {
+            returned_logical_block_address: 0x74706DAF,
+            block_length_in_bytes: 512,
+        }
+        .to_string();
+        let lines: Vec<&str> = capacity.lines().collect();
+
+        assert_eq!(lines[0], "Read Capacity results:", "title");
+        assert_eq!(
+            lines[1], "   Last LBA=1953525167 (0x74706daf), Number of logical blocks=1953525168",
+            "last lba"
+        );
+        assert_eq!(
+            lines[2], "   Logical block length=512 bytes",
+            "block length"
+        );
+        assert_eq!(
+            lines[4], "   Device size: 1000204886016 bytes, 953869.7 MiB, 1000.20 GB",
+            "device size"
+        );
+
+        let capacity = ReadCapacity16Result {
+            returned_logical_block_address: 0x3FF,
+            logical_block_length_in_bytes: 512,
+            read_capacity_basis: 0,
+            protection_type: 0,
+            protection_enabled: false,
+            p_i_exponent: 0,
+            logical_blocks_per_physical_block_exponent: 3,
+            logical_block_provisioning_management_enabled: true,
+            logical_block_provisioning_read_zeros: true,
+            lowest_aligned_logical_block_address: 0,
+        }
+        .to_string();
+        let lines: Vec<&str> = capacity.lines().collect();
+
+        assert_eq!(
+            lines[2], "   Logical block provisioning: lbpme=1, lbprz=1",
+            "provisioning"
+        );
+        assert_eq!(
+            lines[3],
+            "   Logical blocks per physical block exponent=3 [so physical block length=4096 bytes]",
+            "physical block"
+        );
+        assert_eq!(
+            lines[8], "   Device size: 524288 bytes, 0.5 MiB, 0.00 GB",
+            "device size"
+        );
+    }
 }
@@ -5,7 +5,7 @@ use std::{marker::PhantomData, mem::size_of};
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::{bitfield_bound_check, get_array},
+    command::{bitfield_bound_check, format_unit, try_get_array},
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -81,6 +81,177 @@ pub struct PhysicalSectorFormatAddressDescriptor {
     pub sector_number: u32,
 }
 
+impl DefectList {
+    // a trailing partial descriptor is an error
+    pub(crate) fn from_bytes(defect_list_format: u8, bytes: &[u8]) -> crate::Result<Self> {
+        Ok(match defect_list_format {
+            0b0000 => DefectList::ShortBlockFormat(decode_descriptors(bytes)?),
+            0b0001 => DefectList::ExtendedBytesFromIndex(decode_descriptors(bytes)?),
+            0b0010 => DefectList::ExtendedPhysicalSector(decode_descriptors(bytes)?),
+            0b0011 => DefectList::LongBlockFormat(decode_descriptors(bytes)?),
+            0b0100 => DefectList::BytesFromIndexFormat(decode_descriptors(bytes)?),
+            0b0101 => DefectList::PhysicalSectorFormat(decode_descriptors(bytes)?),
+            _ => DefectList::Custom(bytes.to_vec()),
+        })
+    }
+
+    /// Defect lbas of the short and long block formats. The cylinder/head based formats
+    /// and Custom have no lba and give an empty list.
+    pub fn logical_block_addresses(&self) -> Vec<u64> {
+        match self {
+            DefectList::ShortBlockFormat(v) => {
+                v.iter().map(|d| d.short_block_address as u64).collect()
+            }
+            DefectList::LongBlockFormat(v) => v.iter().map(|d| d.long_block_address).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Number of descriptors, or of bytes for Custom.
+    pub fn len(&self) -> usize {
+        match self {
+            DefectList::ShortBlockFormat(v) => v.len(),
+            DefectList::ExtendedBytesFromIndex(v) => v.len(),
+            DefectList::ExtendedPhysicalSector(v) => v.len(),
+            DefectList::LongBlockFormat(v) => v.len(),
+            DefectList::BytesFromIndexFormat(v) => v.len(),
+            DefectList::PhysicalSectorFormat(v) => v.len(),
+            DefectList::Custom(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// splits bytes into descriptors of T's raw size, a trailing partial descriptor is an error
+fn decode_descriptors<T>(bytes: &[u8]) -> crate::Result<Vec<T>>
+where
+    T: DefectDescriptor + for<'b> TryFrom<&'b [u8], Error = crate::Error>,
+{
+    bytes.chunks(T::LENGTH).map(T::try_from).collect()
+}
+
+trait DefectDescriptor {
+    const LENGTH: usize;
+}
+
+impl DefectDescriptor for ShortBlockFormatAddressDescriptor {
+    const LENGTH: usize = size_of::<format_unit::ShortBlockFormatAddressDescriptor>();
+}
+
+impl DefectDescriptor for ExtendedBytesFromIndexAddressDescriptor {
+    const LENGTH: usize = size_of::<format_unit::ExtendedBytesFromIndexAddressDescriptor>();
+}
+
+impl DefectDescriptor for ExtendedPhysicalSectorAddressDescriptor {
+    const LENGTH: usize = size_of::<format_unit::ExtendedPhysicalSectorAddressDescriptor>();
+}
+
+impl DefectDescriptor for LongBlockFormatAddressDescriptor {
+    const LENGTH: usize = size_of::<format_unit::LongBlockFormatAddressDescriptor>();
+}
+
+impl DefectDescriptor for BytesFromIndexFormatAddressDescriptor {
+    const LENGTH: usize = size_of::<format_unit::BytesFromIndexFormatAddressDescriptor>();
+}
+
+impl DefectDescriptor for PhysicalSectorFormatAddressDescriptor {
+    const LENGTH: usize = size_of::<format_unit::PhysicalSectorFormatAddressDescriptor>();
+}
+
+// the TryFrom impls decode one descriptor from the start of bytes, fewer bytes than the
+// descriptor is a BadArgument
+
+impl TryFrom<&[u8]> for ShortBlockFormatAddressDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let (bytes, _) = try_get_array(bytes)?;
+        let raw = format_unit::ShortBlockFormatAddressDescriptor::from_bytes(bytes);
+
+        Ok(Self {
+            short_block_address: raw.short_block_address(),
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for ExtendedBytesFromIndexAddressDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let (bytes, _) = try_get_array(bytes)?;
+        let raw = format_unit::ExtendedBytesFromIndexAddressDescriptor::from_bytes(bytes);
+
+        Ok(Self {
+            cylinder_number: raw.cylinder_number(),
+            head_number: raw.head_number(),
+            multi_address_descriptor_start: raw.multi_address_descriptor_start() != 0,
+            bytes_from_index: raw.bytes_from_index(),
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for ExtendedPhysicalSectorAddressDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let (bytes, _) = try_get_array(bytes)?;
+        let raw = format_unit::ExtendedPhysicalSectorAddressDescriptor::from_bytes(bytes);
+
+        Ok(Self {
+            cylinder_number: raw.cylinder_number(),
+            head_number: raw.head_number(),
+            multi_address_descriptor_start: raw.multi_address_descriptor_start() != 0,
+            sector_number: raw.sector_number(),
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for LongBlockFormatAddressDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let (bytes, _) = try_get_array(bytes)?;
+        let raw = format_unit::LongBlockFormatAddressDescriptor::from_bytes(bytes);
+
+        Ok(Self {
+            long_block_address: raw.long_block_address(),
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for BytesFromIndexFormatAddressDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let (bytes, _) = try_get_array(bytes)?;
+        let raw = format_unit::BytesFromIndexFormatAddressDescriptor::from_bytes(bytes);
+
+        Ok(Self {
+            cylinder_number: raw.cylinder_number(),
+            head_number: raw.head_number(),
+            bytes_from_index: raw.bytes_from_index(),
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for PhysicalSectorFormatAddressDescriptor {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> crate::Result<Self> {
+        let (bytes, _) = try_get_array(bytes)?;
+        let raw = format_unit::PhysicalSectorFormatAddressDescriptor::from_bytes(bytes);
+
+        Ok(Self {
+            cylinder_number: raw.cylinder_number(),
+            head_number: raw.head_number(),
+            sector_number: raw.sector_number(),
+        })
+    }
+}
+
 impl<'a> ReadDefectDataCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
@@ -90,7 +261,7 @@ impl<'a> ReadDefectDataCommand<'a> {
             defect_list_format: 0,
             address_descriptor_index: 0,
             descriptor_length: 0,
-            control: 0,
+            control: interface.default_control(),
         }
     }
 
@@ -155,12 +326,12 @@ impl<'a> ReadDefectDataCommand<'a> {
 
     fn get_defect_list_item_size(&self) -> usize {
         match self.defect_list_format {
-            0b0000 => size_of::<super::format_unit::ShortBlockFormatAddressDescriptor>(),
-            0b0001 => size_of::<super::format_unit::ExtendedBytesFromIndexAddressDescriptor>(),
-            0b0010 => size_of::<super::format_unit::ExtendedPhysicalSectorAddressDescriptor>(),
-            0b0011 => size_of::<super::format_unit::LongBlockFormatAddressDescriptor>(),
-            0b0100 => size_of::<super::format_unit::BytesFromIndexFormatAddressDescriptor>(),
-            0b0101 => size_of::<super::format_unit::PhysicalSectorFormatAddressDescriptor>(),
+            0b0000 => size_of::<format_unit::ShortBlockFormatAddressDescriptor>(),
+            0b0001 => size_of::<format_unit::ExtendedBytesFromIndexAddressDescriptor>(),
+            0b0010 => size_of::<format_unit::ExtendedPhysicalSectorAddressDescriptor>(),
+            0b0011 => size_of::<format_unit::LongBlockFormatAddressDescriptor>(),
+            0b0100 => size_of::<format_unit::BytesFromIndexFormatAddressDescriptor>(),
+            0b0101 => size_of::<format_unit::PhysicalSectorFormatAddressDescriptor>(),
             _ => size_of::<u8>(),
         }
     }
@@ -322,114 +493,27 @@ impl<C: Copy, Body: Copy> Command for ThisCommand<C, Body> {
         (self.extra_allocation_length + size_of::<Body>()) as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        let mut defect_list = match self.defect_list_format {
-            0b0000 => DefectList::ShortBlockFormat(vec![]),
-            0b0001 => DefectList::ExtendedBytesFromIndex(vec![]),
-            0b0010 => DefectList::ExtendedPhysicalSector(vec![]),
-            0b0011 => DefectList::LongBlockFormat(vec![]),
-            0b0100 => DefectList::BytesFromIndexFormat(vec![]),
-            0b0101 => DefectList::PhysicalSectorFormat(vec![]),
-            _ => DefectList::Custom(vec![]),
-        };
-
-        match &mut defect_list {
-            DefectList::ShortBlockFormat(v) => {
-                for chunk in unsafe { result.data.elements_as_slice() }.chunks(size_of::<
-                    super::format_unit::ShortBlockFormatAddressDescriptor,
-                >()) {
-                    let (bytes, _) = get_array(chunk);
-                    let raw =
-                        super::format_unit::ShortBlockFormatAddressDescriptor::from_bytes(bytes);
-                    v.push(ShortBlockFormatAddressDescriptor {
-                        short_block_address: raw.short_block_address(),
-                    });
-                }
-            }
-            DefectList::ExtendedBytesFromIndex(v) => {
-                for chunk in unsafe { result.data.elements_as_slice() }.chunks(size_of::<
-                    super::format_unit::ExtendedBytesFromIndexAddressDescriptor,
-                >()) {
-                    let (bytes, _) = get_array(chunk);
-                    let raw =
-                        super::format_unit::ExtendedBytesFromIndexAddressDescriptor::from_bytes(
-                            bytes,
-                        );
-                    v.push(ExtendedBytesFromIndexAddressDescriptor {
-                        cylinder_number: raw.cylinder_number(),
-                        head_number: raw.head_number(),
-                        multi_address_descriptor_start: raw.multi_address_descriptor_start() != 0,
-                        bytes_from_index: raw.bytes_from_index(),
-                    });
-                }
-            }
-            DefectList::ExtendedPhysicalSector(v) => {
-                for chunk in unsafe { result.data.elements_as_slice() }.chunks(size_of::<
-                    super::format_unit::ExtendedPhysicalSectorAddressDescriptor,
-                >()) {
-                    let (bytes, _) = get_array(chunk);
-                    let raw =
-                        super::format_unit::ExtendedPhysicalSectorAddressDescriptor::from_bytes(
-                            bytes,
-                        );
-                    v.push(ExtendedPhysicalSectorAddressDescriptor {
-                        cylinder_number: raw.cylinder_number(),
-                        head_number: raw.head_number(),
-                        multi_address_descriptor_start: raw.multi_address_descriptor_start() != 0,
-                        sector_number: raw.sector_number(),
-                    });
-                }
-            }
-            DefectList::LongBlockFormat(v) => {
-                for chunk in unsafe { result.data.elements_as_slice() }.chunks(size_of::<
-                    super::format_unit::LongBlockFormatAddressDescriptor,
-                >()) {
-                    let (bytes, _) = get_array(chunk);
-                    let raw =
-                        super::format_unit::LongBlockFormatAddressDescriptor::from_bytes(bytes);
-                    v.push(LongBlockFormatAddressDescriptor {
-                        long_block_address: raw.long_block_address(),
-                    });
-                }
-            }
-            DefectList::BytesFromIndexFormat(v) => {
-                for chunk in unsafe { result.data.elements_as_slice() }.chunks(size_of::<
-                    super::format_unit::BytesFromIndexFormatAddressDescriptor,
-                >()) {
-                    let (bytes, _) = get_array(chunk);
-                    let raw = super::format_unit::BytesFromIndexFormatAddressDescriptor::from_bytes(
-                        bytes,
-                    );
-                    v.push(BytesFromIndexFormatAddressDescriptor {
-                        cylinder_number: raw.cylinder_number(),
-                        head_number: raw.head_number(),
-                        bytes_from_index: raw.bytes_from_index(),
-                    });
-                }
-            }
-            DefectList::PhysicalSectorFormat(v) => {
-                for chunk in unsafe { result.data.elements_as_slice() }.chunks(size_of::<
-                    super::format_unit::PhysicalSectorFormatAddressDescriptor,
-                >()) {
-                    let (bytes, _) = get_array(chunk);
-                    let raw = super::format_unit::PhysicalSectorFormatAddressDescriptor::from_bytes(
-                        bytes,
-                    );
-                    v.push(PhysicalSectorFormatAddressDescriptor {
-                        cylinder_number: raw.cylinder_number(),
-                        head_number: raw.head_number(),
-                        sector_number: raw.sector_number(),
-                    });
-                }
-            }
-            DefectList::Custom(v) => {
-                v.extend_from_slice(unsafe { result.data.elements_as_slice() });
-            }
+        let transferred = result.transferred_data().len();
+        if transferred < size_of::<Body>() {
+            return Err(crate::Error::BadArgument(format!(
+                "short response, {} bytes were transferred but the header is {} bytes.",
+                transferred,
+                size_of::<Body>()
+            )));
         }
 
+        let defect_list = DefectList::from_bytes(self.defect_list_format, unsafe {
+            &result.data.elements_as_slice()[..transferred - size_of::<Body>()]
+        })?;
+
         Ok((
             unsafe { result.data.get_body_maybe_uninit().assume_init() },
             defect_list,
@@ -473,4 +557,121 @@ mod tests {
             concat!("Size of: ", stringify!(DataBufferHeader12))
         );
     }
+
+    #[test]
+    fn logical_block_addresses_test() {
+        let short =
+            DefectList::from_bytes(0b0000, &[0x00, 0x00, 0x10, 0x00, 0xFF, 0xFF, 0xFF, 0xFF])
+                .unwrap();
+        assert_eq!(short.len(), 2, "short block length");
+        assert_eq!(
+            short.logical_block_addresses(),
+            [0x1000, 0xFFFF_FFFF],
+            "short block lbas"
+        );
+
+        let long =
+            DefectList::from_bytes(0b0011, &[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02])
+                .unwrap();
+        assert_eq!(long.len(), 1, "long block length");
+        assert_eq!(
+            long.logical_block_addresses(),
+            [0x1_0000_0002],
+            "long block lbas"
+        );
+
+        for format in [0b0001, 0b0010, 0b0100, 0b0101] {
+            let list =
+                DefectList::from_bytes(format, &[0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x03])
+                    .unwrap();
+            assert_eq!(list.len(), 1, "format {} length", format);
+            assert!(
+                list.logical_block_addresses().is_empty(),
+                "format {} lbas",
+                format
+            );
+        }
+
+        let custom = DefectList::from_bytes(0b0111, &[0x01, 0x02, 0x03]).unwrap();
+        assert_eq!(custom.len(), 3, "custom length");
+        assert!(custom.logical_block_addresses().is_empty(), "custom lbas");
+
+        let empty = DefectList::from_bytes(0b0011, &[]).unwrap();
+        assert!(empty.is_empty(), "empty");
+    }
+
+    #[test]
+    fn short_response_test() {
+        assert!(
+            matches!(
+                DefectList::from_bytes(0b0011, &[0x00, 0x00, 0x00, 0x01, 0x00]),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "partial descriptor"
+        );
+
+        let scsi = Scsi::test_instance();
+        scsi.mock()
+            .push_data(&[0x00, 0x1B, 0x00, 0x08, 0x00, 0x00, 0x00, 0x10, 0x00]);
+        let result = scsi
+            .read_defect_data()
+            .request_grown_defect_list(true)
+            .defect_list_format(0b0011)
+            .descriptor_length(2)
+            .issue_10();
+        assert!(
+            matches!(result, Err(crate::Error::BadArgument(_))),
+            "truncated transfer"
+        );
+
+        scsi.mock().push_data(&[
+            0x00, 0x1B, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+        ]);
+        let result = scsi
+            .read_defect_data()
+            .request_grown_defect_list(true)
+            .defect_list_format(0b0011)
+            .descriptor_length(2)
+            .issue_10()
+            .unwrap();
+        assert_eq!(
+            result.descriptors.logical_block_addresses(),
+            [0x10],
+            "transferred only"
+        );
+    }
+
+    #[test]
+    fn descriptor_try_from_test() {
+        let long = LongBlockFormatAddressDescriptor::try_from(
+            &[0x00, 0x00, 0x00, 0x01, 0x23, 0x45, 0x67, 0x89][..],
+        )
+        .unwrap();
+        assert_eq!(long.long_block_address, 0x1_2345_6789, "long block format");
+
+        let extended = ExtendedBytesFromIndexAddressDescriptor::try_from(
+            &[0x01, 0x02, 0x03, 0x04, 0x80, 0x00, 0x12, 0x34][..],
+        )
+        .unwrap();
+        assert_eq!(extended.cylinder_number, 0x010203, "cylinder number");
+        assert_eq!(extended.head_number, 0x04, "head number");
+        assert!(extended.multi_address_descriptor_start, "mads");
+        assert_eq!(extended.bytes_from_index, 0x1234, "bytes from index");
+
+        let physical = PhysicalSectorFormatAddressDescriptor::try_from(
+            &[0x00, 0x00, 0x10, 0x02, 0x00, 0x00, 0x00, 0x07][..],
+        )
+        .unwrap();
+        assert_eq!(physical.cylinder_number, 0x10, "physical cylinder number");
+        assert_eq!(physical.head_number, 0x02, "physical head number");
+        assert_eq!(physical.sector_number, 0x07, "sector number");
+
+        assert!(
+            matches!(
+                ShortBlockFormatAddressDescriptor::try_from(&[0x00, 0x01][..]),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short descriptor"
+        );
+    }
 }
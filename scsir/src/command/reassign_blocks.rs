@@ -5,7 +5,10 @@ use std::mem::size_of;
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{
+        bitfield_bound_check,
+        sense::{SenseData, SenseKey},
+    },
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -29,7 +32,9 @@ impl<'a> ReassignBlocksCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -47,6 +52,19 @@ impl<'a> ReassignBlocksCommand<'a> {
         self.interface.issue(&ThisCommand {
             command_buffer: self.command_buffer,
             data_buffer: self.data_buffer.clone().into(),
+            detailed: false,
+        })
+    }
+
+    // like issue, but a CHECK CONDITION with MEDIUM ERROR, HARDWARE ERROR or an ASC of 0x32
+    // (no defect spare location, defect list update failure) is a ReassignStopped error
+    // carrying the first lba of the list that was not reassigned, the lbas before it in the
+    // list were reassigned. any other sense stays a CheckCondition
+    pub fn issue_detailed(&mut self) -> crate::Result<()> {
+        self.interface.issue(&ThisCommand {
+            command_buffer: self.command_buffer,
+            data_buffer: self.data_buffer.clone().into(),
+            detailed: true,
         })
     }
 }
@@ -109,7 +127,28 @@ impl Scsi {
     }
 }
 
+// whether the sense reports a reassignment that stopped partway through the list
+fn is_reassign_stopped(sense: &SenseData) -> bool {
+    matches!(
+        sense.sense_key(),
+        Some(SenseKey::MediumError | SenseKey::HardwareError)
+    ) || sense.additional_sense_code().map(|code| code >> 8) == Some(DEFECT_LIST_ASC)
+}
+
+// all ones means the device does not know where it stopped
+fn first_not_reassigned(sense: &SenseData) -> Option<u64> {
+    let unknown = match sense {
+        SenseData::Fixed(_) => u32::MAX as u64,
+        _ => u64::MAX,
+    };
+
+    sense
+        .command_specific_information()
+        .filter(|lba| *lba != unknown)
+}
+
 const OPERATION_CODE: u8 = 0x07;
+const DEFECT_LIST_ASC: u16 = 0x32;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -125,6 +164,7 @@ struct CommandBuffer {
 struct ThisCommand {
     command_buffer: CommandBuffer,
     data_buffer: VecBufferWrapper,
+    detailed: bool,
 }
 
 impl Command for ThisCommand {
@@ -152,11 +192,31 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
-        result.check_common_error()?;
 
-        Ok(())
+        match result.check_common_error() {
+            Err(crate::Error::CheckCondition(sense))
+                if self.detailed
+                    && sense
+                        .downcast_ref::<SenseData>()
+                        .is_some_and(is_reassign_stopped) =>
+            {
+                let first_not_reassigned = sense
+                    .downcast_ref::<SenseData>()
+                    .and_then(first_not_reassigned);
+
+                Err(crate::Error::ReassignStopped {
+                    first_not_reassigned,
+                    sense,
+                })
+            }
+            result => result,
+        }
     }
 }
 
@@ -175,4 +235,84 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn issue_detailed_test() {
+        // MEDIUM ERROR, NO DEFECT SPARE LOCATION AVAILABLE
+        let mut sense = [0; 18];
+        sense[0] = 0x70;
+        sense[2] = 0x03;
+        sense[7] = 0x0A;
+        sense[8..12].copy_from_slice(&0x20u32.to_be_bytes());
+        sense[12] = 0x32;
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&sense);
+        let result = scsi.reassign(&[0x10, 0x20, 0x30]);
+        assert!(
+            matches!(
+                result,
+                Err(crate::Error::ReassignStopped {
+                    first_not_reassigned: Some(0x20),
+                    ..
+                })
+            ),
+            "stopped at 0x20"
+        );
+        assert!(result.unwrap_err().sense_data().is_some(), "sense kept");
+
+        sense[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        scsi.mock().push_sense(&sense);
+        assert!(
+            matches!(
+                scsi.reassign(&[0x10]),
+                Err(crate::Error::ReassignStopped {
+                    first_not_reassigned: None,
+                    ..
+                })
+            ),
+            "unknown stop lba"
+        );
+
+        scsi.mock().push_sense(&sense);
+        let mut command = scsi.reassign_blocks();
+        let mut parameter = command.parameter();
+        parameter.short_lba_list(&[0x10]);
+        assert!(
+            matches!(
+                parameter.done().unwrap().issue(),
+                Err(crate::Error::CheckCondition(_))
+            ),
+            "plain issue"
+        );
+    }
+
+    #[test]
+    fn issue_detailed_other_sense_test() {
+        // ILLEGAL REQUEST, INVALID FIELD IN PARAMETER LIST
+        let mut sense = [0; 18];
+        sense[0] = 0x70;
+        sense[2] = 0x05;
+        sense[7] = 0x0A;
+        sense[12] = 0x26;
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&sense);
+        assert!(
+            matches!(scsi.reassign(&[0x10]), Err(crate::Error::CheckCondition(_))),
+            "illegal request passed through"
+        );
+
+        // ASC 0x32 with another sense key
+        sense[12] = 0x32;
+        sense[13] = 0x01;
+        scsi.mock().push_sense(&sense);
+        assert!(
+            matches!(
+                scsi.reassign(&[0x10]),
+                Err(crate::Error::ReassignStopped { .. })
+            ),
+            "defect list update failure"
+        );
+    }
 }
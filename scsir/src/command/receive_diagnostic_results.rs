@@ -3,6 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
+    command::SizedDataIn,
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -18,7 +19,9 @@ impl<'a> ReceiveDiagnosticResultsCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -44,6 +47,28 @@ impl<'a> ReceiveDiagnosticResultsCommand<'a> {
             command_buffer: self.command_buffer,
         })
     }
+
+    // reads the whole diagnostic page page_code and returns it without its 4 byte header
+    pub fn page(&mut self, page_code: u8) -> crate::Result<Vec<u8>> {
+        let mut page = self.page_code(Some(page_code)).issue_sized()?;
+
+        Ok(page.split_off(usize::min(PAGE_HEADER_LENGTH, page.len())))
+    }
+}
+
+impl SizedDataIn for ReceiveDiagnosticResultsCommand<'_> {
+    fn probe_length(&self) -> usize {
+        PAGE_HEADER_LENGTH
+    }
+
+    fn response_length(&self, header: &[u8]) -> usize {
+        u16::from_be_bytes([header[2], header[3]]) as usize + PAGE_HEADER_LENGTH
+    }
+
+    fn issue_with_allocation_length(&mut self, allocation_length: usize) -> crate::Result<Vec<u8>> {
+        self.allocation_length(allocation_length.try_into().unwrap_or(u16::MAX))
+            .issue()
+    }
 }
 
 impl Scsi {
@@ -53,6 +78,7 @@ impl Scsi {
 }
 
 const OPERATION_CODE: u8 = 0x1C;
+const PAGE_HEADER_LENGTH: usize = 4;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -94,11 +120,19 @@ impl Command for ThisCommand {
         self.command_buffer.allocation_length() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
-        Ok(std::mem::take(result.data))
+        let length = result.transferred_data().len();
+        let mut data = result.into_data();
+        data.truncate(length);
+
+        Ok(data)
     }
 }
 
@@ -117,4 +151,76 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn page_round_trip_test() {
+        let scsi = Scsi::test_instance();
+        let payload = [0xDE, 0xAD, 0xBE, 0xEF, 0x01];
+
+        scsi.send_diagnostic().page(0x80, &payload).issue().unwrap();
+
+        let sent = scsi.mock().issued().remove(0);
+        assert_eq!(sent.cdb, [0x1D, 0x10, 0x00, 0x00, 0x09, 0x00], "send cdb");
+        assert_eq!(
+            sent.data_out,
+            [0x80, 0x00, 0x00, 0x05, 0xDE, 0xAD, 0xBE, 0xEF, 0x01],
+            "sent page"
+        );
+
+        scsi.mock().push_data(&sent.data_out);
+        scsi.mock().push_data(&sent.data_out);
+
+        let page = scsi.receive_diagnostic_results().page(0x80).unwrap();
+        assert_eq!(page, payload, "received payload");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[1].cdb,
+            [0x1C, 0x01, 0x80, 0x00, 0x04, 0x00],
+            "header cdb"
+        );
+        assert_eq!(
+            issued[2].cdb,
+            [0x1C, 0x01, 0x80, 0x00, 0x09, 0x00],
+            "page cdb"
+        );
+    }
+
+    #[test]
+    fn issue_sized_test() {
+        let mut data = vec![0x02, 0x00, 0x01, 0x04];
+        data.extend((0..0x104).map(|n| n as u8));
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&data[..4]);
+        scsi.mock().push_data(&data);
+
+        let bytes = scsi
+            .receive_diagnostic_results()
+            .page_code(Some(0x02))
+            .issue_sized()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [0x1C, 0x01, 0x02, 0x00, 0x04, 0x00],
+            "probe cdb"
+        );
+        assert_eq!(issued[1].cdb[3..5], [0x01, 0x08], "full allocation length");
+        assert_eq!(bytes, data, "enclosure status page");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x02, 0x00]);
+
+        assert!(
+            matches!(
+                scsi.receive_diagnostic_results()
+                    .page_code(Some(0x02))
+                    .issue_sized(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "short probe"
+        );
+    }
 }
@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, check_allocation_length, SizedDataIn},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -16,6 +16,47 @@ pub struct ReportIdentifyingInformationCommand<'a> {
     command_buffer: CommandBuffer,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InformationType {
+    // 0000000b
+    PeripheralDeviceIdentifyingInformation,
+    // 0000010b
+    PeripheralDeviceTextIdentifyingInformation,
+    // 1111111b, only for REPORT IDENTIFYING INFORMATION
+    SupportedIdentifyingInformation,
+    // reserved, or restricted for SCC-2
+    Other(u8),
+}
+
+impl From<u8> for InformationType {
+    fn from(value: u8) -> Self {
+        match value {
+            0b000_0000 => Self::PeripheralDeviceIdentifyingInformation,
+            0b000_0010 => Self::PeripheralDeviceTextIdentifyingInformation,
+            0b111_1111 => Self::SupportedIdentifyingInformation,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<InformationType> for u8 {
+    fn from(value: InformationType) -> Self {
+        match value {
+            InformationType::PeripheralDeviceIdentifyingInformation => 0b000_0000,
+            InformationType::PeripheralDeviceTextIdentifyingInformation => 0b000_0010,
+            InformationType::SupportedIdentifyingInformation => 0b111_1111,
+            InformationType::Other(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IdentifyingInformation {
+    pub information_type: InformationType,
+    // without the parameter data header
+    pub bytes: Vec<u8>,
+}
+
 impl<'a> ReportIdentifyingInformationCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
@@ -23,7 +64,8 @@ impl<'a> ReportIdentifyingInformationCommand<'a> {
             information_type: 0,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -38,6 +80,10 @@ impl<'a> ReportIdentifyingInformationCommand<'a> {
         self
     }
 
+    pub fn information(&mut self, value: InformationType) -> &mut Self {
+        self.information_type(value.into())
+    }
+
     pub fn control(&mut self, value: u8) -> &mut Self {
         self.command_buffer.set_control(value);
         self
@@ -45,6 +91,7 @@ impl<'a> ReportIdentifyingInformationCommand<'a> {
 
     pub fn issue(&mut self) -> crate::Result<Vec<u8>> {
         bitfield_bound_check!(self.information_type, 7, "information type")?;
+        check_allocation_length(self.command_buffer.allocation_length())?;
 
         self.interface.issue(&ThisCommand {
             command_buffer: self
@@ -54,6 +101,36 @@ impl<'a> ReportIdentifyingInformationCommand<'a> {
     }
 }
 
+impl ReportIdentifyingInformationCommand<'_> {
+    // reads the whole parameter data like issue_sized and strips its header,
+    // the allocation length set on the command is ignored
+    pub fn issue_information(&mut self) -> crate::Result<IdentifyingInformation> {
+        let mut bytes = self.issue_sized()?;
+        bytes.drain(..PARAMETER_DATA_HEADER_LENGTH);
+
+        Ok(IdentifyingInformation {
+            information_type: self.information_type.into(),
+            bytes,
+        })
+    }
+}
+
+impl SizedDataIn for ReportIdentifyingInformationCommand<'_> {
+    fn probe_length(&self) -> usize {
+        PARAMETER_DATA_HEADER_LENGTH
+    }
+
+    fn response_length(&self, header: &[u8]) -> usize {
+        u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize
+            + PARAMETER_DATA_HEADER_LENGTH
+    }
+
+    fn issue_with_allocation_length(&mut self, allocation_length: usize) -> crate::Result<Vec<u8>> {
+        self.allocation_length(allocation_length.try_into().unwrap_or(u32::MAX))
+            .issue()
+    }
+}
+
 impl Scsi {
     pub fn report_identifying_information(&self) -> ReportIdentifyingInformationCommand<'_> {
         ReportIdentifyingInformationCommand::new(self)
@@ -62,6 +139,7 @@ impl Scsi {
 
 const OPERATION_CODE: u8 = 0xA3;
 const SERVICE_ACTION: u8 = 0x05;
+const PARAMETER_DATA_HEADER_LENGTH: usize = 4;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -105,6 +183,14 @@ impl Command for ThisCommand {
         self.command_buffer.allocation_length()
     }
 
+    fn allows_empty_data_in(&self) -> bool {
+        false
+    }
+
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -128,4 +214,66 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn issue_sized_test() {
+        let data = [0x00, 0x00, 0x00, 0x04, b'n', b'a', b'm', b'e'];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&data[..4]);
+        scsi.mock().push_data(&data);
+
+        let bytes = scsi
+            .report_identifying_information()
+            .information_type(0x02)
+            .issue_sized()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb[6..10],
+            [0, 0, 0, 4],
+            "probe allocation length"
+        );
+        assert_eq!(issued[1].cdb[6..10], [0, 0, 0, 8], "full allocation length");
+        assert_eq!(bytes, data, "identifying information");
+    }
+
+    #[test]
+    fn issue_information_test() {
+        let data = [0x00, 0x00, 0x00, 0x05, b'r', b'a', b'c', b'k', b'7'];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&data[..4]);
+        scsi.mock().push_data(&data);
+
+        let information = scsi
+            .report_identifying_information()
+            .information(InformationType::PeripheralDeviceTextIdentifyingInformation)
+            .issue_information()
+            .unwrap();
+
+        assert_eq!(
+            information.information_type,
+            InformationType::PeripheralDeviceTextIdentifyingInformation,
+            "information type"
+        );
+        assert_eq!(information.bytes, b"rack7", "identifying information");
+        assert_eq!(
+            scsi.mock().issued()[0].cdb[10],
+            0x04,
+            "information type field"
+        );
+
+        assert_eq!(
+            InformationType::from(0x7F),
+            InformationType::SupportedIdentifyingInformation,
+            "supported"
+        );
+        assert_eq!(
+            InformationType::from(0x01),
+            InformationType::Other(0x01),
+            "other"
+        );
+    }
 }
@@ -28,7 +28,9 @@ impl<'a> ReportLunsCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             descriptor_length: 0,
         }
     }
@@ -43,7 +45,7 @@ impl<'a> ReportLunsCommand<'a> {
         self
     }
 
-    // descriptor length must be less than 536870910(0x1FFF_FFFE), which is (0xFFFF_FFFF - 8) / 8
+    // descriptor length must be between 1 and 536870910(0x1FFF_FFFE), which is (0xFFFF_FFFF - 8) / 8
     pub fn descriptor_length(&mut self, value: u32) -> &mut Self {
         self.descriptor_length = value;
         self
@@ -61,6 +63,13 @@ impl<'a> ReportLunsCommand<'a> {
             )));
         }
 
+        // SPC-3 devices reject allocation lengths below 16 bytes, which is one descriptor
+        if self.descriptor_length == 0 {
+            return Err(crate::Error::BadArgument(
+                "zero allocation length, descriptor length should be at least 1.".to_owned(),
+            ));
+        }
+
         self.interface.issue(&ThisCommand {
             command_buffer: self
                 .command_buffer
@@ -118,6 +127,14 @@ impl Command for ThisCommand {
         self.command_buffer.allocation_length()
     }
 
+    fn allows_empty_data_in(&self) -> bool {
+        false
+    }
+
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -151,4 +168,29 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn zero_allocation_length_test() {
+        let scsi = Scsi::test_instance();
+
+        assert!(
+            matches!(
+                scsi.report_luns().issue(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "zero descriptor length"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+
+        scsi.mock()
+            .push_data(&[0, 0, 0, 8, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        let result = scsi.report_luns().descriptor_length(1).issue().unwrap();
+
+        assert_eq!(
+            scsi.mock().issued()[0].cdb[6..10],
+            [0, 0, 0, 16],
+            "allocation length"
+        );
+        assert_eq!(result.descriptors, [0x0001_0000_0000_0000], "lun");
+    }
 }
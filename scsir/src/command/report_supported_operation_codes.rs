@@ -1,11 +1,11 @@
 #![allow(dead_code)]
 
-use std::mem::size_of;
+use std::{mem::size_of, time::Duration};
 
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::{bitfield_bound_check, get_array},
+    command::{bitfield_bound_check, check_allocation_length, try_get_array},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -53,6 +53,37 @@ pub struct TimeoutsDescriptor {
     pub recommend_command_timeout: u32,
 }
 
+/// What REPORT SUPPORTED OPERATION CODES reports, together with the requested command.
+/// The requested operation code and service action are only honored by the options that
+/// carry them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportingOptions {
+    /// every supported command, 000b
+    AllCommands,
+    /// one command without service actions, 001b
+    OneCommand { operation_code: u8 },
+    /// one command with service actions, 010b
+    OneCommandWithServiceAction {
+        operation_code: u8,
+        service_action: u16,
+    },
+    /// one command, service_action is ignored if the command has no service actions, 011b
+    OneCommandOptionalServiceAction {
+        operation_code: u8,
+        service_action: u16,
+    },
+}
+
+impl TimeoutsDescriptor {
+    pub fn nominal(&self) -> Duration {
+        Duration::from_secs(self.nominal_command_processing_timeout as u64)
+    }
+
+    pub fn recommended(&self) -> Duration {
+        Duration::from_secs(self.recommend_command_timeout as u64)
+    }
+}
+
 impl<'a> ReportSupportedOperationCodesCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
@@ -60,7 +91,8 @@ impl<'a> ReportSupportedOperationCodesCommand<'a> {
             reporting_options: 0,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -76,6 +108,46 @@ impl<'a> ReportSupportedOperationCodesCommand<'a> {
         self
     }
 
+    // sets reporting_options, requested_operation_code and requested_service_action together,
+    // the fields an option doesn't use are cleared
+    pub fn reporting(&mut self, value: ReportingOptions) -> &mut Self {
+        let (reporting_options, operation_code, service_action) = match value {
+            ReportingOptions::AllCommands => (0b000, 0, 0),
+            ReportingOptions::OneCommand { operation_code } => (0b001, operation_code, 0),
+            ReportingOptions::OneCommandWithServiceAction {
+                operation_code,
+                service_action,
+            } => (0b010, operation_code, service_action),
+            ReportingOptions::OneCommandOptionalServiceAction {
+                operation_code,
+                service_action,
+            } => (0b011, operation_code, service_action),
+        };
+
+        self.reporting_options(reporting_options)
+            .requested_operation_code(operation_code)
+            .requested_service_action(service_action)
+    }
+
+    pub fn all(&mut self) -> &mut Self {
+        self.reporting(ReportingOptions::AllCommands)
+    }
+
+    pub fn one_command(&mut self, operation_code: u8) -> &mut Self {
+        self.reporting(ReportingOptions::OneCommand { operation_code })
+    }
+
+    pub fn one_command_with_service_action(
+        &mut self,
+        operation_code: u8,
+        service_action: u16,
+    ) -> &mut Self {
+        self.reporting(ReportingOptions::OneCommandWithServiceAction {
+            operation_code,
+            service_action,
+        })
+    }
+
     pub fn requested_operation_code(&mut self, value: u8) -> &mut Self {
         self.command_buffer.set_requested_operation_code(value);
         self
@@ -98,6 +170,7 @@ impl<'a> ReportSupportedOperationCodesCommand<'a> {
 
     pub fn issue(&mut self) -> crate::Result<CommandResult> {
         bitfield_bound_check!(self.reporting_options, 3, "reporting options")?;
+        check_allocation_length(self.command_buffer.allocation_length())?;
 
         self.interface.issue(&ThisCommand {
             command_buffer: self
@@ -105,16 +178,98 @@ impl<'a> ReportSupportedOperationCodesCommand<'a> {
                 .with_reporting_options(self.reporting_options),
         })
     }
+
+    // like issue, but an all commands list that did not fit the allocation length is an
+    // AllocationTooSmall error instead of being returned cut short
+    pub fn issue_strict(&mut self) -> crate::Result<CommandResult> {
+        let result = self.issue()?;
+        if let CommandResult::AllCommands(all) = &result {
+            if all.required_allocation_length > self.command_buffer.allocation_length() {
+                return Err(crate::Error::AllocationTooSmall {
+                    required: all.required_allocation_length,
+                });
+            }
+        }
+
+        Ok(result)
+    }
+
+    // the result can be passed to Scsi::set_timeout before issuing the command
+    pub fn recommended_timeout(
+        &mut self,
+        operation_code: u8,
+        service_action: Option<u16>,
+    ) -> crate::Result<Option<Duration>> {
+        let one = self
+            .return_command_timeouts_descriptor(true)
+            .allocation_length(ONE_COMMAND_ALLOCATION_LENGTH)
+            .issue_one(operation_code, service_action)?;
+
+        Ok(one
+            .timeout_descriptor
+            .map(|t| t.recommended())
+            .filter(|t| !t.is_zero()))
+    }
+
+    fn issue_one(
+        &mut self,
+        operation_code: u8,
+        service_action: Option<u16>,
+    ) -> crate::Result<OneCommand> {
+        match service_action {
+            Some(service_action) => {
+                self.one_command_with_service_action(operation_code, service_action)
+            }
+            None => self.one_command(operation_code),
+        };
+
+        match self.issue()? {
+            CommandResult::OneCommand(one) => Ok(one),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Learns the required allocation length first, then reads the complete list of commands.
+    pub fn issue_all(&mut self) -> crate::Result<Vec<CommandDescriptor>> {
+        self.all()
+            .allocation_length(size_of::<AllCommandsParameterDataHeader>() as u32);
+
+        let required_allocation_length = match self.issue()? {
+            CommandResult::AllCommands(all) => all.required_allocation_length,
+            _ => unreachable!(),
+        };
+
+        match self.allocation_length(required_allocation_length).issue()? {
+            CommandResult::AllCommands(all) => Ok(all.descriptors),
+            _ => unreachable!(),
+        }
+    }
 }
 
 impl Scsi {
     pub fn report_supported_operation_codes(&self) -> ReportSupportedOperationCodesCommand<'_> {
         ReportSupportedOperationCodesCommand::new(self)
     }
+
+    pub fn supports_operation(
+        &self,
+        operation_code: u8,
+        service_action: Option<u16>,
+    ) -> crate::Result<bool> {
+        let one = self
+            .report_supported_operation_codes()
+            .allocation_length(ONE_COMMAND_ALLOCATION_LENGTH)
+            .issue_one(operation_code, service_action)?;
+
+        // 0b011: supported by standard, 0b101: supported in a vendor specific manner
+        Ok(matches!(one.support, 0b011 | 0b101))
+    }
 }
 
 const OPERATION_CODE: u8 = 0xA3;
 const SERVICE_ACTION: u8 = 0x0C;
+// header, the largest cdb usage data and a timeouts descriptor
+const ONE_COMMAND_ALLOCATION_LENGTH: u32 = 48;
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -200,17 +355,33 @@ impl Command for ThisCommand {
         self.command_buffer.allocation_length()
     }
 
+    fn allows_empty_data_in(&self) -> bool {
+        false
+    }
+
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
+        let data = result.transferred_data();
+
         if self.command_buffer.reporting_options() == 0 {
-            let (bytes, left) = get_array(result.data);
+            let (bytes, left) = try_get_array(data)?;
             let header = AllCommandsParameterDataHeader::from_bytes(bytes);
             let mut descriptors = vec![];
+            // a list cut short by the allocation length ends with a partial descriptor
+            let truncated = header.command_data_length() as usize > left.len();
             let mut left = &left[..usize::min(header.command_data_length() as usize, left.len())];
             while !left.is_empty() {
-                let (bytes, l) = get_array(left);
+                let (bytes, l) = match try_get_array(left) {
+                    Ok(result) => result,
+                    Err(_) if truncated => break,
+                    Err(e) => return Err(e),
+                };
                 left = l;
                 let command_descriptor = CommandDescriptorHeader::from_bytes(bytes);
                 let service_action = if command_descriptor.service_action_valid() != 0 {
@@ -222,7 +393,11 @@ impl Command for ThisCommand {
                 let timeout_descriptor = if command_descriptor.command_timeouts_descriptor_present()
                     != 0
                 {
-                    let (bytes, l) = get_array(left);
+                    let (bytes, l) = match try_get_array(left) {
+                        Ok(result) => result,
+                        Err(_) if truncated => break,
+                        Err(e) => return Err(e),
+                    };
                     left = l;
                     let timeout_descriptor = CommandTimeoutsDescriptor::from_bytes(bytes);
                     Some(TimeoutsDescriptor {
@@ -249,13 +424,13 @@ impl Command for ThisCommand {
                 descriptors,
             }))
         } else if self.command_buffer.reporting_options() < 0b100 {
-            let (bytes, left) = get_array(result.data);
+            let (bytes, left) = try_get_array(data)?;
             let header = OneCommandParameterDataHeader::from_bytes(bytes);
             let (cdb_data, left) =
                 left[..].split_at(usize::min(header.cdb_size() as usize, left.len()));
             let cdb_data = Vec::from(cdb_data);
             let timeout_descriptor = if header.command_timeout_descriptor_present() != 0 {
-                let (bytes, _) = get_array(left);
+                let (bytes, _) = try_get_array(left)?;
                 let timeout_descriptor = CommandTimeoutsDescriptor::from_bytes(bytes);
                 Some(TimeoutsDescriptor {
                     command_specific: timeout_descriptor.command_specific(),
@@ -273,7 +448,7 @@ impl Command for ThisCommand {
                 timeout_descriptor,
             }))
         } else {
-            Ok(CommandResult::Other(std::mem::take(result.data)))
+            Ok(CommandResult::Other(data.to_vec()))
         }
     }
 }
@@ -321,4 +496,170 @@ mod tests {
             concat!("Size of: ", stringify!(CommandTimeoutsDescriptor))
         );
     }
+
+    #[test]
+    fn reporting_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.report_supported_operation_codes();
+        command.allocation_length(ONE_COMMAND_ALLOCATION_LENGTH);
+
+        command.one_command_with_service_action(0x9E, 0x10);
+        assert_eq!(
+            command.command_buffer.bytes[3..6],
+            [0x9E, 0x00, 0x10],
+            "with service action"
+        );
+        assert_eq!(command.reporting_options, 0b010, "with service action");
+
+        command.one_command(0x28);
+        assert_eq!(
+            command.command_buffer.bytes[3..6],
+            [0x28, 0x00, 0x00],
+            "service action cleared"
+        );
+        assert_eq!(command.reporting_options, 0b001, "one command");
+
+        command.reporting(ReportingOptions::OneCommandOptionalServiceAction {
+            operation_code: 0xA3,
+            service_action: 0x0C,
+        });
+        assert_eq!(
+            command.command_buffer.bytes[3..6],
+            [0xA3, 0x00, 0x0C],
+            "optional service action"
+        );
+        assert_eq!(command.reporting_options, 0b011, "optional service action");
+
+        command.all();
+        assert_eq!(
+            command.command_buffer.bytes[3..6],
+            [0x00, 0x00, 0x00],
+            "all commands"
+        );
+        assert_eq!(command.reporting_options, 0b000, "all commands");
+
+        scsi.mock().push_data(&[0x00, 0x03, 0x00, 0x00]);
+        command
+            .one_command_with_service_action(0x9E, 0x10)
+            .issue()
+            .unwrap();
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [0xA3, 0x0C, 0x02, 0x9E, 0x00, 0x10, 0, 0, 0, 48, 0, 0],
+            "issued cdb"
+        );
+    }
+
+    #[test]
+    fn issue_all_test() {
+        let scsi = Scsi::test_instance();
+        let response = [
+            0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x9E, 0x00,
+            0x00, 0x10, 0x00, 0x01, 0x00, 0x10,
+        ];
+        scsi.mock().push_data(&response);
+        scsi.mock().push_data(&response);
+
+        let descriptors = scsi.report_supported_operation_codes().issue_all().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "issue count");
+        assert_eq!(
+            &issued[0].cdb[6..10],
+            &[0, 0, 0, 4],
+            "probe allocation length"
+        );
+        assert_eq!(
+            &issued[1].cdb[6..10],
+            &[0, 0, 0, 20],
+            "full allocation length"
+        );
+
+        assert_eq!(descriptors.len(), 2, "descriptor count");
+        assert_eq!(descriptors[0].operation_code, 0x00, "operation code");
+        assert_eq!(descriptors[1].operation_code, 0x9E, "operation code");
+        assert_eq!(descriptors[1].service_action, Some(0x10), "service action");
+    }
+
+    #[test]
+    fn issue_strict_test() {
+        let scsi = Scsi::test_instance();
+        let response = [
+            0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x9E, 0x00,
+            0x00, 0x10, 0x00, 0x01, 0x00, 0x10,
+        ];
+        scsi.mock().push_data(&response);
+        scsi.mock().push_data(&response);
+
+        let result = scsi
+            .report_supported_operation_codes()
+            .allocation_length(12)
+            .issue_strict();
+        assert!(
+            matches!(
+                result,
+                Err(crate::Error::AllocationTooSmall { required: 20 })
+            ),
+            "required length"
+        );
+
+        let result = scsi
+            .report_supported_operation_codes()
+            .allocation_length(20)
+            .issue_strict()
+            .unwrap();
+        match result {
+            CommandResult::AllCommands(all) => {
+                assert_eq!(all.descriptors.len(), 2, "descriptor count")
+            }
+            _ => panic!("unexpected result"),
+        }
+    }
+
+    #[test]
+    fn supports_operation_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock()
+            .push_data(&[0x00, 0x03, 0x00, 0x0A, 0x42, 0x00, 0, 0, 0, 0, 0, 0, 0, 0]);
+        scsi.mock().push_data(&[0x00, 0x01, 0x00, 0x00]);
+
+        assert!(scsi.supports_operation(0x42, None).unwrap(), "supported");
+        assert!(
+            !scsi.supports_operation(0x9E, Some(0x12)).unwrap(),
+            "not supported"
+        );
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            &issued[0].cdb[1..4],
+            &[0x0C, 0x01, 0x42],
+            "reporting options 1"
+        );
+        assert_eq!(
+            &issued[1].cdb[1..6],
+            &[0x0C, 0x02, 0x9E, 0x00, 0x12],
+            "reporting options 2"
+        );
+    }
+
+    #[test]
+    fn recommended_timeout_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[
+            0x00, 0x83, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1E,
+            0x00, 0x00, 0x00, 0x78,
+        ]);
+
+        let timeout = scsi
+            .report_supported_operation_codes()
+            .recommended_timeout(0x35, None)
+            .unwrap();
+
+        assert_eq!(timeout, Some(Duration::from_secs(120)), "recommended");
+        assert_eq!(
+            scsi.mock().issued()[0].cdb[2],
+            0x81,
+            "rctd and reporting options"
+        );
+    }
 }
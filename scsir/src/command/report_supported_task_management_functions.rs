@@ -46,7 +46,8 @@ impl<'a> ReportSupportedTaskManagementFunctionsCommand<'a> {
                 .with_service_action(SERVICE_ACTION)
                 .with_allocation_length(size_of::<
                     ReportSupportedTaskManagementFunctionsExtendedParameterData,
-                >() as u32),
+                >() as u32)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -25,7 +25,8 @@ impl<'a> ReportTimestampCommand<'a> {
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
                 .with_service_action(SERVICE_ACTION)
-                .with_allocation_length(size_of::<ReportTimestampParameterData>() as u32),
+                .with_allocation_length(size_of::<ReportTimestampParameterData>() as u32)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -20,7 +20,8 @@ impl<'a> RequestSenseCommand<'a> {
             interface,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_allocation_length(MAX_SENSE_BUFFER_LENGTH as u8),
+                .with_allocation_length(MAX_SENSE_BUFFER_LENGTH as u8)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -89,6 +90,10 @@ impl Command for ThisCommand {
         self.command_buffer.allocation_length() as u32
     }
 
+    fn allows_empty_data_in(&self) -> bool {
+        false
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
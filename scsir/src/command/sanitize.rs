@@ -3,7 +3,10 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{
+        bitfield_bound_check,
+        sense::{SenseData, SenseKey},
+    },
     data_wrapper::{AnyType, FlexibleStruct},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -26,11 +29,23 @@ pub enum ServiceAction {
     ExitFailureMode = 0x1F,
 }
 
+/// Whether a sanitize operation is running on the logical unit, see Scsi::sanitize_status.
+#[derive(Clone, Debug)]
+pub enum SanitizeStatus {
+    Idle,
+    /// percent done, 0 when the device reports no progress indication
+    InProgress(u8),
+    /// the last sanitize failed, the logical unit stays in failure mode
+    /// until ServiceAction::ExitFailureMode or a new sanitize
+    Failed(SenseData),
+}
+
 pub struct OverwriteParameterListBuilder<'a> {
     parent: &'a mut SanitizeCommand<'a>,
     test: u8,
     overwrite_count: u8,
     initialization_pattern: Vec<u8>,
+    logical_block_size: Option<u32>,
     header_buffer: OverwriteParameterListHeader,
 }
 
@@ -41,7 +56,8 @@ impl<'a> SanitizeCommand<'a> {
             sanitize_service_action: ServiceAction::Overwrite,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(ServiceAction::Overwrite as u8),
+                .with_service_action(ServiceAction::Overwrite as u8)
+                .with_control(interface.default_control()),
             data_buffer: FlexibleStruct::new(),
         }
     }
@@ -92,11 +108,14 @@ impl<'a> SanitizeCommand<'a> {
 
 impl<'a> OverwriteParameterListBuilder<'a> {
     fn new(parent: &'a mut SanitizeCommand<'a>) -> Self {
+        let logical_block_size = parent.interface.cached_block_size();
+
         Self {
             parent,
             test: 0,
             overwrite_count: 0,
             initialization_pattern: vec![],
+            logical_block_size,
             header_buffer: OverwriteParameterListHeader::new(),
         }
     }
@@ -124,6 +143,20 @@ impl<'a> OverwriteParameterListBuilder<'a> {
         self
     }
 
+    // when set, the initialization pattern must be one logical block long or evenly divide it,
+    // defaults to the block size cached on Scsi, if any
+    pub fn logical_block_size(&mut self, value: Option<u32>) -> &mut Self {
+        self.logical_block_size = value;
+        self
+    }
+
+    // three passes, each one the inverse of the previous, ending on the pattern itself
+    pub fn dod_style_passes(&mut self, pattern: &[u8]) -> &mut Self {
+        self.overwrite_count(3)
+            .invert(true)
+            .initialization_pattern(pattern)
+    }
+
     pub fn done(&'a mut self) -> crate::Result<&'a mut SanitizeCommand<'a>> {
         bitfield_bound_check!(self.test, 2, "test")?;
         bitfield_bound_check!(self.overwrite_count, 5, "overwrite count")?;
@@ -133,6 +166,19 @@ impl<'a> OverwriteParameterListBuilder<'a> {
             "initialization pattern length"
         )?;
 
+        if let Some(logical_block_size) = self.logical_block_size {
+            let pattern_length = self.initialization_pattern.len() as u32;
+            if pattern_length == 0
+                || pattern_length > logical_block_size
+                || logical_block_size % pattern_length != 0
+            {
+                return Err(crate::Error::BadArgument(format!(
+                    "initialization pattern length should evenly divide the logical block size {}, but {} was provided.",
+                    logical_block_size, pattern_length
+                )));
+            }
+        }
+
         self.parent.data_buffer.set_body(
             self.header_buffer
                 .with_test(self.test)
@@ -152,8 +198,27 @@ impl Scsi {
     pub fn sanitize(&self) -> SanitizeCommand<'_> {
         SanitizeCommand::new(self)
     }
+
+    /// Queries with REQUEST SENSE whether a sanitize is running, e.g. one started by another
+    /// host, without starting one. NOT READY with SANITIZE IN PROGRESS is InProgress, and
+    /// SANITIZE COMMAND FAILED is Failed, any other sense data counts as Idle.
+    pub fn sanitize_status(&self) -> crate::Result<SanitizeStatus> {
+        let sense = self.request_sense().issue()?;
+
+        Ok(match sense.additional_sense_code() {
+            Some(SANITIZE_IN_PROGRESS) if matches!(sense.sense_key(), Some(SenseKey::NotReady)) => {
+                let progress = sense.progress().unwrap_or(0) as u32;
+                SanitizeStatus::InProgress((progress * 100 / 0x10000) as u8)
+            }
+            Some(SANITIZE_COMMAND_FAILED) => SanitizeStatus::Failed(sense),
+            _ => SanitizeStatus::Idle,
+        })
+    }
 }
 
+const SANITIZE_IN_PROGRESS: u16 = 0x041B;
+const SANITIZE_COMMAND_FAILED: u16 = 0x3103;
+
 const OPERATION_CODE: u8 = 0x43;
 
 #[bitfield]
@@ -213,6 +278,10 @@ impl Command for ThisCommand {
         }
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -243,4 +312,92 @@ mod tests {
             concat!("Size of: ", stringify!(OverwriteParameterListHeader))
         );
     }
+
+    #[test]
+    fn pattern_length_test() {
+        let scsi = Scsi::test_instance();
+
+        for (pattern_length, expected_ok) in [(512, true), (4, true), (3, false), (1024, false)] {
+            let mut command = scsi.sanitize();
+            let result = command
+                .parameter()
+                .logical_block_size(Some(512))
+                .initialization_pattern(&vec![0xA5; pattern_length])
+                .done()
+                .is_ok();
+            assert_eq!(result, expected_ok, "pattern length {}", pattern_length);
+        }
+
+        let mut command = scsi.sanitize();
+        let result = command
+            .parameter()
+            .initialization_pattern(&[0xA5; 3])
+            .done()
+            .is_ok();
+        assert!(result, "unknown block size");
+    }
+
+    #[test]
+    fn dod_style_passes_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.sanitize();
+        command
+            .parameter()
+            .dod_style_passes(&[0x55; 4])
+            .done()
+            .unwrap()
+            .issue()
+            .unwrap();
+
+        assert_eq!(
+            scsi.mock().issued()[0].data_out,
+            [0b1000_0011, 0x00, 0x00, 0x04, 0x55, 0x55, 0x55, 0x55],
+            "parameter list"
+        );
+    }
+
+    #[test]
+    fn sanitize_status_test() {
+        let scsi = Scsi::test_instance();
+        let mut in_progress = [
+            0x70, 0, 0x02, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x04, 0x1B, 0, 0x80, 0x40, 0x00,
+        ];
+        let failed = [
+            0x70, 0, 0x03, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x31, 0x03, 0, 0, 0, 0,
+        ];
+
+        scsi.mock().push_data(&in_progress);
+        in_progress[15] = 0;
+        scsi.mock().push_data(&in_progress);
+        scsi.mock().push_data(&failed);
+        scsi.mock()
+            .push_data(&[0x70, 0, 0, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert!(
+            matches!(
+                scsi.sanitize_status().unwrap(),
+                SanitizeStatus::InProgress(25)
+            ),
+            "in progress"
+        );
+        assert!(
+            matches!(
+                scsi.sanitize_status().unwrap(),
+                SanitizeStatus::InProgress(0)
+            ),
+            "no progress indication"
+        );
+        match scsi.sanitize_status().unwrap() {
+            SanitizeStatus::Failed(sense) => assert!(
+                matches!(sense.sense_key(), Some(SenseKey::MediumError)),
+                "sense key"
+            ),
+            status => panic!("expected failed, got {:?}", status),
+        }
+        assert!(
+            matches!(scsi.sanitize_status().unwrap(), SanitizeStatus::Idle),
+            "idle"
+        );
+        assert_eq!(scsi.mock().issued()[0].cdb[0], 0x03, "request sense");
+    }
 }
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use std::fmt::Debug;
+
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
@@ -9,7 +11,7 @@ use crate::{
     Command, DataDirection, Scsi,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct SecurityProtocolOutCommand<'a> {
     interface: &'a Scsi,
     command_buffer: CommandBuffer,
@@ -20,7 +22,9 @@ impl<'a> SecurityProtocolOutCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -50,6 +54,11 @@ impl<'a> SecurityProtocolOutCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         let transfer_length = if self.command_buffer.inc_512() == 0 {
             self.data_buffer.len()
@@ -74,6 +83,20 @@ impl<'a> SecurityProtocolOutCommand<'a> {
     }
 }
 
+// the parameter carries keys and passwords, only its length is printed
+impl Debug for SecurityProtocolOutCommand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecurityProtocolOutCommand")
+            .field("interface", &self.interface)
+            .field("command_buffer", &self.command_buffer)
+            .field(
+                "data_buffer",
+                &format_args!("[redacted; {}]", self.data_buffer.len()),
+            )
+            .finish()
+    }
+}
+
 impl Scsi {
     pub fn security_protocol_out(&self) -> SecurityProtocolOutCommand<'_> {
         SecurityProtocolOutCommand::new(self)
@@ -126,6 +149,10 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -149,4 +176,16 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn debug_redaction_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.security_protocol_out();
+        command.parameter(b"hunter2-secret");
+
+        let output = format!("{:?}", command);
+        assert!(output.contains("[redacted; 14]"), "{}", output);
+        assert!(!output.contains("104, 117, 110"), "{}", output);
+        assert!(!output.contains("hunter2"), "{}", output);
+    }
 }
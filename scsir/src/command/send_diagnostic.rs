@@ -22,7 +22,9 @@ impl<'a> SendDiagnosticCommand<'a> {
         Self {
             interface,
             self_test_code: 0,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -66,6 +68,22 @@ impl<'a> SendDiagnosticCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.command_buffer
+            .set_parameter_list_length(value.len() as u16);
+        self.data_buffer = value;
+        self
+    }
+
+    // sends bytes as diagnostic page page_code behind its 4 byte header, with PF set
+    pub fn page(&mut self, page_code: u8, bytes: &[u8]) -> &mut Self {
+        let mut page = vec![page_code, 0];
+        page.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+        page.extend_from_slice(bytes);
+
+        self.page_format(true).parameter_vec(page)
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.self_test_code, 3, "self test code")?;
         bitfield_bound_check!(self.data_buffer.len(), 16, "parameter list length")?;
@@ -130,6 +148,10 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
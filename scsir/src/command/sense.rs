@@ -39,6 +39,7 @@ impl SenseData {
                     is_sense_key_specific_valid: (raw[15] & 0b10000000) != 0,
                     sense_key_specific: SenseKeySpecific::parse(&raw[15..=17], sense_key),
                     additional_sense_bytes: Vec::from(&raw[18..sense_length]),
+                    raw_bytes: Vec::from(&raw[..sense_length]),
                 };
 
                 Self::Fixed(sense)
@@ -55,7 +56,8 @@ impl SenseData {
                 while descriptor_index < sense_length {
                     let descriptor = Descriptor::parse(&raw[descriptor_index..], sense_key);
                     descriptors.push(descriptor);
-                    descriptor_index += DESCRIPTOR_HEADER_LENGTH + raw[1] as usize;
+                    descriptor_index +=
+                        DESCRIPTOR_HEADER_LENGTH + raw[descriptor_index + 1] as usize;
                 }
 
                 let sense = DescriptorSenseData {
@@ -63,6 +65,7 @@ impl SenseData {
                     sense_key,
                     additional_sense_code: AdditionalSenseCode::from((raw[2], raw[3])),
                     descriptors,
+                    raw_bytes: Vec::from(&raw[..sense_length]),
                 };
 
                 Self::Descriptor(sense)
@@ -70,6 +73,145 @@ impl SenseData {
             _ => Self::Raw(Vec::from(&raw[..sense_length])),
         }
     }
+
+    // the sense bytes exactly as the device returned them, e.g. to forward them elsewhere or
+    // to decode vendor specific sense. Empty for SenseData::None
+    pub fn raw_bytes(&self) -> &[u8] {
+        match self {
+            Self::Fixed(sense) => &sense.raw_bytes,
+            Self::Descriptor(sense) => &sense.raw_bytes,
+            Self::Raw(bytes) => bytes,
+            Self::None => &[],
+        }
+    }
+
+    pub fn sense_key(&self) -> Option<SenseKey> {
+        match self {
+            Self::Fixed(sense) => Some(sense.sense_key),
+            Self::Descriptor(sense) => Some(sense.sense_key),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    // the additional sense code in the high byte and its qualifier in the low byte
+    pub fn additional_sense_code(&self) -> Option<u16> {
+        match self {
+            Self::Fixed(sense) => Some(*sense.additional_sense_code),
+            Self::Descriptor(sense) => Some(*sense.additional_sense_code),
+            Self::None | Self::Raw(_) => None,
+        }
+    }
+
+    // whether the sense reports a deferred error (response code 0x71 or 0x73), a failure of an
+    // earlier command such as one issued with IMMED, not of the command that returned it
+    pub fn is_deferred(&self) -> bool {
+        match self {
+            Self::Fixed(sense) => matches!(sense.response_code, ErrorType::Deferred),
+            Self::Descriptor(sense) => matches!(sense.response_code, ErrorType::Deferred),
+            Self::None | Self::Raw(_) => false,
+        }
+    }
+
+    // the information field, e.g. the first failing lba of a MEDIUM ERROR, only returned when VALID is set
+    pub fn information(&self) -> Option<u64> {
+        match self {
+            Self::Fixed(FixedSenseData {
+                is_valid: true,
+                information,
+                ..
+            }) => Some(u32::from_be_bytes(*information) as u64),
+            Self::Descriptor(sense) => sense.descriptors.iter().find_map(|d| match d {
+                Descriptor::Information {
+                    is_valid: true,
+                    information,
+                } => Some(u64::from_be_bytes(*information)),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    // the command-specific information field, e.g. the first lba REASSIGN BLOCKS did not reassign,
+    // None when descriptor format sense carries no such descriptor
+    pub fn command_specific_information(&self) -> Option<u64> {
+        match self {
+            Self::Fixed(FixedSenseData {
+                command_specific_information,
+                ..
+            }) => Some(u32::from_be_bytes(*command_specific_information) as u64),
+            Self::Descriptor(sense) => sense.descriptors.iter().find_map(|d| match d {
+                Descriptor::CommandSpecificInformation {
+                    command_specific_information,
+                } => Some(u64::from_be_bytes(*command_specific_information)),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    // progress of a long running operation such as FORMAT UNIT or SANITIZE, as the completed
+    // fraction of 65536: 0x8000 is half done. it never reaches 65536, once the operation is
+    // done the device stops reporting progress
+    pub fn progress(&self) -> Option<u16> {
+        match self.sense_key_specific()? {
+            SenseKeySpecific::NoSense {
+                progress_indication,
+            } => Some(*progress_indication),
+            _ => None,
+        }
+    }
+
+    // which byte and bit of the cdb or parameter list an ILLEGAL REQUEST refers to
+    pub fn field_pointer(&self) -> Option<FieldPointer> {
+        match self.sense_key_specific()? {
+            SenseKeySpecific::IllegalRequest {
+                is_command_data,
+                is_bit_pointer_valid,
+                bit_pointer,
+                field_pointer,
+            } => Some(FieldPointer {
+                is_command_data: *is_command_data,
+                bit_pointer: is_bit_pointer_valid.then_some(*bit_pointer),
+                field_pointer: *field_pointer,
+            }),
+            _ => None,
+        }
+    }
+
+    // retries done for a RECOVERED ERROR, MEDIUM ERROR or HARDWARE ERROR
+    pub fn retry_count(&self) -> Option<u16> {
+        match self.sense_key_specific()? {
+            SenseKeySpecific::HardwareError { actual_retry_count } => Some(*actual_retry_count),
+            _ => None,
+        }
+    }
+
+    // only returned when SKSV is set
+    fn sense_key_specific(&self) -> Option<&SenseKeySpecific> {
+        match self {
+            Self::Fixed(FixedSenseData {
+                is_sense_key_specific_valid: true,
+                sense_key_specific,
+                ..
+            }) => Some(sense_key_specific),
+            Self::Descriptor(sense) => sense.descriptors.iter().find_map(|d| match d {
+                Descriptor::SenseKeySpecific {
+                    is_sense_key_specific_valid: true,
+                    sense_key_specific,
+                } => Some(sense_key_specific),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldPointer {
+    // false means the parameter list
+    pub is_command_data: bool,
+    pub bit_pointer: Option<u8>,
+    pub field_pointer: u16,
 }
 
 #[derive(Clone, Debug)]
@@ -87,6 +229,8 @@ pub struct FixedSenseData {
     pub is_sense_key_specific_valid: bool,
     pub sense_key_specific: SenseKeySpecific,
     pub additional_sense_bytes: Vec<u8>,
+    // the whole sense as returned, see SenseData::raw_bytes
+    pub raw_bytes: Vec<u8>,
 }
 
 #[derive(Clone, Debug)]
@@ -95,9 +239,12 @@ pub struct DescriptorSenseData {
     pub sense_key: SenseKey,
     pub additional_sense_code: AdditionalSenseCode,
     pub descriptors: Vec<Descriptor>,
+    // the whole sense as returned, see SenseData::raw_bytes
+    pub raw_bytes: Vec<u8>,
 }
 
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum ErrorType {
     Current,
     Deferred,
@@ -1125,3 +1272,189 @@ impl From<AdditionalSenseCode> for (u8, u8) {
 }
 
 const DESCRIPTOR_HEADER_LENGTH: usize = 2;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> SenseData {
+        let mut raw = [0; MAX_SENSE_BUFFER_LENGTH];
+        raw[..bytes.len()].copy_from_slice(bytes);
+        SenseData::parse(&raw, bytes.len())
+    }
+
+    fn fixed(sense_key: u8, sense_key_specific: [u8; 3]) -> SenseData {
+        let mut bytes = [0; 18];
+        bytes[0] = 0x70;
+        bytes[2] = sense_key;
+        bytes[7] = 0x0A;
+        bytes[15..18].copy_from_slice(&sense_key_specific);
+        parse(&bytes)
+    }
+
+    fn descriptor(sense_key: u8, sense_key_specific: [u8; 3]) -> SenseData {
+        let mut bytes = [0; 24];
+        bytes[0] = 0x72;
+        bytes[1] = sense_key;
+        bytes[7] = 0x10;
+        bytes[8..10].copy_from_slice(&[0x02, 0x06]);
+        bytes[12..15].copy_from_slice(&sense_key_specific);
+        bytes[16..18].copy_from_slice(&[0x03, 0x02]);
+        parse(&bytes)
+    }
+
+    #[test]
+    fn raw_bytes_test() {
+        let mut fixed = [0; 20];
+        fixed[0] = 0x70;
+        fixed[2] = 0x03;
+        fixed[7] = 0x0C;
+        fixed[18..20].copy_from_slice(&[0xAB, 0xCD]);
+
+        let mut descriptor = [0; 20];
+        descriptor[0] = 0x72;
+        descriptor[1] = 0x05;
+        descriptor[7] = 0x0C;
+        descriptor[8..20].copy_from_slice(&[0x00, 0x0A, 0x80, 0, 0, 0, 0, 0, 0, 0, 0x12, 0x34]);
+
+        let vendor = [0x7F, 0x01, 0x02, 0x03];
+
+        for (bytes, name) in [
+            (&fixed[..], "fixed"),
+            (&descriptor[..], "descriptor"),
+            (&vendor[..], "vendor specific"),
+        ] {
+            assert_eq!(parse(bytes).raw_bytes(), bytes, "{}", name);
+        }
+        assert!(matches!(parse(&fixed), SenseData::Fixed(_)), "parsed fixed");
+        assert!(
+            matches!(parse(&descriptor), SenseData::Descriptor(_)),
+            "parsed descriptor"
+        );
+        assert!(parse(&[0; 18]).raw_bytes().is_empty(), "no sense");
+    }
+
+    #[test]
+    fn information_test() {
+        let mut bytes = [0; 18];
+        bytes[0] = 0xF0;
+        bytes[2] = 0x03;
+        bytes[3..7].copy_from_slice(&0x1234u32.to_be_bytes());
+        bytes[7] = 0x0A;
+        assert_eq!(parse(&bytes).information(), Some(0x1234), "fixed");
+
+        bytes[0] = 0x70;
+        assert_eq!(parse(&bytes).information(), None, "fixed without valid");
+
+        let mut bytes = [0; 20];
+        bytes[0] = 0x72;
+        bytes[1] = 0x03;
+        bytes[7] = 0x0C;
+        bytes[8..10].copy_from_slice(&[0x00, 0x0A]);
+        bytes[10] = 0x80;
+        bytes[12..20].copy_from_slice(&0x1_0000_1234u64.to_be_bytes());
+        assert_eq!(
+            parse(&bytes).information(),
+            Some(0x1_0000_1234),
+            "descriptor"
+        );
+    }
+
+    #[test]
+    fn command_specific_information_test() {
+        let mut bytes = [0; 18];
+        bytes[0] = 0x70;
+        bytes[2] = 0x03;
+        bytes[7] = 0x0A;
+        bytes[8..12].copy_from_slice(&0x5678u32.to_be_bytes());
+        assert_eq!(
+            parse(&bytes).command_specific_information(),
+            Some(0x5678),
+            "fixed"
+        );
+
+        let mut bytes = [0; 20];
+        bytes[0] = 0x72;
+        bytes[1] = 0x03;
+        bytes[7] = 0x0C;
+        bytes[8..10].copy_from_slice(&[0x01, 0x0A]);
+        bytes[12..20].copy_from_slice(&0x1_0000_5678u64.to_be_bytes());
+        assert_eq!(
+            parse(&bytes).command_specific_information(),
+            Some(0x1_0000_5678),
+            "descriptor"
+        );
+
+        bytes[8] = 0x00;
+        assert_eq!(
+            parse(&bytes).command_specific_information(),
+            None,
+            "descriptor without command-specific information"
+        );
+    }
+
+    #[test]
+    fn field_pointer_test() {
+        for sense in [
+            fixed(0x05, [0b1100_1011, 0x00, 0x02]),
+            descriptor(0x05, [0b1100_1011, 0x00, 0x02]),
+        ] {
+            assert_eq!(
+                sense.field_pointer(),
+                Some(FieldPointer {
+                    is_command_data: true,
+                    bit_pointer: Some(3),
+                    field_pointer: 2,
+                }),
+                "{:?}",
+                sense
+            );
+            assert_eq!(sense.progress(), None, "{:?}", sense);
+        }
+
+        let sense = fixed(0x05, [0b1000_0011, 0x00, 0x10]);
+        assert_eq!(
+            sense.field_pointer(),
+            Some(FieldPointer {
+                is_command_data: false,
+                bit_pointer: None,
+                field_pointer: 0x10,
+            }),
+            "parameter list without bit pointer"
+        );
+    }
+
+    #[test]
+    fn progress_test() {
+        for sense in [
+            fixed(0x02, [0x80, 0x40, 0x00]),
+            descriptor(0x02, [0x80, 0x40, 0x00]),
+        ] {
+            assert_eq!(sense.progress(), Some(0x4000), "{:?}", sense);
+            assert_eq!(sense.retry_count(), None, "{:?}", sense);
+        }
+
+        assert_eq!(
+            fixed(0x02, [0x00, 0x40, 0x00]).progress(),
+            None,
+            "sksv not set"
+        );
+        assert_eq!(
+            fixed(0x02, [0x80, 0xFF, 0xFF]).progress(),
+            Some(0xFFFF),
+            "65535 of 65536"
+        );
+    }
+
+    #[test]
+    fn retry_count_test() {
+        for sense in [
+            fixed(0x01, [0x80, 0x00, 0x05]),
+            fixed(0x03, [0x80, 0x00, 0x05]),
+            descriptor(0x04, [0x80, 0x00, 0x05]),
+        ] {
+            assert_eq!(sense.retry_count(), Some(5), "{:?}", sense);
+            assert_eq!(sense.field_pointer(), None, "{:?}", sense);
+        }
+    }
+}
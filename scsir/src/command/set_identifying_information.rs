@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, report_identifying_information::InformationType},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -24,7 +24,8 @@ impl<'a> SetIdentifyingInformationCommand<'a> {
             information_type: 0,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -35,6 +36,11 @@ impl<'a> SetIdentifyingInformationCommand<'a> {
         self
     }
 
+    // InformationType::SupportedIdentifyingInformation can only be reported, not set
+    pub fn information(&mut self, value: InformationType) -> &mut Self {
+        self.information_type(value.into())
+    }
+
     pub fn control(&mut self, value: u8) -> &mut Self {
         self.command_buffer.set_control(value);
         self
@@ -47,6 +53,13 @@ impl<'a> SetIdentifyingInformationCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.command_buffer
+            .set_parameter_list_length(value.len() as u32);
+        self.data_buffer = value;
+        self
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.information_type, 7, "information type")?;
         bitfield_bound_check!(self.data_buffer.len(), 32, "parameter list length")?;
@@ -112,6 +125,10 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -135,4 +152,22 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn information_test() {
+        let scsi = Scsi::test_instance();
+        scsi.set_identifying_information()
+            .information(InformationType::PeripheralDeviceTextIdentifyingInformation)
+            .parameter(b"rack7")
+            .issue()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [0xA4, 0x06, 0, 0, 0, 0, 0, 0, 0, 0x05, 0x04, 0],
+            "set identifying information cdb"
+        );
+        assert_eq!(issued[0].data_out, b"rack7", "identifying information");
+    }
 }
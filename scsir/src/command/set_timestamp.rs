@@ -22,7 +22,8 @@ impl<'a> SetTimestampCommand<'a> {
             interface,
             command_buffer: CommandBuffer::new()
                 .with_operation_code(OPERATION_CODE)
-                .with_service_action(SERVICE_ACTION),
+                .with_service_action(SERVICE_ACTION)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -39,6 +40,13 @@ impl<'a> SetTimestampCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.command_buffer
+            .set_parameter_list_length(value.len() as u32);
+        self.data_buffer = value;
+        self
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.data_buffer.len(), 32, "parameter list length")?;
 
@@ -100,6 +108,10 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -0,0 +1,162 @@
+use crate::{
+    command::ata::{AtaProtocol, SatDirection},
+    Scsi,
+};
+
+#[derive(Clone, Debug)]
+pub struct AtaIdentity {
+    pub model_number: String,
+    pub serial_number: String,
+    pub firmware_revision: String,
+    pub lba48_supported: bool,
+    pub smart_supported: bool,
+    pub security_supported: bool,
+    pub write_cache_supported: bool,
+    pub ncq_supported: bool,
+    pub trim_supported: bool,
+    // LBA48 sector count when supported, the 28-bit count otherwise
+    pub user_addressable_sectors: u64,
+}
+
+impl AtaIdentity {
+    pub fn from_bytes(bytes: &[u8; IDENTIFY_DATA_LENGTH]) -> Self {
+        let word = |index: usize| u16::from_le_bytes([bytes[index * 2], bytes[index * 2 + 1]]);
+        let bit = |index: usize, bit: u32| word(index) & (1 << bit) != 0;
+
+        let lba48_supported = bit(83, 10);
+        let user_addressable_sectors = if lba48_supported {
+            (100..=103)
+                .rev()
+                .fold(0u64, |sectors, index| (sectors << 16) | word(index) as u64)
+        } else {
+            ((word(61) as u64) << 16) | word(60) as u64
+        };
+
+        Self {
+            model_number: ata_string(&bytes[27 * 2..47 * 2]),
+            serial_number: ata_string(&bytes[10 * 2..20 * 2]),
+            firmware_revision: ata_string(&bytes[23 * 2..27 * 2]),
+            lba48_supported,
+            smart_supported: bit(82, 0),
+            security_supported: bit(82, 1),
+            write_cache_supported: bit(82, 5),
+            ncq_supported: bit(76, 8),
+            trim_supported: bit(169, 0),
+            user_addressable_sectors,
+        }
+    }
+}
+
+impl Scsi {
+    /// Issues ATA IDENTIFY DEVICE through ATA PASS-THROUGH(16), for ATA devices behind a SATL.
+    pub fn ata_identify(&self) -> crate::Result<AtaIdentity> {
+        let data = self
+            .ata_passthru()
+            .command(
+                SatDirection::FromDevice,
+                AtaProtocol::PioDataIn,
+                IDENTIFY_DEVICE_COMMAND,
+            )
+            .count(IDENTIFY_DATA_LENGTH as u16)
+            .issue_16()?
+            .unwrap_or_default();
+
+        let bytes: &[u8; IDENTIFY_DATA_LENGTH] = data
+            .get(..IDENTIFY_DATA_LENGTH)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| {
+                crate::Error::Other(format!(
+                    "IDENTIFY DEVICE returned {} bytes instead of {}.",
+                    data.len(),
+                    IDENTIFY_DATA_LENGTH
+                ))
+            })?;
+
+        Ok(AtaIdentity::from_bytes(bytes))
+    }
+}
+
+// ATA strings hold two characters per word, the first one in the high byte
+fn ata_string(bytes: &[u8]) -> String {
+    let swapped: Vec<u8> = bytes
+        .chunks_exact(2)
+        .flat_map(|pair| [pair[1], pair[0]])
+        .collect();
+
+    String::from_utf8_lossy(&swapped).trim().to_owned()
+}
+
+const IDENTIFY_DEVICE_COMMAND: u8 = 0xEC;
+const IDENTIFY_DATA_LENGTH: usize = 512;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_string(data: &mut [u8], word: usize, value: &str, words: usize) {
+        let mut padded = format!("{:<width$}", value, width = words * 2).into_bytes();
+        for pair in padded.chunks_exact_mut(2) {
+            pair.swap(0, 1);
+        }
+        data[word * 2..(word + words) * 2].copy_from_slice(&padded);
+    }
+
+    fn set_word(data: &mut [u8], word: usize, value: u16) {
+        data[word * 2..word * 2 + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn identify_data() -> Vec<u8> {
+        let mut data = vec![0; IDENTIFY_DATA_LENGTH];
+        set_string(&mut data, 10, "WD-WCC4N1234567", 10);
+        set_string(&mut data, 23, "82.00A82", 4);
+        set_string(&mut data, 27, "WDC WD20EFRX-68EUZN0", 20);
+        set_word(&mut data, 60, 0xFFFF);
+        set_word(&mut data, 61, 0x0FFF);
+        set_word(&mut data, 76, 0x0100);
+        set_word(&mut data, 82, 0x0021);
+        set_word(&mut data, 83, 0x0400);
+        set_word(&mut data, 100, 0x88B0);
+        set_word(&mut data, 101, 0xE8E0);
+        set_word(&mut data, 102, 0x0000);
+        data
+    }
+
+    #[test]
+    fn from_bytes_test() {
+        let identity = AtaIdentity::from_bytes(identify_data()[..].try_into().unwrap());
+
+        assert_eq!(identity.model_number, "WDC WD20EFRX-68EUZN0", "model");
+        assert_eq!(identity.serial_number, "WD-WCC4N1234567", "serial");
+        assert_eq!(identity.firmware_revision, "82.00A82", "firmware");
+        assert!(identity.lba48_supported, "lba48");
+        assert!(identity.smart_supported, "smart");
+        assert!(!identity.security_supported, "security");
+        assert!(identity.write_cache_supported, "write cache");
+        assert!(identity.ncq_supported, "ncq");
+        assert!(!identity.trim_supported, "trim");
+        assert_eq!(identity.user_addressable_sectors, 3_907_029_168, "sectors");
+    }
+
+    #[test]
+    fn ata_identify_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&identify_data());
+
+        let identity = scsi.ata_identify().unwrap();
+
+        let cdb = &scsi.mock().issued()[0].cdb;
+        assert_eq!(&cdb[..3], &[0x85, 0x08, 0x0E], "pio data-in");
+        assert_eq!(cdb[6], 0x01, "one sector");
+        assert_eq!(cdb[14], 0xEC, "identify device");
+        assert_eq!(identity.model_number, "WDC WD20EFRX-68EUZN0", "model");
+
+        let mut lba28 = identify_data();
+        set_word(&mut lba28, 83, 0x0000);
+        scsi.mock().push_data(&lba28);
+        let identity = scsi.ata_identify().unwrap();
+        assert_eq!(
+            identity.user_addressable_sectors, 0x0FFF_FFFF,
+            "28-bit sectors"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{
+    command::ata::{AtaProtocol, SatDirection},
+    Scsi,
+};
+
+#[derive(Clone, Debug)]
+pub struct SmartData {
+    pub data: Vec<u8>,
+    // only the used entries of the attribute table, id 0 marks an unused one
+    pub attributes: Vec<SmartAttribute>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmartAttribute {
+    pub id: u8,
+    pub flags: u16,
+    pub current: u8,
+    pub worst: u8,
+    pub raw: u64,
+}
+
+impl SmartData {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let table = &bytes[usize::min(ATTRIBUTE_TABLE_OFFSET, bytes.len())..];
+        let attributes = table
+            .chunks_exact(ATTRIBUTE_LENGTH)
+            .take(ATTRIBUTE_COUNT)
+            .filter(|entry| entry[0] != 0)
+            .map(|entry| SmartAttribute {
+                id: entry[0],
+                flags: u16::from_le_bytes([entry[1], entry[2]]),
+                current: entry[3],
+                worst: entry[4],
+                raw: u64::from_le_bytes([
+                    entry[5], entry[6], entry[7], entry[8], entry[9], entry[10], 0, 0,
+                ]),
+            })
+            .collect();
+
+        Self {
+            data: bytes.to_vec(),
+            attributes,
+        }
+    }
+}
+
+impl Scsi {
+    /// Issues ATA SMART READ DATA through ATA PASS-THROUGH(16).
+    pub fn ata_smart_read_data(&self) -> crate::Result<SmartData> {
+        let data = self
+            .ata_passthru()
+            .command(
+                SatDirection::FromDevice,
+                AtaProtocol::PioDataIn,
+                SMART_COMMAND,
+            )
+            .features(SMART_READ_DATA)
+            .lba(SMART_LBA)
+            .count(SMART_DATA_LENGTH)
+            .issue_16()?
+            .unwrap_or_default();
+
+        Ok(SmartData::from_bytes(&data))
+    }
+
+    /// Issues ATA SMART RETURN STATUS with CK_COND set and returns true when a threshold is
+    /// exceeded. Fails with DeviceUnsupported if the SATL does not return the ATA registers in
+    /// descriptor format sense data.
+    pub fn ata_smart_return_status(&self) -> crate::Result<bool> {
+        let (_, task_file) = self
+            .ata_passthru()
+            .command(SatDirection::ToDevice, AtaProtocol::NonData, SMART_COMMAND)
+            .features(SMART_RETURN_STATUS)
+            .lba(SMART_LBA)
+            .check_condition(true)
+            .issue_16_with_task_file()?;
+
+        let task_file = task_file.ok_or_else(|| {
+            crate::Error::DeviceUnsupported("Returning the ATA registers in sense data".to_owned())
+        })?;
+
+        match (task_file.lba >> 8) & 0xFFFF {
+            SMART_LBA_OK => Ok(false),
+            SMART_LBA_THRESHOLD_EXCEEDED => Ok(true),
+            other => Err(crate::Error::Other(format!(
+                "unexpected SMART RETURN STATUS registers {:#06X}.",
+                other
+            ))),
+        }
+    }
+}
+
+const SMART_COMMAND: u8 = 0xB0;
+const SMART_READ_DATA: u16 = 0xD0;
+const SMART_RETURN_STATUS: u16 = 0xDA;
+// LBA(23:8) of every SMART command
+const SMART_LBA: u64 = 0xC2_4F00;
+const SMART_LBA_OK: u64 = 0xC24F;
+const SMART_LBA_THRESHOLD_EXCEEDED: u64 = 0x2CF4;
+const SMART_DATA_LENGTH: u16 = 512;
+const ATTRIBUTE_TABLE_OFFSET: usize = 2;
+const ATTRIBUTE_LENGTH: usize = 12;
+const ATTRIBUTE_COUNT: usize = 30;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smart_data() -> Vec<u8> {
+        let mut data = vec![0; SMART_DATA_LENGTH as usize];
+        data[0] = 0x10;
+        // reallocated sector count
+        data[2..14].copy_from_slice(&[
+            0x05, 0x33, 0x00, 0xC8, 0xC8, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        // power-on hours, third slot to check unused ones are skipped
+        data[26..38].copy_from_slice(&[
+            0x09, 0x32, 0x00, 0x5F, 0x5F, 0x10, 0x27, 0x01, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        data
+    }
+
+    fn ata_return_sense(lba_mid: u8, lba_high: u8) -> Vec<u8> {
+        vec![
+            0x72, 0x01, 0x00, 0x1D, 0x00, 0x00, 0x00, 0x0E, 0x09, 0x0C, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, lba_mid, 0x00, lba_high, 0x00, 0x50,
+        ]
+    }
+
+    #[test]
+    fn read_data_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&smart_data());
+
+        let smart = scsi.ata_smart_read_data().unwrap();
+
+        let cdb = &scsi.mock().issued()[0].cdb;
+        assert_eq!(cdb[4], 0xD0, "feature");
+        assert_eq!((cdb[10], cdb[12]), (0x4F, 0xC2), "lba mid and high");
+        assert_eq!(cdb[14], 0xB0, "command");
+
+        assert_eq!(smart.data.len(), 512, "data length");
+        assert_eq!(
+            smart.attributes,
+            [
+                SmartAttribute {
+                    id: 0x05,
+                    flags: 0x0033,
+                    current: 200,
+                    worst: 200,
+                    raw: 2,
+                },
+                SmartAttribute {
+                    id: 0x09,
+                    flags: 0x0032,
+                    current: 95,
+                    worst: 95,
+                    raw: 0x1_2710,
+                },
+            ],
+            "attributes"
+        );
+    }
+
+    #[test]
+    fn return_status_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&ata_return_sense(0x4F, 0xC2));
+        scsi.mock().push_sense(&ata_return_sense(0xF4, 0x2C));
+
+        assert!(!scsi.ata_smart_return_status().unwrap(), "good");
+        assert!(
+            scsi.ata_smart_return_status().unwrap(),
+            "threshold exceeded"
+        );
+
+        let cdb = &scsi.mock().issued()[0].cdb;
+        assert_eq!(cdb[2] & 0x20, 0x20, "ck_cond");
+        assert_eq!(cdb[4], 0xDA, "feature");
+
+        let result = scsi.ata_smart_return_status();
+        assert!(
+            matches!(result, Err(crate::Error::DeviceUnsupported(_))),
+            "no registers returned"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    shortcut::mode::{DescriptorStorage, DescriptorType, HeaderStorage, HeaderType},
+    Scsi,
+};
+
+impl Scsi {
+    /// Reads the logical block length from the first block descriptor returned by
+    /// MODE SENSE(10), for devices such as tapes and optical drives without READ CAPACITY.
+    /// Long block descriptors are accepted. The result is not cached.
+    pub fn probe_block_size_from_mode_sense(&self) -> crate::Result<u32> {
+        let bytes = self
+            .mode_sense()
+            .long_lba_accepted(true)
+            .page_code(ALL_PAGES_PAGE_CODE)
+            .allocation_length(ALLOCATION_LENGTH as u16)
+            .issue_10()?;
+
+        if bytes.len() < LONG_HEADER_LENGTH {
+            return Err(crate::Error::BadArgument(
+                "short response, the mode parameter header is incomplete.".to_owned(),
+            ));
+        }
+
+        let (header, bytes) = HeaderStorage::from_bytes(HeaderType::Long, &bytes);
+        let descriptor_type = header.descriptor_type();
+        let descriptor_length = match descriptor_type {
+            DescriptorType::Short => SHORT_DESCRIPTOR_LENGTH,
+            DescriptorType::Long => LONG_DESCRIPTOR_LENGTH,
+        };
+
+        if (header.block_descriptor_length() as usize) < descriptor_length {
+            return Err(crate::Error::DeviceUnsupported(
+                "Returning a block descriptor".to_owned(),
+            ));
+        }
+
+        if bytes.len() < descriptor_length {
+            return Err(crate::Error::BadArgument(
+                "short response, the block descriptor is incomplete.".to_owned(),
+            ));
+        }
+
+        let (descriptor, _) = DescriptorStorage::from_bytes(descriptor_type, bytes);
+        Ok(descriptor.logical_block_length())
+    }
+
+    /// Issues READ CAPACITY(16) and, if the device rejects it with ILLEGAL REQUEST,
+    /// falls back to probe_block_size_from_mode_sense. Either way the logical block
+    /// length is cached for READ and WRITE, as with cache_block_size.
+    pub fn detect_block_size(&self) -> crate::Result<u32> {
+        let block_size = match self.cache_block_size() {
+            Err(e) if e.is_illegal_request() => self.probe_block_size_from_mode_sense()?,
+            result => return result,
+        };
+        self.set_cached_block_size(block_size);
+
+        Ok(block_size)
+    }
+}
+
+const ALL_PAGES_PAGE_CODE: u8 = 0x3F;
+const LONG_HEADER_LENGTH: usize = 8;
+const SHORT_DESCRIPTOR_LENGTH: usize = 8;
+const LONG_DESCRIPTOR_LENGTH: usize = 16;
+const ALLOCATION_LENGTH: usize = LONG_HEADER_LENGTH + LONG_DESCRIPTOR_LENGTH;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ILLEGAL_REQUEST: [u8; 18] = [
+        0x70, 0x00, 0x05, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x20, 0x00, 0, 0, 0, 0,
+    ];
+
+    #[test]
+    fn probe_block_size_from_mode_sense_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[
+            0x00, 0x16, 0x00, 0x00, 0x01, 0x00, 0x00, 0x10, // long header
+            0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0, 0x00, 0x01, 0x00, 0x00,
+        ]);
+        scsi.mock()
+            .push_data(&[0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        assert_eq!(
+            scsi.probe_block_size_from_mode_sense().unwrap(),
+            0x10000,
+            "long descriptor"
+        );
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [0x5A, 0x10, 0x3F, 0x00, 0, 0, 0, 0x00, 0x18, 0x00],
+            "mode sense cdb"
+        );
+        assert!(
+            matches!(
+                scsi.probe_block_size_from_mode_sense(),
+                Err(crate::Error::DeviceUnsupported(_))
+            ),
+            "no block descriptor"
+        );
+    }
+
+    #[test]
+    fn detect_block_size_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&ILLEGAL_REQUEST);
+        scsi.mock().push_data(&[
+            0x00, 0x0E, 0x01, 0x00, 0x00, 0x00, 0x00, 0x08, // long header
+            0, 0, 0, 0, 0, 0, 0x08, 0x00,
+        ]);
+
+        assert_eq!(scsi.block_size().unwrap(), 2048, "mode sense fallback");
+        assert_eq!(scsi.block_size().unwrap(), 2048, "cached block size");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "issued commands");
+        assert_eq!(issued[0].cdb[0], 0x9E, "read capacity(16)");
+        assert_eq!(issued[1].cdb[0], 0x5A, "mode sense(10)");
+    }
+}
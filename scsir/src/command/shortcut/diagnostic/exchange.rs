@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use crate::{command::sense::SenseKey, Scsi};
+
+impl Scsi {
+    /// Sends bytes as diagnostic page page_code with SEND DIAGNOSTIC, then reads the page
+    /// back with RECEIVE DIAGNOSTIC RESULTS and returns it without its 4 byte header.
+    ///
+    /// Some devices compute the result asynchronously and answer with NOT READY until it is
+    /// available, so the receive side is retried every 100 milliseconds until timeout has
+    /// passed. After that the last NOT READY error is returned.
+    pub fn diagnostic_exchange(
+        &self,
+        page_code: u8,
+        bytes: &[u8],
+        timeout: Duration,
+    ) -> crate::Result<Vec<u8>> {
+        self.send_diagnostic().page(page_code, bytes).issue()?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.receive_diagnostic_results().page(page_code) {
+                Err(e) if is_not_ready(&e) && Instant::now() < deadline => {
+                    std::thread::sleep(DIAGNOSTIC_POLL_INTERVAL);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+const DIAGNOSTIC_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn is_not_ready(error: &crate::Error) -> bool {
+    matches!(
+        error.sense_data().and_then(|sense| sense.sense_key()),
+        Some(SenseKey::NotReady)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT_READY: [u8; 18] = [
+        0x70, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x00,
+        0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn diagnostic_exchange_test() {
+        let page = [0x40, 0x00, 0x00, 0x02, 0xAB, 0xCD];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&NOT_READY);
+        scsi.mock().push_data(&page[..4]);
+        scsi.mock().push_data(&page);
+
+        let bytes = scsi
+            .diagnostic_exchange(0x40, &[0x01, 0x02], Duration::from_secs(10))
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        let operation_codes: Vec<u8> = issued.iter().map(|command| command.cdb[0]).collect();
+        assert_eq!(
+            operation_codes,
+            [0x1D, 0x1C, 0x1C, 0x1C],
+            "command sequence"
+        );
+        assert_eq!(
+            issued[0].data_out,
+            [0x40, 0x00, 0x00, 0x02, 0x01, 0x02],
+            "sent page"
+        );
+        assert_eq!(issued[1].cdb[2], 0x40, "page code");
+        assert_eq!(bytes, [0xAB, 0xCD], "page");
+    }
+
+    #[test]
+    fn timeout_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&NOT_READY);
+
+        let result = scsi.diagnostic_exchange(0x40, &[], Duration::ZERO);
+
+        assert!(is_not_ready(&result.unwrap_err()), "not ready");
+        assert_eq!(scsi.mock().issued().len(), 2, "no retry after timeout");
+    }
+}
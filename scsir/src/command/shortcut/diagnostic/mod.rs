@@ -0,0 +1,4 @@
+mod exchange;
+mod translate_address;
+
+pub use translate_address::*;
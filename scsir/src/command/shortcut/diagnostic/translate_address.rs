@@ -0,0 +1,282 @@
+#![allow(dead_code)]
+
+use std::mem::size_of;
+
+use modular_bitfield_msb::prelude::*;
+
+use crate::command::{
+    bitfield_bound_check, format_unit, get_array,
+    read_defect_data::{
+        BytesFromIndexFormatAddressDescriptor, DefectList, ExtendedBytesFromIndexAddressDescriptor,
+        ExtendedPhysicalSectorAddressDescriptor, LongBlockFormatAddressDescriptor,
+        PhysicalSectorFormatAddressDescriptor, ShortBlockFormatAddressDescriptor,
+    },
+    receive_diagnostic_results::ReceiveDiagnosticResultsCommand,
+    send_diagnostic::SendDiagnosticCommand,
+    try_get_array,
+};
+
+pub const TRANSLATE_ADDRESS_PAGE_CODE: u8 = 0x40;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Address {
+    ShortBlockFormat(ShortBlockFormatAddressDescriptor),
+    ExtendedBytesFromIndex(ExtendedBytesFromIndexAddressDescriptor),
+    ExtendedPhysicalSector(ExtendedPhysicalSectorAddressDescriptor),
+    LongBlockFormat(LongBlockFormatAddressDescriptor),
+    BytesFromIndexFormat(BytesFromIndexFormatAddressDescriptor),
+    PhysicalSectorFormat(PhysicalSectorFormatAddressDescriptor),
+}
+
+#[derive(Clone, Debug)]
+pub struct TranslateAddressOutput {
+    pub supplied_format: u8,
+    pub reserved_area: bool,
+    pub alternate_sector: bool,
+    pub alternate_track: bool,
+    pub translated_format: u8,
+    pub translated_addresses: DefectList,
+}
+
+impl Address {
+    pub fn format(&self) -> u8 {
+        match self {
+            Address::ShortBlockFormat(_) => 0b000,
+            Address::ExtendedBytesFromIndex(_) => 0b001,
+            Address::ExtendedPhysicalSector(_) => 0b010,
+            Address::LongBlockFormat(_) => 0b011,
+            Address::BytesFromIndexFormat(_) => 0b100,
+            Address::PhysicalSectorFormat(_) => 0b101,
+        }
+    }
+
+    fn to_bytes(self) -> crate::Result<[u8; 8]> {
+        let mut bytes = [0; 8];
+
+        match self {
+            Address::ShortBlockFormat(a) => bytes[..4].copy_from_slice(
+                &format_unit::ShortBlockFormatAddressDescriptor::new()
+                    .with_short_block_address(a.short_block_address)
+                    .into_bytes(),
+            ),
+            Address::ExtendedBytesFromIndex(a) => {
+                bitfield_bound_check!(a.cylinder_number, 24, "cylinder number")?;
+                bitfield_bound_check!(a.bytes_from_index, 28, "bytes from index")?;
+                bytes = format_unit::ExtendedBytesFromIndexAddressDescriptor::new()
+                    .with_cylinder_number(a.cylinder_number)
+                    .with_head_number(a.head_number)
+                    .with_multi_address_descriptor_start(a.multi_address_descriptor_start.into())
+                    .with_bytes_from_index(a.bytes_from_index)
+                    .into_bytes();
+            }
+            Address::ExtendedPhysicalSector(a) => {
+                bitfield_bound_check!(a.cylinder_number, 24, "cylinder number")?;
+                bitfield_bound_check!(a.sector_number, 28, "sector number")?;
+                bytes = format_unit::ExtendedPhysicalSectorAddressDescriptor::new()
+                    .with_cylinder_number(a.cylinder_number)
+                    .with_head_number(a.head_number)
+                    .with_multi_address_descriptor_start(a.multi_address_descriptor_start.into())
+                    .with_sector_number(a.sector_number)
+                    .into_bytes();
+            }
+            Address::LongBlockFormat(a) => {
+                bytes = format_unit::LongBlockFormatAddressDescriptor::new()
+                    .with_long_block_address(a.long_block_address)
+                    .into_bytes();
+            }
+            Address::BytesFromIndexFormat(a) => {
+                bitfield_bound_check!(a.cylinder_number, 24, "cylinder number")?;
+                bytes = format_unit::BytesFromIndexFormatAddressDescriptor::new()
+                    .with_cylinder_number(a.cylinder_number)
+                    .with_head_number(a.head_number)
+                    .with_bytes_from_index(a.bytes_from_index)
+                    .into_bytes();
+            }
+            Address::PhysicalSectorFormat(a) => {
+                bitfield_bound_check!(a.cylinder_number, 24, "cylinder number")?;
+                bytes = format_unit::PhysicalSectorFormatAddressDescriptor::new()
+                    .with_cylinder_number(a.cylinder_number)
+                    .with_head_number(a.head_number)
+                    .with_sector_number(a.sector_number)
+                    .into_bytes();
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl TranslateAddressOutput {
+    pub fn from_bytes(bytes: &[u8]) -> crate::Result<Self> {
+        let (array, bytes) = try_get_array(bytes)?;
+        let header = OutputPageHeader::from_bytes(array);
+
+        let length = (header.page_length() as usize)
+            .saturating_sub(size_of::<OutputPageHeader>() - PAGE_HEADER_LENGTH);
+        let bytes = &bytes[..usize::min(length, bytes.len())];
+
+        Ok(Self {
+            supplied_format: header.supplied_format(),
+            reserved_area: header.reserved_area() != 0,
+            alternate_sector: header.alternate_sector() != 0,
+            alternate_track: header.alternate_track() != 0,
+            translated_format: header.translated_format(),
+            translated_addresses: DefectList::from_bytes(header.translated_format(), bytes)?,
+        })
+    }
+}
+
+// translate_format must be less than 0x08
+pub fn translate_address_page(address: Address, translate_format: u8) -> crate::Result<Vec<u8>> {
+    bitfield_bound_check!(translate_format, 3, "translate format")?;
+
+    let page = InputPage::new()
+        .with_page_code(TRANSLATE_ADDRESS_PAGE_CODE)
+        .with_page_length((size_of::<InputPage>() - PAGE_HEADER_LENGTH) as u16)
+        .with_supplied_format(address.format())
+        .with_translate_format(translate_format)
+        .with_address_to_translate(u64::from_be_bytes(address.to_bytes()?));
+
+    Ok(page.into_bytes().to_vec())
+}
+
+// translate_format must be less than 0x08
+pub fn send_translate_address(
+    this: &mut SendDiagnosticCommand,
+    address: Address,
+    translate_format: u8,
+) -> crate::Result<()> {
+    let page = translate_address_page(address, translate_format)?;
+
+    this.page_format(true)
+        .self_test(false)
+        .parameter(&page)
+        .issue()
+}
+
+pub fn receive_translate_address(
+    this: &mut ReceiveDiagnosticResultsCommand,
+) -> crate::Result<TranslateAddressOutput> {
+    this.page_code(Some(TRANSLATE_ADDRESS_PAGE_CODE));
+
+    let header = this.allocation_length(PAGE_HEADER_LENGTH as u16).issue()?;
+    let (array, _) = get_array::<PAGE_HEADER_LENGTH>(&header);
+    let page_length = u16::from_be_bytes([array[2], array[3]]);
+
+    let bytes = this
+        .allocation_length(page_length.saturating_add(PAGE_HEADER_LENGTH as u16))
+        .issue()?;
+
+    TranslateAddressOutput::from_bytes(&bytes)
+}
+
+const PAGE_HEADER_LENGTH: usize = 4;
+
+#[bitfield]
+#[derive(Clone, Copy, Debug)]
+struct InputPage {
+    page_code: B8,
+    reserved_0: B8,
+    page_length: B16,
+    reserved_1: B5,
+    supplied_format: B3,
+    reserved_2: B5,
+    translate_format: B3,
+    address_to_translate: B64,
+}
+
+#[bitfield]
+#[derive(Clone, Copy, Debug)]
+struct OutputPageHeader {
+    page_code: B8,
+    reserved_0: B8,
+    page_length: B16,
+    reserved_1: B5,
+    supplied_format: B3,
+    reserved_area: B1,
+    alternate_sector: B1,
+    alternate_track: B1,
+    reserved_2: B2,
+    translated_format: B3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    const INPUT_PAGE_LENGTH: usize = 14;
+    const OUTPUT_PAGE_HEADER_LENGTH: usize = 6;
+
+    #[test]
+    fn layout_test() {
+        assert_eq!(
+            size_of::<InputPage>(),
+            INPUT_PAGE_LENGTH,
+            concat!("Size of: ", stringify!(InputPage))
+        );
+
+        assert_eq!(
+            size_of::<OutputPageHeader>(),
+            OUTPUT_PAGE_HEADER_LENGTH,
+            concat!("Size of: ", stringify!(OutputPageHeader))
+        );
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let page = translate_address_page(
+            Address::LongBlockFormat(LongBlockFormatAddressDescriptor {
+                long_block_address: 0x1234,
+            }),
+            0b101,
+        )
+        .unwrap();
+
+        assert_eq!(
+            page,
+            [0x40, 0x00, 0x00, 0x0A, 0x03, 0x05, 0, 0, 0, 0, 0, 0, 0x12, 0x34],
+            "input page"
+        );
+
+        let output = TranslateAddressOutput::from_bytes(&[
+            0x40,
+            0x00,
+            0x00,
+            0x12,
+            0x03,
+            0b0100_0101,
+            0x00,
+            0x00,
+            0x07,
+            0x02,
+            0x00,
+            0x00,
+            0x00,
+            0x2A,
+            0x00,
+            0x00,
+            0x07,
+            0x02,
+            0x00,
+            0x00,
+            0x00,
+            0x2B,
+        ])
+        .unwrap();
+
+        assert_eq!(output.supplied_format, 0b011, "supplied format");
+        assert!(output.alternate_sector, "alternate sector");
+        assert!(!output.reserved_area, "reserved area");
+
+        match output.translated_addresses {
+            DefectList::PhysicalSectorFormat(v) => {
+                assert_eq!(v.len(), 2, "translated address count");
+                assert_eq!(v[0].cylinder_number, 0x07, "cylinder number");
+                assert_eq!(v[0].head_number, 0x02, "head number");
+                assert_eq!(v[1].sector_number, 0x2B, "sector number");
+            }
+            _ => panic!("unexpected translated format"),
+        }
+    }
+}
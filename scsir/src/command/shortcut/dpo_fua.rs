@@ -0,0 +1,60 @@
+use crate::{
+    shortcut::mode::{HeaderStorage, HeaderType, CACHING_PAGE_CODE},
+    Scsi,
+};
+
+impl Scsi {
+    /// Whether the device honours the DPO and FUA bits of READ and WRITE, the DPOFUA bit of
+    /// the mode parameter header. Only the header of the Caching mode page is read.
+    pub fn dpo_fua_supported(&self) -> crate::Result<bool> {
+        let bytes = self
+            .mode_sense()
+            .disable_block_descriptors(true)
+            .page_code(CACHING_PAGE_CODE)
+            .allocation_length(LONG_HEADER_LENGTH as u16)
+            .issue_10()?;
+
+        if bytes.len() < LONG_HEADER_LENGTH {
+            return Err(crate::Error::ProtocolViolation(
+                "short response, the mode parameter header is incomplete.".to_owned(),
+            ));
+        }
+
+        let (header, _) = HeaderStorage::from_bytes(HeaderType::Long, &bytes);
+        Ok(header.dpo_and_fua_support())
+    }
+}
+
+const LONG_HEADER_LENGTH: usize = 8;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dpo_fua_supported_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock()
+            .push_data(&[0x00, 0x1A, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00]);
+        scsi.mock()
+            .push_data(&[0x00, 0x1A, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00]);
+
+        assert!(scsi.dpo_fua_supported().unwrap(), "supported");
+        assert!(!scsi.dpo_fua_supported().unwrap(), "write protect only");
+        assert_eq!(
+            scsi.mock().issued()[0].cdb,
+            [0x5A, 0x08, 0x08, 0x00, 0, 0, 0, 0x00, 0x08, 0x00],
+            "mode sense cdb"
+        );
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x1A, 0x00, 0x10]);
+        assert!(
+            matches!(
+                scsi.dpo_fua_supported(),
+                Err(crate::Error::ProtocolViolation(_))
+            ),
+            "short header"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fs, path::PathBuf};
+
+use crate::{shortcut::DeviceIdentity, Scsi};
+
+impl Scsi {
+    /// Opens every SCSI block device of the system in name order, e.g. `/dev/sda`, `/dev/sdb`
+    /// and `/dev/sr0`, and returns the first one whose `identify` report satisfies predicate,
+    /// e.g. the disk with a given serial number.
+    ///
+    /// Devices are opened like Scsi::new, read-write, which usually needs root or membership
+    /// of the `disk` group. Devices that can't be opened or identified are skipped, so
+    /// missing permissions show up as `None` rather than an error.
+    pub fn find<F>(mut predicate: F) -> crate::Result<Option<Scsi>>
+    where
+        F: FnMut(&DeviceIdentity) -> bool,
+    {
+        for path in scsi_block_devices()? {
+            let Ok(scsi) = Scsi::new(&path) else {
+                continue;
+            };
+
+            if scsi.identify().is_ok_and(|identity| predicate(&identity)) {
+                return Ok(Some(scsi));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+const SYS_BLOCK: &str = "/sys/block";
+
+// block devices backed by the SCSI mid layer, sorted so sdz comes before sdaa
+fn scsi_block_devices() -> crate::Result<Vec<PathBuf>> {
+    let mut names = vec![];
+
+    for entry in fs::read_dir(SYS_BLOCK)? {
+        let entry = entry?;
+        if entry.path().join("device/scsi_device").exists() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+
+    names.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+    Ok(names
+        .into_iter()
+        .map(|name| PathBuf::from("/dev").join(name))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // needs a real SCSI device with a unit serial number,
+    // e.g. SCSIR_TEST_DEVICE=/dev/sdb cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn find_test() {
+        let path = std::env::var("SCSIR_TEST_DEVICE").expect("SCSIR_TEST_DEVICE is not set");
+        let serial_number = Scsi::new(&path)
+            .unwrap()
+            .identify()
+            .unwrap()
+            .serial_number
+            .expect("no unit serial number");
+
+        let found = Scsi::find(|identity| identity.serial_number.as_ref() == Some(&serial_number))
+            .unwrap()
+            .expect("device not found");
+
+        assert_eq!(
+            found.identify().unwrap().serial_number,
+            Some(serial_number),
+            "serial number"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    command::write_buffer::{
+        ACTIVATE_DEFERRED_MICROCODE_MODE, DOWNLOAD_MICROCODE_DEFER_ACTIVATE_MODE,
+    },
+    Scsi,
+};
+
+impl Scsi {
+    /// Stages image in buffer_id with WRITE BUFFER mode 0x0E, chunk_size bytes at a time,
+    /// then activates it with mode 0x0F.
+    ///
+    /// The device may reset while activating, this Scsi should be reopened once it returns.
+    /// A wrong image, or an interrupted download, can leave the device unusable. chunk_size
+    /// should be a multiple of the offset boundary reported by READ BUFFER descriptor mode.
+    /// The 24 bit buffer offset limits image to 16 MiB, a larger one is rejected before
+    /// anything is sent.
+    pub fn download_firmware_deferred(
+        &self,
+        buffer_id: u8,
+        image: &[u8],
+        chunk_size: usize,
+    ) -> crate::Result<()> {
+        if image.is_empty() || chunk_size == 0 {
+            return Err(crate::Error::BadArgument(
+                "image and chunk size should not be empty.".to_owned(),
+            ));
+        }
+
+        if image.len() > MAX_IMAGE_LENGTH {
+            return Err(crate::Error::ArgumentOutOfBounds(format!(
+                "image length is out of bounds. The maximum possible value is {}, but {} was provided.",
+                MAX_IMAGE_LENGTH,
+                image.len()
+            )));
+        }
+
+        let mut offset = 0;
+        for chunk in image.chunks(chunk_size) {
+            self.write_buffer()
+                .mode(DOWNLOAD_MICROCODE_DEFER_ACTIVATE_MODE)
+                .buffer_id(buffer_id)
+                .buffer_offset(offset as u32)
+                .parameter(chunk)
+                .issue()?;
+            offset += chunk.len();
+        }
+
+        self.write_buffer()
+            .mode(ACTIVATE_DEFERRED_MICROCODE_MODE)
+            .issue()
+    }
+}
+
+// the buffer offset field of WRITE BUFFER is 24 bits wide
+const MAX_IMAGE_LENGTH: usize = 0x100_0000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_firmware_deferred_test() {
+        let scsi = Scsi::test_instance();
+        let image: Vec<u8> = (0..10).collect();
+
+        scsi.download_firmware_deferred(0x02, &image, 4).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 4, "three chunks and the activation");
+        for (index, (offset, length)) in [(0, 4), (4, 4), (8, 2)].into_iter().enumerate() {
+            assert_eq!(
+                issued[index].cdb,
+                [0x3B, 0x0E, 0x02, 0, 0, offset, 0, 0, length, 0],
+                "staging cdb {index}"
+            );
+            assert_eq!(
+                issued[index].data_out,
+                image[offset as usize..(offset + length) as usize],
+                "staging data {index}"
+            );
+        }
+        assert_eq!(
+            issued[3].cdb,
+            [0x3B, 0x0F, 0, 0, 0, 0, 0, 0, 0, 0],
+            "activation cdb"
+        );
+        assert!(issued[3].data_out.is_empty(), "activation data");
+
+        assert!(
+            scsi.download_firmware_deferred(0, &image, 0).is_err(),
+            "zero chunk size"
+        );
+    }
+
+    #[test]
+    fn oversized_image_test() {
+        let scsi = Scsi::test_instance();
+        let image = vec![0; MAX_IMAGE_LENGTH + 1];
+
+        assert!(
+            matches!(
+                scsi.download_firmware_deferred(0, &image, 0x10000),
+                Err(crate::Error::ArgumentOutOfBounds(_))
+            ),
+            "oversized image"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing sent");
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{
+    shortcut::inquiry::{
+        device_identification, standard_inquiry, unit_serial_number, Association, Identifier,
+    },
+    Scsi,
+};
+
+#[derive(Clone, Debug)]
+pub struct DeviceIdentity {
+    pub vendor_identification: String,
+    pub product_identification: String,
+    pub product_revision_level: String,
+    // None when the unit serial number VPD page is not supported
+    pub serial_number: Option<String>,
+    // NAA designator of the logical unit, None when the device identification VPD page
+    // is not supported or carries no NAA designator
+    pub world_wide_name: Option<Vec<u8>>,
+    pub logical_block_size: u32,
+    pub physical_block_size: u32,
+    pub capacity_in_bytes: u64,
+}
+
+impl Scsi {
+    /// Collects the standard INQUIRY data, the serial number and WWN VPD pages and
+    /// the READ CAPACITY data into one report.
+    ///
+    /// Optional VPD pages which the device rejects are reported as `None`.
+    /// READ CAPACITY(10) is used when READ CAPACITY(16) is not supported.
+    pub fn identify(&self) -> crate::Result<DeviceIdentity> {
+        let inquiry = standard_inquiry(&mut self.inquiry())?;
+
+        let serial_number = unit_serial_number(&mut self.inquiry())
+            .ok()
+            .map(|page| page.product_serial_number.trim().to_owned());
+
+        let world_wide_name = device_identification(&mut self.inquiry())
+            .ok()
+            .and_then(|page| {
+                page.descriptors.into_iter().find_map(|descriptor| {
+                    match (descriptor.association, descriptor.identifier) {
+                        (
+                            Association::AddressedPhysicalOrLogicalDevice,
+                            Identifier::Binary(identifier),
+                        ) if descriptor.identifier_type == NAA_IDENTIFIER_TYPE => Some(identifier),
+                        _ => None,
+                    }
+                })
+            });
+
+        let (logical_block_size, physical_block_size, capacity_in_blocks) =
+            match self.read_capacity().issue_16() {
+                Ok(capacity) => (
+                    capacity.logical_block_length_in_bytes,
+                    capacity.physical_block_size(),
+                    capacity.returned_logical_block_address.saturating_add(1),
+                ),
+                Err(_) => {
+                    let capacity = self.read_capacity().issue_10()?;
+                    (
+                        capacity.block_length_in_bytes,
+                        capacity.block_length_in_bytes,
+                        capacity.returned_logical_block_address as u64 + 1,
+                    )
+                }
+            };
+
+        Ok(DeviceIdentity {
+            vendor_identification: inquiry.t10_vendor_identification.trim().to_owned(),
+            product_identification: inquiry.product_identification.trim().to_owned(),
+            product_revision_level: inquiry.product_revision_level.trim().to_owned(),
+            serial_number,
+            world_wide_name,
+            logical_block_size,
+            physical_block_size,
+            capacity_in_bytes: capacity_in_blocks.saturating_mul(logical_block_size as u64),
+        })
+    }
+}
+
+const NAA_IDENTIFIER_TYPE: u8 = 0x3;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standard_inquiry_data() -> Vec<u8> {
+        let mut data = vec![0; 96];
+        data[2] = 0x06;
+        data[3] = 0x02;
+        data[4] = 91;
+        data[8..16].copy_from_slice(b"VENDOR  ");
+        data[16..32].copy_from_slice(b"PRODUCT         ");
+        data[32..36].copy_from_slice(b"1.0 ");
+        data
+    }
+
+    fn unit_serial_number_data() -> Vec<u8> {
+        let mut data = vec![0x00, 0x80, 0x00, 0x0A];
+        data.extend_from_slice(b"  SN123456");
+        data
+    }
+
+    fn device_identification_data() -> Vec<u8> {
+        vec![
+            0x00, 0x83, 0x00, 0x0C, // page header
+            0x01, 0x03, 0x00, 0x08, // binary, logical unit, NAA
+            0x50, 0x00, 0xC5, 0x00, 0x12, 0x34, 0x56, 0x78,
+        ]
+    }
+
+    fn read_capacity_16_data() -> Vec<u8> {
+        let mut data = vec![0; 32];
+        data[..8].copy_from_slice(&0x3FFu64.to_be_bytes());
+        data[8..12].copy_from_slice(&512u32.to_be_bytes());
+        data[13] = 0x03;
+        data
+    }
+
+    #[test]
+    fn identify_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&standard_inquiry_data());
+        scsi.mock().push_data(&unit_serial_number_data());
+        scsi.mock().push_data(&unit_serial_number_data());
+        scsi.mock().push_data(&device_identification_data());
+        scsi.mock().push_data(&device_identification_data());
+        scsi.mock().push_data(&read_capacity_16_data());
+
+        let identity = scsi.identify().unwrap();
+
+        assert_eq!(identity.vendor_identification, "VENDOR", "vendor");
+        assert_eq!(identity.product_identification, "PRODUCT", "product");
+        assert_eq!(identity.product_revision_level, "1.0", "revision");
+        assert_eq!(
+            identity.serial_number.as_deref(),
+            Some("SN123456"),
+            "serial"
+        );
+        assert_eq!(
+            identity.world_wide_name,
+            Some(vec![0x50, 0x00, 0xC5, 0x00, 0x12, 0x34, 0x56, 0x78]),
+            "world wide name"
+        );
+        assert_eq!(identity.logical_block_size, 512, "logical block size");
+        assert_eq!(identity.physical_block_size, 4096, "physical block size");
+        assert_eq!(identity.capacity_in_bytes, 0x400 * 512, "capacity");
+    }
+
+    #[test]
+    fn missing_vpd_test() {
+        let illegal_request = [
+            0x70, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x24, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&standard_inquiry_data());
+        scsi.mock().push_sense(&illegal_request);
+        scsi.mock().push_sense(&illegal_request);
+        scsi.mock().push_sense(&illegal_request);
+
+        let mut read_capacity_10 = vec![];
+        read_capacity_10.extend_from_slice(&0xFFu32.to_be_bytes());
+        read_capacity_10.extend_from_slice(&4096u32.to_be_bytes());
+        scsi.mock().push_data(&read_capacity_10);
+
+        let identity = scsi.identify().unwrap();
+
+        assert_eq!(identity.serial_number, None, "serial");
+        assert_eq!(identity.world_wide_name, None, "world wide name");
+        assert_eq!(identity.logical_block_size, 4096, "logical block size");
+        assert_eq!(identity.physical_block_size, 4096, "physical block size");
+        assert_eq!(identity.capacity_in_bytes, 0x100 * 4096, "capacity");
+    }
+}
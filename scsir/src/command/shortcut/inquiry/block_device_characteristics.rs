@@ -17,6 +17,52 @@ pub struct BlockDeviceCharacteristics {
     pub vbuls: bool,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RotationRate {
+    NotReported,
+    // solid state or another medium that does not rotate
+    NonRotating,
+    Rpm(u16),
+    Reserved(u16),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NominalFormFactor {
+    NotReported,
+    FiveAndOneQuarterInch,
+    ThreeAndOneHalfInch,
+    TwoAndOneHalfInch,
+    OnePointEightInch,
+    LessThanOnePointEightInch,
+    Reserved(u8),
+}
+
+impl BlockDeviceCharacteristics {
+    pub fn rotation_rate(&self) -> RotationRate {
+        match self.medium_rotation_rate {
+            0x0000 => RotationRate::NotReported,
+            0x0001 => RotationRate::NonRotating,
+            0x0401..=0xFFFE => RotationRate::Rpm(self.medium_rotation_rate),
+            other => RotationRate::Reserved(other),
+        }
+    }
+
+    pub fn form_factor(&self) -> NominalFormFactor {
+        match self.nominal_form_factor {
+            0x0 => NominalFormFactor::NotReported,
+            0x1 => NominalFormFactor::FiveAndOneQuarterInch,
+            0x2 => NominalFormFactor::ThreeAndOneHalfInch,
+            0x3 => NominalFormFactor::TwoAndOneHalfInch,
+            0x4 => NominalFormFactor::OnePointEightInch,
+            0x5 => NominalFormFactor::LessThanOnePointEightInch,
+            other => NominalFormFactor::Reserved(other),
+        }
+    }
+}
+
+// fields a short page does not cover read as zero, i.e. not reported
 pub fn block_device_characteristics(
     this: &mut InquiryCommand,
 ) -> crate::Result<BlockDeviceCharacteristics> {
@@ -80,4 +126,48 @@ mod tests {
             concat!("Size of: ", stringify!(Page))
         );
     }
+
+    fn push_page(scsi: &crate::Scsi, rotation_rate: u16, form_factor: u8) {
+        let mut page = vec![0x00, 0xB1, 0x00, 0x3C];
+        page.extend_from_slice(&rotation_rate.to_be_bytes());
+        page.extend_from_slice(&[0x00, form_factor]);
+        page.resize(PAGE_LENGTH, 0);
+        scsi.mock().push_data(&page);
+    }
+
+    #[test]
+    fn rotation_rate_test() {
+        let scsi = crate::Scsi::test_instance();
+        push_page(&scsi, 0x0001, 0x03);
+        push_page(&scsi, 7200, 0x02);
+        scsi.mock().push_data(&[0x00, 0xB1, 0x00, 0x00]);
+
+        let ssd = block_device_characteristics(&mut scsi.inquiry()).unwrap();
+        assert_eq!(ssd.rotation_rate(), RotationRate::NonRotating, "ssd");
+        assert_eq!(
+            ssd.form_factor(),
+            NominalFormFactor::TwoAndOneHalfInch,
+            "ssd form factor"
+        );
+
+        let hdd = block_device_characteristics(&mut scsi.inquiry()).unwrap();
+        assert_eq!(hdd.rotation_rate(), RotationRate::Rpm(7200), "hdd");
+        assert_eq!(
+            hdd.form_factor(),
+            NominalFormFactor::ThreeAndOneHalfInch,
+            "hdd form factor"
+        );
+
+        let short = block_device_characteristics(&mut scsi.inquiry()).unwrap();
+        assert_eq!(
+            short.rotation_rate(),
+            RotationRate::NotReported,
+            "short page"
+        );
+        assert_eq!(
+            short.form_factor(),
+            NominalFormFactor::NotReported,
+            "short page form factor"
+        );
+    }
 }
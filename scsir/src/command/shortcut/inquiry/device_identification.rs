@@ -29,6 +29,7 @@ pub enum Identifier {
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Association {
     AddressedPhysicalOrLogicalDevice,
     PortThatReceivedTheRequest,
@@ -156,4 +157,24 @@ mod tests {
             concat!("Size of: ", stringify!(DescriptorHeader))
         );
     }
+
+    #[test]
+    fn unknown_code_test() {
+        let (descriptor, bytes) =
+            IdentificationDescriptor::from_bytes(&[0xA1, 0x93, 0x00, 0x02, 0xAB, 0xCD, 0x01]);
+        assert!(
+            matches!(
+                descriptor.protocol_identifier,
+                ProtocolIdentifier::Other(0xA)
+            ),
+            "protocol identifier"
+        );
+        assert_eq!(bytes, [0x01], "remaining");
+
+        let (descriptor, _) = IdentificationDescriptor::from_bytes(&[0x01, 0x33, 0x00, 0x00]);
+        assert!(
+            matches!(descriptor.association, Association::Other(0x3)),
+            "association"
+        );
+    }
 }
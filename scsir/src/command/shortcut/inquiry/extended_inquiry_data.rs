@@ -35,6 +35,24 @@ pub struct ExtendedInquiryData {
     pub maximum_supported_sense_data_length: u8,
 }
 
+impl ExtendedInquiryData {
+    // protection information types the logical unit can be formatted with, decoded from
+    // supported_protection_type for direct access block devices. only meaningful when
+    // PROTECT is set in the standard inquiry data, empty for the reserved code
+    pub fn supported_protection_types(&self) -> &'static [u8] {
+        match self.supported_protection_type {
+            0b000 => &[1],
+            0b001 => &[1, 2],
+            0b010 => &[2],
+            0b011 => &[1, 3],
+            0b100 => &[3],
+            0b101 => &[2, 3],
+            0b111 => &[1, 2, 3],
+            _ => &[],
+        }
+    }
+}
+
 pub fn extended_inquiry_data(this: &mut InquiryCommand) -> crate::Result<ExtendedInquiryData> {
     this.page_code(Some(PAGE_CODE));
 
@@ -140,4 +158,32 @@ mod tests {
             concat!("Size of: ", stringify!(Page))
         );
     }
+
+    #[test]
+    fn supported_protection_types_test() {
+        let mut page = [0; PAGE_LENGTH];
+        page[1] = PAGE_CODE;
+        page[3] = 0x3C;
+        page[4] = 0b00_011_101;
+        page[13] = 0xFC;
+
+        let scsi = crate::Scsi::test_instance();
+        scsi.mock().push_data(&page);
+        page[4] = 0b00_110_000;
+        scsi.mock().push_data(&page);
+
+        let data = extended_inquiry_data(&mut scsi.inquiry()).unwrap();
+        assert_eq!(data.supported_protection_types(), [1, 3], "types 1 and 3");
+        assert!(data.guard_check, "guard check");
+        assert!(!data.application_tag_check, "application tag check");
+        assert!(data.reference_tag_check, "reference tag check");
+        assert_eq!(
+            data.maximum_supported_sense_data_length, 0xFC,
+            "maximum sense data length"
+        );
+
+        let data = extended_inquiry_data(&mut scsi.inquiry()).unwrap();
+        assert!(data.supported_protection_types().is_empty(), "reserved");
+        assert!(!data.guard_check, "no guard check");
+    }
 }
@@ -17,7 +17,9 @@ mod unit_serial_number;
 mod zoned_block_device_characteristics;
 
 pub use ascii_information::ascii_information;
-pub use block_device_characteristics::block_device_characteristics;
+pub use block_device_characteristics::{
+    block_device_characteristics, NominalFormFactor, RotationRate,
+};
 pub use block_device_characteristics_extension::block_device_characteristics_extension;
 pub use block_limits::block_limits;
 pub use block_limits_extension::block_limits_extension;
@@ -1,4 +1,5 @@
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ProtocolIdentifier {
     None,
     FibreChannel,
@@ -8,4 +9,4 @@ pub enum ProtocolIdentifier {
     InternetScsi,
     SasSerialScsiProtocol,
     Other(u8),
-}
\ No newline at end of file
+}
@@ -1,10 +1,10 @@
 #![allow(dead_code)]
 
-use std::mem::size_of;
+use std::{fmt::Display, mem::size_of};
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{command::inquiry::InquiryCommand, data_wrapper::FlexibleStruct};
+use crate::{command::inquiry::InquiryCommand, data_wrapper::FlexibleStruct, PeripheralDeviceType};
 
 #[derive(Debug)]
 pub struct StandardInquiryData {
@@ -93,6 +93,73 @@ pub fn standard_inquiry(this: &mut InquiryCommand) -> crate::Result<StandardInqu
     })
 }
 
+// laid out like the standard INQUIRY output of sg_inq
+impl Display for StandardInquiryData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let version = match self.version {
+            0x00 => "no conformance claimed",
+            0x03 => "SPC",
+            0x04 => "SPC-2",
+            0x05 => "SPC-3",
+            0x06 => "SPC-4",
+            0x07 => "SPC-5",
+            _ => "unknown",
+        };
+
+        writeln!(f, "standard INQUIRY:")?;
+        writeln!(
+            f,
+            "  PQual={}  PDT={}  RMB={}  version=0x{:02x}  [{}]",
+            self.peripheral_qualifier,
+            self.peripheral_device_type,
+            self.removable_media as u8,
+            self.version,
+            version
+        )?;
+        writeln!(
+            f,
+            "  NormACA={}  HiSUP={}  Resp_data_format={}",
+            self.normal_aca_supported as u8,
+            self.hierarchical_support as u8,
+            self.response_data_format
+        )?;
+        writeln!(
+            f,
+            "  SCCS={}  ACC={}  TPGS={}  3PC={}  Protect={}",
+            self.scc_supported as u8,
+            self.access_controls_coordinator as u8,
+            self.target_port_group_support,
+            self.third_party_copy as u8,
+            self.protect as u8
+        )?;
+        writeln!(
+            f,
+            "  EncServ={}  MultiP={}  CmdQue={}",
+            self.enclosure_services as u8, self.multi_port as u8, self.command_queuing as u8
+        )?;
+        writeln!(
+            f,
+            " Peripheral device type: {}",
+            PeripheralDeviceType::from(self.peripheral_device_type)
+        )?;
+        writeln!(
+            f,
+            " Vendor identification: {}",
+            self.t10_vendor_identification.trim()
+        )?;
+        writeln!(
+            f,
+            " Product identification: {}",
+            self.product_identification.trim()
+        )?;
+        write!(
+            f,
+            " Product revision level: {}",
+            self.product_revision_level.trim()
+        )
+    }
+}
+
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
 struct PageHeader {
@@ -153,4 +220,35 @@ mod tests {
             concat!("Size of: ", stringify!(PageHeader))
         );
     }
+
+    #[test]
+    fn display_test() {
+        let scsi = crate::Scsi::test_instance();
+        let mut data = vec![0; 96];
+        data[2] = 0x06;
+        data[3] = 0x12;
+        data[4] = 91;
+        data[7] = 0x02;
+        data[8..16].copy_from_slice(b"VENDOR  ");
+        data[16..32].copy_from_slice(b"PRODUCT         ");
+        data[32..36].copy_from_slice(b"1.0 ");
+        scsi.mock().push_data(&data);
+        scsi.mock().push_data(&data);
+
+        let output = standard_inquiry(&mut scsi.inquiry()).unwrap().to_string();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "standard INQUIRY:", "title");
+        assert_eq!(
+            lines[1], "  PQual=0  PDT=0  RMB=0  version=0x06  [SPC-4]",
+            "version line"
+        );
+        assert_eq!(
+            lines[4], "  EncServ=0  MultiP=0  CmdQue=1",
+            "command queuing line"
+        );
+        assert_eq!(lines[5], " Peripheral device type: disk", "device type");
+        assert_eq!(lines[6], " Vendor identification: VENDOR", "vendor");
+        assert_eq!(lines[8], " Product revision level: 1.0", "revision");
+    }
 }
@@ -0,0 +1,112 @@
+use crate::{shortcut::inquiry::block_limits, Scsi};
+
+impl Scsi {
+    /// The largest number of logical blocks a single READ or WRITE should transfer, the
+    /// size chunked transfers should use.
+    ///
+    /// This is the smaller of the MAXIMUM TRANSFER LENGTH of the Block Limits VPD page and
+    /// the SG reserved size converted to blocks. If the device rejects the VPD page with
+    /// ILLEGAL REQUEST, or the page reports no limit, the reserved size alone is used, and
+    /// 64 KiB where there is no SG reserved size, as on Windows. Any other error reading
+    /// either is returned. The result is at least 1.
+    pub fn max_transfer_blocks(&self) -> crate::Result<u32> {
+        let block_size = self.block_size()?;
+        if block_size == 0 {
+            return Err(crate::Error::BadArgument(
+                "logical block size should not be 0.".to_owned(),
+            ));
+        }
+
+        // Unsupported is the platform lacking an SG reserved size, device errors are returned
+        let reserved_size = match self.sg_reserved_size() {
+            Ok(0) | Err(crate::Error::Unsupported(_)) => DEFAULT_TRANSFER_BYTES,
+            result => result?,
+        };
+        let reserved_blocks = u32::max(reserved_size / block_size, 1);
+
+        // 0 means the device reports no limit
+        let maximum_transfer_length = match block_limits(&mut self.inquiry()) {
+            Ok(limits) => limits.maximum_transfer_length,
+            Err(e) if e.is_illegal_request() => 0,
+            Err(e) => return Err(e),
+        };
+
+        Ok(match maximum_transfer_length {
+            0 => reserved_blocks,
+            length => u32::min(length, reserved_blocks),
+        })
+    }
+}
+
+const DEFAULT_TRANSFER_BYTES: u32 = 64 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ILLEGAL_REQUEST: [u8; 18] = [
+        0x70, 0x00, 0x05, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x24, 0x00, 0, 0, 0, 0,
+    ];
+
+    fn block_limits_page(maximum_transfer_length: u32) -> Vec<u8> {
+        let mut page = vec![0; 64];
+        page[1] = 0xB0;
+        page[3] = 0x3C;
+        page[8..12].copy_from_slice(&maximum_transfer_length.to_be_bytes());
+        page
+    }
+
+    #[test]
+    fn max_transfer_blocks_test() {
+        let scsi = Scsi::test_instance();
+        scsi.set_cached_block_size(512);
+        scsi.mock().set_reserved_size(128 * 1024);
+
+        scsi.mock().push_data(&block_limits_page(64));
+        assert_eq!(scsi.max_transfer_blocks().unwrap(), 64, "vpd limit");
+
+        scsi.mock().push_data(&block_limits_page(1024));
+        assert_eq!(scsi.max_transfer_blocks().unwrap(), 256, "reserved size");
+
+        scsi.mock().push_data(&block_limits_page(0));
+        assert_eq!(scsi.max_transfer_blocks().unwrap(), 256, "no vpd limit");
+
+        scsi.mock().push_sense(&ILLEGAL_REQUEST);
+        assert_eq!(scsi.max_transfer_blocks().unwrap(), 256, "no vpd page");
+        assert_eq!(
+            scsi.mock().issued()[0].cdb[..3],
+            [0x12, 0x01, 0xB0],
+            "inquiry"
+        );
+    }
+
+    #[test]
+    fn default_transfer_test() {
+        let scsi = Scsi::test_instance();
+        scsi.set_cached_block_size(4096);
+
+        scsi.mock().push_sense(&ILLEGAL_REQUEST);
+        assert_eq!(scsi.max_transfer_blocks().unwrap(), 16, "default size");
+    }
+
+    #[test]
+    fn vpd_error_test() {
+        // NOT READY, LOGICAL UNIT NOT READY
+        let mut not_ready = ILLEGAL_REQUEST;
+        not_ready[2] = 0x02;
+        not_ready[12] = 0x04;
+
+        let scsi = Scsi::test_instance();
+        scsi.set_cached_block_size(512);
+        scsi.mock().set_reserved_size(128 * 1024);
+        scsi.mock().push_sense(&not_ready);
+
+        assert!(
+            matches!(
+                scsi.max_transfer_blocks(),
+                Err(crate::Error::CheckCondition(_))
+            ),
+            "not ready returned"
+        );
+    }
+}
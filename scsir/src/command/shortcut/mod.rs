@@ -1,3 +1,25 @@
+mod ata_identify;
+mod ata_smart;
+mod block_size;
+pub mod diagnostic;
+mod dpo_fua;
+#[cfg(target_os = "linux")]
+mod find;
+mod firmware;
+mod identify;
 pub mod inquiry;
 pub mod log;
+mod max_transfer;
 pub mod mode;
+mod reassign;
+mod self_test;
+mod sense_format;
+mod surface_scan;
+mod vpd_dump;
+mod write_pattern;
+mod wwn;
+
+pub use ata_identify::AtaIdentity;
+pub use ata_smart::{SmartAttribute, SmartData};
+pub use identify::DeviceIdentity;
+pub use self_test::{SelfTestKind, SelfTestResult};
@@ -2,7 +2,9 @@
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::command::get_array;
+use crate::command::{bitfield_bound_check, get_array};
+
+use super::{HeaderStorage, HeaderType, LongHeader, ShortHeader};
 
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
@@ -74,6 +76,55 @@ impl DescriptorStorage {
     }
 }
 
+// builds a mode parameter list holding only a header and one block descriptor, for mode select.
+// use HeaderType::Short with mode select(6) and HeaderType::Long with mode select(10),
+// a long descriptor requires a long header
+pub fn build_block_descriptor(
+    header_type: HeaderType,
+    descriptor_type: DescriptorType,
+    number_of_blocks: u64,
+    logical_block_length: u32,
+) -> crate::Result<Vec<u8>> {
+    let descriptor = match descriptor_type {
+        DescriptorType::Short => {
+            bitfield_bound_check!(number_of_blocks, 32, "number of blocks")?;
+            bitfield_bound_check!(logical_block_length, 24, "logical block length")?;
+            DescriptorStorage::Short(
+                ShortDescriptor::new()
+                    .with_number_of_blocks(number_of_blocks as u32)
+                    .with_logical_block_length(logical_block_length),
+            )
+        }
+        DescriptorType::Long => DescriptorStorage::Long(
+            LongDescriptor::new()
+                .with_number_of_blocks(number_of_blocks)
+                .with_logical_block_length(logical_block_length),
+        ),
+    };
+    let descriptor = descriptor.to_bytes();
+
+    let mut bytes = match (header_type, descriptor_type) {
+        (HeaderType::Short, DescriptorType::Short) => HeaderStorage::Short(
+            ShortHeader::new().with_block_descriptor_length(descriptor.len() as u8),
+        )
+        .to_bytes(),
+        (HeaderType::Short, DescriptorType::Long) => {
+            return Err(crate::Error::BadArgument(
+                "long descriptor is not allowed with short header".to_owned(),
+            ))
+        }
+        (HeaderType::Long, _) => HeaderStorage::Long(
+            LongHeader::new()
+                .with_long_lba(matches!(descriptor_type, DescriptorType::Long).into())
+                .with_block_descriptor_length(descriptor.len() as u16),
+        )
+        .to_bytes(),
+    };
+    bytes.extend_from_slice(&descriptor);
+
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,4 +147,43 @@ mod tests {
             concat!("Size of: ", stringify!(LongDescriptor))
         );
     }
+
+    #[test]
+    fn build_block_descriptor_test() {
+        assert_eq!(
+            build_block_descriptor(HeaderType::Short, DescriptorType::Short, 0, 4096).unwrap(),
+            [0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00],
+            "short header, short descriptor"
+        );
+
+        assert_eq!(
+            build_block_descriptor(HeaderType::Long, DescriptorType::Short, 0x1000, 512).unwrap(),
+            [
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+                0x02, 0x00
+            ],
+            "long header, short descriptor"
+        );
+
+        assert_eq!(
+            build_block_descriptor(HeaderType::Long, DescriptorType::Long, 0x1_0000_0000, 4096)
+                .unwrap(),
+            [
+                0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00
+            ],
+            "long header, long descriptor"
+        );
+
+        assert!(
+            build_block_descriptor(HeaderType::Short, DescriptorType::Long, 0, 4096).is_err(),
+            "short header, long descriptor"
+        );
+
+        assert!(
+            build_block_descriptor(HeaderType::Short, DescriptorType::Short, 0x1_0000_0000, 512)
+                .is_err(),
+            "number of blocks out of bounds"
+        );
+    }
 }
@@ -0,0 +1,67 @@
+#![allow(dead_code)]
+
+use modular_bitfield_msb::prelude::*;
+
+use crate::command::get_array;
+
+use super::ModePage;
+
+pub const DISCONNECT_RECONNECT_PAGE_CODE: u8 = 0x02;
+pub const DISCONNECT_RECONNECT_SUBPAGE_CODE: u8 = 0x00;
+
+// the protocol independent layout of SPC, as used by parallel SCSI targets;
+// Fibre Channel and SAS targets define their own in DisconnectReconnectFcPage and
+// DisconnectReconnectSasPage
+#[bitfield]
+#[derive(Clone, Copy, Debug)]
+pub struct DisconnectReconnectPage {
+    pub parameters_saveable: B1,
+    pub subpage_format: B1,
+    pub page_code: B6,
+    pub page_length: B8,
+    pub buffer_full_ratio: B8,
+    pub buffer_empty_ratio: B8,
+    pub bus_inactivity_limit: B16,
+    pub disconnect_time_limit: B16,
+    pub connect_time_limit: B16,
+    pub maximum_burst_size: B16,
+    pub enable_modify_data_pointers: B1,
+    pub fair_arbitration: B3,
+    pub disconnect_immediate: B1,
+    pub data_transfer_disconnect_control: B3,
+    reserved: B8,
+    pub first_burst_size: B16,
+}
+
+impl ModePage for DisconnectReconnectPage {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]) {
+        let (array, bytes) = get_array(bytes);
+
+        (Self::from_bytes(array), bytes)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    const PAGE_LENGTH: usize = 16;
+
+    #[test]
+    fn layout_test() {
+        assert_eq!(
+            size_of::<DisconnectReconnectPage>(),
+            PAGE_LENGTH,
+            concat!("Size of: ", stringify!(DisconnectReconnectPage))
+        );
+    }
+}
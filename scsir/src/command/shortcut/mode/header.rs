@@ -6,6 +6,8 @@ use modular_bitfield_msb::prelude::*;
 
 use crate::command::get_array;
 
+use super::DescriptorType;
+
 #[bitfield]
 #[derive(Clone, Copy, Debug)]
 pub struct ShortHeader {
@@ -104,6 +106,16 @@ impl HeaderStorage {
         }
     }
 
+    // the block descriptors are 16 bytes long only when LONGLBA of a MODE SENSE(10)
+    // header is set, a MODE SENSE(6) header never has it
+    pub fn descriptor_type(&self) -> DescriptorType {
+        if self.long_lba() {
+            DescriptorType::Long
+        } else {
+            DescriptorType::Short
+        }
+    }
+
     pub fn block_descriptor_length(&self) -> u16 {
         match self {
             HeaderStorage::Short(h) => h.block_descriptor_length() as u16,
@@ -111,6 +123,21 @@ impl HeaderStorage {
         }
     }
 
+    // MODE SELECT requires the mode data length, the medium type and
+    // the device specific parameter to be zero, only LONGLBA is kept
+    pub fn for_mode_select(&self, block_descriptor_length: u16) -> Self {
+        match self {
+            HeaderStorage::Short(_) => HeaderStorage::Short(
+                ShortHeader::new().with_block_descriptor_length(block_descriptor_length as u8),
+            ),
+            HeaderStorage::Long(h) => HeaderStorage::Long(
+                LongHeader::new()
+                    .with_long_lba(h.long_lba())
+                    .with_block_descriptor_length(block_descriptor_length),
+            ),
+        }
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
             HeaderStorage::Short(h) => h.bytes.to_vec(),
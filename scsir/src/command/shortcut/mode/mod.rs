@@ -6,6 +6,7 @@ mod command_duration_limit;
 mod control;
 mod control_extension;
 mod descriptor;
+mod disconnect_reconnect;
 mod disconnect_reconnect_fc;
 mod disconnect_reconnect_sas;
 mod enhanced_phy_control;
@@ -35,6 +36,7 @@ pub use command_duration_limit::*;
 pub use control::*;
 pub use control_extension::*;
 pub use descriptor::*;
+pub use disconnect_reconnect::*;
 pub use disconnect_reconnect_fc::*;
 pub use disconnect_reconnect_sas::*;
 pub use enhanced_phy_control::*;
@@ -47,6 +47,44 @@ impl<Page: ModePage> PageWrapper<Page> {
         }
     }
 
+    // like from_bytes, with the descriptor type taken from LONGLBA of the header
+    pub fn from_mode_sense(header_type: HeaderType, bytes: &[u8]) -> Self {
+        let (header, _) = HeaderStorage::from_bytes(header_type, bytes);
+
+        Self::from_bytes(header_type, header.descriptor_type(), bytes)
+    }
+
+    pub fn is_write_protected(&self) -> bool {
+        self.header.write_protect()
+    }
+
+    pub fn dpofua_supported(&self) -> bool {
+        self.header.dpo_and_fua_support()
+    }
+
+    // the bytes to send back with MODE SELECT after changing a page read by MODE SENSE,
+    // with the fields MODE SELECT requires to be zero cleared, including the PS bit
+    pub fn to_mode_select_parameter_list(&self) -> Vec<u8> {
+        let mut descriptor_bytes = vec![];
+        for item in &self.descriptors {
+            descriptor_bytes.extend_from_slice(&item.to_bytes());
+        }
+
+        let mut bytes = self
+            .header
+            .for_mode_select(descriptor_bytes.len() as u16)
+            .to_bytes();
+        bytes.extend_from_slice(&descriptor_bytes);
+
+        let mut page_bytes = self.page.to_bytes();
+        if let Some(first) = page_bytes.first_mut() {
+            *first &= !PARAMETERS_SAVEABLE_MASK;
+        }
+        bytes.extend_from_slice(&page_bytes);
+
+        bytes
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![];
         bytes.extend_from_slice(&self.header.to_bytes());
@@ -60,3 +98,129 @@ impl<Page: ModePage> PageWrapper<Page> {
         bytes
     }
 }
+
+const PARAMETERS_SAVEABLE_MASK: u8 = 0b1000_0000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcut::mode::CachingPage;
+
+    #[test]
+    fn mode_select_round_trip_test() {
+        let mut sense = vec![
+            0x1F,
+            0x05,
+            0b1001_0000,
+            0x08, // header
+            0x00,
+            0x00,
+            0x10,
+            0x00,
+            0x00,
+            0x00,
+            0x02,
+            0x00, // block descriptor
+            0x88,
+            0x12,
+            0x00, // caching page with PS set
+        ];
+        sense.resize(4 + 8 + 20, 0);
+
+        let mut wrapper: PageWrapper<CachingPage> =
+            PageWrapper::from_bytes(HeaderType::Short, DescriptorType::Short, &sense);
+
+        assert!(wrapper.is_write_protected(), "write protected");
+        assert!(wrapper.dpofua_supported(), "dpofua supported");
+
+        wrapper.page.set_write_cache_enable(1);
+        let bytes = wrapper.to_mode_select_parameter_list();
+
+        let mut expected = vec![
+            0x00, 0x00, 0x00, 0x08, // header
+            0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, // block descriptor
+            0x08, 0x12, 0x04, // caching page with PS cleared and WCE set
+        ];
+        expected.resize(4 + 8 + 20, 0);
+        assert_eq!(bytes, expected, "parameter list");
+
+        let wrapper: PageWrapper<CachingPage> =
+            PageWrapper::from_bytes(HeaderType::Short, DescriptorType::Short, &bytes);
+        assert_eq!(wrapper.page.write_cache_enable(), 1, "write cache enable");
+        assert_eq!(wrapper.descriptors.len(), 1, "descriptors");
+    }
+
+    #[test]
+    fn mode_sense_6_test() {
+        let mut sense = vec![
+            0x1F, 0x00, 0x00, 0x08, // header, block descriptor length in byte 3
+            0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, // block descriptor
+            0x08, 0x12, 0x04, // caching page with WCE set
+        ];
+        sense.resize(4 + 8 + 20, 0);
+
+        let wrapper: PageWrapper<CachingPage> =
+            PageWrapper::from_mode_sense(HeaderType::Short, &sense);
+
+        assert_eq!(wrapper.header.mode_data_length(), 0x1F, "mode data length");
+        assert_eq!(
+            wrapper.header.block_descriptor_length(),
+            8,
+            "descriptor length"
+        );
+        assert!(!wrapper.header.long_lba(), "long lba");
+        assert_eq!(wrapper.descriptors.len(), 1, "descriptors");
+        assert!(
+            matches!(wrapper.descriptors[0], DescriptorStorage::Short(_)),
+            "short descriptor"
+        );
+        assert_eq!(wrapper.descriptors[0].number_of_blocks(), 0x1000, "blocks");
+        assert_eq!(
+            wrapper.descriptors[0].logical_block_length(),
+            0x200,
+            "block length"
+        );
+        assert_eq!(wrapper.page.page_code(), 0x08, "page code");
+        assert_eq!(wrapper.page.write_cache_enable(), 1, "write cache enable");
+    }
+
+    #[test]
+    fn mode_sense_10_test() {
+        let mut sense = vec![
+            0x00, 0x2E, 0x00, 0x00, 0x01, 0x00, // header with LONGLBA set
+            0x00, 0x10, // block descriptor length in bytes 6 and 7
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // long block descriptor
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, //
+            0x08, 0x12, 0x04, // caching page with WCE set
+        ];
+        sense.resize(8 + 16 + 20, 0);
+
+        let wrapper: PageWrapper<CachingPage> =
+            PageWrapper::from_mode_sense(HeaderType::Long, &sense);
+
+        assert_eq!(wrapper.header.mode_data_length(), 0x2E, "mode data length");
+        assert_eq!(
+            wrapper.header.block_descriptor_length(),
+            16,
+            "descriptor length"
+        );
+        assert!(wrapper.header.long_lba(), "long lba");
+        assert_eq!(wrapper.descriptors.len(), 1, "descriptors");
+        assert!(
+            matches!(wrapper.descriptors[0], DescriptorStorage::Long(_)),
+            "long descriptor"
+        );
+        assert_eq!(
+            wrapper.descriptors[0].number_of_blocks(),
+            0x1_0000_0000,
+            "blocks"
+        );
+        assert_eq!(
+            wrapper.descriptors[0].logical_block_length(),
+            0x1000,
+            "block length"
+        );
+        assert_eq!(wrapper.page.page_code(), 0x08, "page code");
+        assert_eq!(wrapper.page.write_cache_enable(), 1, "write cache enable");
+    }
+}
@@ -27,9 +27,9 @@ pub struct PhyControlAndDiscoverPageHeader {
     pub page_length: B16,
     reserved_0: B8,
     reserved_1: B4,
-    protocol_identifier: B4,
-    generation_code: B8,
-    number_of_phys: B8,
+    pub protocol_identifier: B4,
+    pub generation_code: B8,
+    pub number_of_phys: B8,
 }
 
 #[bitfield]
@@ -66,6 +66,50 @@ pub struct PhyControlAndDiscoverPageDescriptor {
     reserved_10: B32,
 }
 
+#[derive(Clone, Debug)]
+pub enum ProtocolSpecificPortPhys {
+    Sas(PhyControlAndDiscoverPage),
+    // the whole subpage, for protocols other than SAS
+    Other {
+        protocol_identifier: u8,
+        bytes: Vec<u8>,
+    },
+}
+
+impl PhyControlAndDiscoverPage {
+    pub fn header(&self) -> &PhyControlAndDiscoverPageHeader {
+        &self.header
+    }
+
+    pub fn descriptors(&self) -> &[PhyControlAndDiscoverPageDescriptor] {
+        &self.descriptors
+    }
+}
+
+// decodes the protocol specific port subpage 0x01, bytes start at its page header
+pub fn protocol_specific_port(bytes: &[u8]) -> ProtocolSpecificPortPhys {
+    let (array, _) = get_array(bytes);
+    let header = PhyControlAndDiscoverPageHeader::from_bytes(array);
+
+    if header.protocol_identifier() == SAS_PROTOCOL_IDENTIFIER {
+        return ProtocolSpecificPortPhys::Sas(
+            <PhyControlAndDiscoverPage as ModePage>::from_bytes(bytes).0,
+        );
+    }
+
+    let length = usize::min(
+        header.page_length() as usize + size_of::<page_header::CommomSubpageHeader>(),
+        bytes.len(),
+    );
+
+    ProtocolSpecificPortPhys::Other {
+        protocol_identifier: header.protocol_identifier(),
+        bytes: bytes[..length].to_vec(),
+    }
+}
+
+const SAS_PROTOCOL_IDENTIFIER: u8 = 0x6;
+
 impl ModePage for PhyControlAndDiscoverPage {
     fn new() -> Self {
         Self {
@@ -139,4 +183,76 @@ mod tests {
             concat!("Size of: ", stringify!(PhyControlAndDiscoverPageDescriptor))
         );
     }
+
+    fn descriptor(phy_identifier: u8, sas_address: u64) -> Vec<u8> {
+        let mut bytes = vec![0; PAGE_DESCRIPTOR_LENGTH];
+        bytes[1] = phy_identifier;
+        bytes[4] = 0b0001_0000;
+        bytes[5] = 0x0B;
+        bytes[7] = 0b0000_1000;
+        bytes[8..16].copy_from_slice(&sas_address.to_be_bytes());
+        bytes[16..24].copy_from_slice(&0x5000_C500_0000_0001u64.to_be_bytes());
+        bytes[32] = 0x88;
+        bytes[33] = 0xBC;
+        bytes
+    }
+
+    #[test]
+    fn protocol_specific_port_test() {
+        let mut bytes = vec![0x59, 0x01, 0x00, 0x64, 0x00, 0x06, 0x01, 0x02];
+        bytes.extend(descriptor(0, 0x5000_C500_1234_0000));
+        bytes.extend(descriptor(1, 0x5000_C500_1234_0001));
+
+        let page = match protocol_specific_port(&bytes) {
+            ProtocolSpecificPortPhys::Sas(page) => page,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        assert_eq!(page.header().number_of_phys(), 2, "number of phys");
+        assert_eq!(page.descriptors().len(), 2, "descriptors");
+
+        let phy = &page.descriptors()[1];
+        assert_eq!(phy.phy_identifier(), 1, "phy identifier");
+        assert_eq!(phy.sas_address(), 0x5000_C500_1234_0001, "sas address");
+        assert_eq!(
+            phy.attached_sas_address(),
+            0x5000_C500_0000_0001,
+            "attached sas address"
+        );
+        assert_eq!(phy.attached_device_type(), 1, "attached device type");
+        assert_eq!(phy.attached_ssp_target_port(), 1, "attached ssp target");
+        assert_eq!(phy.negotiated_physical_link_rate(), 0xB, "negotiated rate");
+        assert_eq!(
+            phy.programmed_minimum_physical_link_rate(),
+            0x8,
+            "programmed min"
+        );
+        assert_eq!(
+            phy.hardware_minimum_physical_link_rate(),
+            0x8,
+            "hardware min"
+        );
+        assert_eq!(
+            phy.programmed_maximum_physical_link_rate(),
+            0xB,
+            "programmed max"
+        );
+        assert_eq!(
+            phy.hardware_maximum_physical_link_rate(),
+            0xC,
+            "hardware max"
+        );
+
+        bytes[5] = 0x00;
+        match protocol_specific_port(&bytes) {
+            ProtocolSpecificPortPhys::Other {
+                protocol_identifier,
+                bytes: raw,
+            } => {
+                assert_eq!(protocol_identifier, 0, "protocol identifier");
+                assert_eq!(raw, bytes, "raw bytes");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
 }
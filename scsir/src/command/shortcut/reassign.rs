@@ -0,0 +1,67 @@
+use crate::Scsi;
+
+impl Scsi {
+    /// Reassigns the given logical blocks with one REASSIGN BLOCKS, e.g. the lbas returned
+    /// by `surface_scan`.
+    ///
+    /// The short lba list is used when every lba fits in 32 bits, the long one otherwise.
+    /// When the device stops partway with a medium or hardware error, or a defect list
+    /// failure (ASC 0x32), the ReassignStopped error names the first lba that was not
+    /// reassigned, so the rest of the list can be retried from there.
+    pub fn reassign(&self, lbas: &[u64]) -> crate::Result<()> {
+        if lbas.is_empty() {
+            return Err(crate::Error::BadArgument(
+                "lba list should not be empty.".to_owned(),
+            ));
+        }
+
+        let mut command = self.reassign_blocks();
+        let mut parameter = command.parameter();
+
+        if lbas.iter().all(|lba| *lba <= u32::MAX as u64) {
+            let short_lbas: Vec<u32> = lbas.iter().map(|lba| *lba as u32).collect();
+            parameter.short_lba_list(&short_lbas);
+        } else {
+            parameter.long_lba_list(lbas);
+        }
+
+        parameter.done()?.issue_detailed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_list_test() {
+        let scsi = Scsi::test_instance();
+        scsi.reassign(&[0x10, 0xFFFF_FFFF]).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb, [0x07, 0x00, 0, 0, 0, 0], "cdb");
+        assert_eq!(
+            issued[0].data_out,
+            [0, 0, 0, 8, 0, 0, 0, 0x10, 0xFF, 0xFF, 0xFF, 0xFF],
+            "parameter list"
+        );
+    }
+
+    #[test]
+    fn long_list_test() {
+        let scsi = Scsi::test_instance();
+        scsi.reassign(&[0x10, 0x1_0000_0000]).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[1], 0b11, "long lba and long list");
+        assert_eq!(issued[0].data_out.len(), 4 + 16, "parameter length");
+        assert_eq!(issued[0].data_out[..4], [0, 0, 0, 16], "list length");
+        assert_eq!(
+            issued[0].data_out[12..],
+            [0, 0, 0, 1, 0, 0, 0, 0],
+            "long lba"
+        );
+
+        assert!(scsi.reassign(&[]).is_err(), "empty list");
+    }
+}
@@ -0,0 +1,313 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    command::sense::SenseKey,
+    shortcut::log::{PageWrapper, SelfTestResultsParameter, SELF_TEST_RESULTS_PAGE_CODE},
+    Scsi,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfTestKind {
+    BackgroundShort,
+    BackgroundExtended,
+    ForegroundShort,
+    ForegroundExtended,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelfTestResult {
+    pub self_test_code: u8,
+    // 0x0 completed without error, 0x1 to 0x7 aborted or failed, 0xF in progress
+    pub self_test_results: u8,
+    pub self_test_number: u8,
+    pub accumulated_power_on_hours: u16,
+    // None when the device reports no failing lba
+    pub address_of_first_failure: Option<u64>,
+    pub sense_key: u8,
+    pub additional_sense_code: u8,
+    pub additional_sense_code_qualifier: u8,
+}
+
+impl SelfTestKind {
+    fn self_test_code(self) -> u8 {
+        match self {
+            SelfTestKind::BackgroundShort => 0b001,
+            SelfTestKind::BackgroundExtended => 0b010,
+            SelfTestKind::ForegroundShort => 0b101,
+            SelfTestKind::ForegroundExtended => 0b110,
+        }
+    }
+
+    fn is_foreground(self) -> bool {
+        matches!(
+            self,
+            SelfTestKind::ForegroundShort | SelfTestKind::ForegroundExtended
+        )
+    }
+}
+
+impl SelfTestResult {
+    pub fn passed(&self) -> bool {
+        self.self_test_results == SELF_TEST_COMPLETED
+    }
+
+    pub fn in_progress(&self) -> bool {
+        self.self_test_results == SELF_TEST_IN_PROGRESS
+    }
+
+    fn from_parameter(parameter: &SelfTestResultsParameter) -> Self {
+        let address_of_first_failure = parameter.address_of_first_failure();
+
+        Self {
+            self_test_code: parameter.self_test_code(),
+            self_test_results: parameter.self_test_results(),
+            self_test_number: parameter.self_test_number(),
+            accumulated_power_on_hours: parameter.accumulated_power_on_hours(),
+            address_of_first_failure: (address_of_first_failure != u64::MAX)
+                .then_some(address_of_first_failure),
+            sense_key: parameter.sense_key(),
+            additional_sense_code: parameter.additional_sense_code(),
+            additional_sense_code_qualifier: parameter.additional_sense_code_qualifier(),
+        }
+    }
+}
+
+impl Scsi {
+    /// Starts a self-test with SEND DIAGNOSTIC, waits for it to finish and returns the
+    /// most recent entry of the self-test results log page.
+    ///
+    /// Foreground tests keep the unit NOT READY with SELF-TEST IN PROGRESS while they run,
+    /// so TEST UNIT READY is polled every `poll_interval` until that clears. Any other error
+    /// is returned as is, and once timeout has passed the last SELF-TEST IN PROGRESS error
+    /// is returned. Background tests are followed through the self-test results log page
+    /// until the newest entry is no longer in progress, or fail with Other once timeout has
+    /// passed. A foreground test may hold the SEND DIAGNOSTIC itself until it finishes, so
+    /// the timeout of this Scsi should cover the test duration.
+    pub fn run_self_test(
+        &self,
+        kind: SelfTestKind,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> crate::Result<SelfTestResult> {
+        self.send_diagnostic()
+            .self_test_code(kind.self_test_code())
+            .issue()?;
+
+        let deadline = Instant::now() + timeout;
+
+        if kind.is_foreground() {
+            loop {
+                match self.test_unit_ready().issue() {
+                    Err(e) if is_self_test_in_progress(&e) && Instant::now() < deadline => {
+                        std::thread::sleep(poll_interval);
+                    }
+                    result => break result?,
+                }
+            }
+
+            return self.latest_self_test_result();
+        }
+
+        loop {
+            let result = self.latest_self_test_result()?;
+            if !result.in_progress() {
+                return Ok(result);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(crate::Error::Other(
+                    "self-test still in progress after the timeout.".to_owned(),
+                ));
+            }
+
+            std::thread::sleep(poll_interval);
+        }
+    }
+
+    fn latest_self_test_result(&self) -> crate::Result<SelfTestResult> {
+        let bytes = self
+            .log_sense()
+            .page_code(SELF_TEST_RESULTS_PAGE_CODE)
+            .issue_full()?;
+        let page = PageWrapper::<SelfTestResultsParameter>::from_bytes(&bytes);
+
+        page.parameters
+            .first()
+            .map(SelfTestResult::from_parameter)
+            .ok_or_else(|| crate::Error::Other("self-test results log page is empty.".to_owned()))
+    }
+}
+
+fn is_self_test_in_progress(error: &crate::Error) -> bool {
+    error.sense_data().is_some_and(|sense| {
+        matches!(sense.sense_key(), Some(SenseKey::NotReady))
+            && sense.additional_sense_code() == Some(SELF_TEST_IN_PROGRESS_SENSE)
+    })
+}
+
+const SELF_TEST_IN_PROGRESS_SENSE: u16 = 0x0409;
+
+const SELF_TEST_COMPLETED: u8 = 0x0;
+const SELF_TEST_IN_PROGRESS: u8 = 0xF;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn self_test_results_page(self_test_code: u8, self_test_results: u8) -> Vec<u8> {
+        let mut page = vec![0x10, 0x00, 0x00, 0x14];
+        page.extend_from_slice(&[0x00, 0x01, 0x03, 0x10]);
+        page.push(self_test_code << 5 | self_test_results);
+        page.push(0x01);
+        page.extend_from_slice(&0x1234u16.to_be_bytes());
+        page.extend_from_slice(&u64::MAX.to_be_bytes());
+        page.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        page
+    }
+
+    fn not_ready(additional_sense_code_qualifier: u8) -> [u8; 18] {
+        [
+            0x70,
+            0x00,
+            0x02,
+            0x00,
+            0x00,
+            0x00,
+            0x00,
+            0x0A,
+            0x00,
+            0x00,
+            0x00,
+            0x00,
+            0x04,
+            additional_sense_code_qualifier,
+            0x00,
+            0x00,
+            0x00,
+            0x00,
+        ]
+    }
+
+    #[test]
+    fn foreground_test() {
+        let self_test_in_progress = not_ready(0x09);
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&self_test_in_progress);
+        scsi.mock().push_data(&[]);
+        let page = self_test_results_page(0b110, 0x0);
+        scsi.mock().push_data(&page);
+        scsi.mock().push_data(&page);
+
+        let result = scsi
+            .run_self_test(
+                SelfTestKind::ForegroundExtended,
+                Duration::ZERO,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        let operation_codes: Vec<u8> = issued.iter().map(|command| command.cdb[0]).collect();
+        assert_eq!(
+            operation_codes,
+            [0x1D, 0x00, 0x00, 0x4D, 0x4D],
+            "command sequence"
+        );
+        assert_eq!(issued[0].cdb[1] >> 5, 0b110, "self test code");
+
+        assert!(result.passed(), "passed");
+        assert_eq!(result.self_test_code, 0b110, "self test code");
+        assert_eq!(result.self_test_number, 0x01, "self test number");
+        assert_eq!(result.accumulated_power_on_hours, 0x1234, "power on hours");
+        assert_eq!(result.address_of_first_failure, None, "first failure");
+    }
+
+    #[test]
+    fn background_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        let in_progress = self_test_results_page(0b001, 0xF);
+        let failed = self_test_results_page(0b001, 0x7);
+        scsi.mock().push_data(&in_progress);
+        scsi.mock().push_data(&in_progress);
+        scsi.mock().push_data(&failed);
+        scsi.mock().push_data(&failed);
+
+        let result = scsi
+            .run_self_test(
+                SelfTestKind::BackgroundShort,
+                Duration::ZERO,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 5, "issued");
+        assert_eq!(issued[0].cdb[1] >> 5, 0b001, "self test code");
+        assert_eq!(issued[4].cdb[2] & 0x3F, 0x10, "page code");
+
+        assert!(!result.passed(), "failed");
+        assert!(!result.in_progress(), "finished");
+        assert_eq!(result.self_test_results, 0x7, "self test results");
+    }
+
+    #[test]
+    fn foreground_error_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&not_ready(0x02));
+
+        let result = scsi.run_self_test(
+            SelfTestKind::ForegroundShort,
+            Duration::ZERO,
+            Duration::from_secs(60),
+        );
+
+        assert!(
+            matches!(result, Err(crate::Error::CheckCondition(_))),
+            "other NOT READY returned"
+        );
+        assert_eq!(scsi.mock().issued().len(), 2, "no further polling");
+    }
+
+    #[test]
+    fn timeout_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&not_ready(0x09));
+
+        let result = scsi.run_self_test(
+            SelfTestKind::ForegroundShort,
+            Duration::ZERO,
+            Duration::ZERO,
+        );
+
+        assert!(
+            result
+                .unwrap_err()
+                .sense_data()
+                .is_some_and(|sense| sense.additional_sense_code() == Some(0x0409)),
+            "last self-test in progress error"
+        );
+
+        let scsi = Scsi::test_instance();
+        let in_progress = self_test_results_page(0b001, 0xF);
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_data(&in_progress);
+        scsi.mock().push_data(&in_progress);
+
+        let result = scsi.run_self_test(
+            SelfTestKind::BackgroundShort,
+            Duration::ZERO,
+            Duration::ZERO,
+        );
+
+        assert!(
+            matches!(result, Err(crate::Error::Other(_))),
+            "still in progress"
+        );
+        assert_eq!(scsi.mock().issued().len(), 3, "polled once");
+    }
+}
@@ -0,0 +1,131 @@
+use std::mem::size_of;
+
+use crate::{
+    shortcut::mode::{ControlPage, DescriptorType, HeaderType, PageWrapper, CONTROL_PAGE_CODE},
+    Scsi,
+};
+
+impl Scsi {
+    /// Whether the device returns sense data in descriptor format, the D_SENSE bit of the
+    /// Control mode page.
+    pub fn descriptor_format_sense(&self) -> crate::Result<bool> {
+        Ok(self
+            .control_mode_page()?
+            .page
+            .descriptor_format_sense_data()
+            != 0)
+    }
+
+    /// Sets D_SENSE in the Control mode page with MODE SENSE(10) and MODE SELECT(10).
+    ///
+    /// Descriptor format sense is needed for information fields of lbas beyond 32 bits.
+    /// The setting lives on the device, not in this Scsi: it applies to every initiator
+    /// until it is changed again or the device resets, and with save it also survives
+    /// power cycles.
+    pub fn set_descriptor_format_sense(&self, enabled: bool, save: bool) -> crate::Result<()> {
+        let mut wrapper = self.control_mode_page()?;
+        wrapper
+            .page
+            .set_descriptor_format_sense_data(enabled.into());
+
+        self.mode_select()
+            .page_format(true)
+            .saved_pages(save)
+            .parameter_vec(wrapper.to_mode_select_parameter_list())
+            .issue_10()
+    }
+
+    fn control_mode_page(&self) -> crate::Result<PageWrapper<ControlPage>> {
+        let bytes = self
+            .mode_sense()
+            .disable_block_descriptors(true)
+            .page_code(CONTROL_PAGE_CODE)
+            .allocation_length(CONTROL_MODE_SENSE_LENGTH as u16)
+            .issue_10()?;
+
+        if bytes.len() < CONTROL_MODE_SENSE_LENGTH {
+            return Err(crate::Error::BadArgument(
+                "short response, the control mode page is incomplete.".to_owned(),
+            ));
+        }
+
+        Ok(PageWrapper::from_bytes(
+            HeaderType::Long,
+            DescriptorType::Short,
+            &bytes,
+        ))
+    }
+}
+
+const LONG_HEADER_LENGTH: usize = 8;
+const CONTROL_MODE_SENSE_LENGTH: usize = LONG_HEADER_LENGTH + size_of::<ControlPage>();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control_mode_sense_data(control: u8) -> Vec<u8> {
+        let mut data = vec![0x00, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        data.extend_from_slice(&[0x8A, 0x0A, control, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&[0x00, 0x00, 0xFF, 0xFF, 0x00, 0x1E]);
+        data
+    }
+
+    #[test]
+    fn descriptor_format_sense_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&control_mode_sense_data(0x04));
+        scsi.mock().push_data(&control_mode_sense_data(0x00));
+
+        assert!(scsi.descriptor_format_sense().unwrap(), "descriptor format");
+        assert!(!scsi.descriptor_format_sense().unwrap(), "fixed format");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [0x5A, 0x08, 0x0A, 0x00, 0, 0, 0, 0x00, 0x14, 0x00],
+            "mode sense cdb"
+        );
+    }
+
+    #[test]
+    fn set_descriptor_format_sense_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&control_mode_sense_data(0x02));
+
+        scsi.set_descriptor_format_sense(true, true).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "read, then write");
+        assert_eq!(issued[0].cdb[0], 0x5A, "mode sense");
+        assert_eq!(
+            issued[1].cdb,
+            [0x55, 0x11, 0, 0, 0, 0, 0, 0x00, 0x14, 0x00],
+            "mode select with page format and save pages"
+        );
+        assert_eq!(
+            issued[1].data_out[LONG_HEADER_LENGTH..LONG_HEADER_LENGTH + 4],
+            [0x0A, 0x0A, 0x06, 0x00],
+            "PS cleared, D_SENSE set, other bits kept"
+        );
+        assert_eq!(
+            issued[1].data_out[LONG_HEADER_LENGTH + 8..],
+            [0xFF, 0xFF, 0x00, 0x1E],
+            "rest of the page kept"
+        );
+    }
+
+    #[test]
+    fn short_response_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&control_mode_sense_data(0x04)[..12]);
+
+        assert!(
+            matches!(
+                scsi.descriptor_format_sense(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "truncated page"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{command::sense::SenseKey, Scsi};
+
+impl Scsi {
+    /// Verifies `num_blocks` logical blocks starting at `start_lba` with VERIFY(16),
+    /// `chunk_blocks` at a time, and returns the lbas reported by MEDIUM ERRORs.
+    ///
+    /// After a MEDIUM ERROR the scan resumes at the block following the failing lba.
+    /// Any other error, or a MEDIUM ERROR without a valid information field, aborts the scan.
+    /// The returned lbas can be passed to `reassign` as they are.
+    pub fn surface_scan(
+        &self,
+        start_lba: u64,
+        num_blocks: u64,
+        chunk_blocks: u32,
+    ) -> crate::Result<Vec<u64>> {
+        if chunk_blocks == 0 {
+            return Err(crate::Error::BadArgument(
+                "chunk blocks should not be 0.".to_owned(),
+            ));
+        }
+
+        let end_lba = start_lba.checked_add(num_blocks).ok_or_else(|| {
+            crate::Error::ArgumentOutOfBounds(
+                "start lba plus number of blocks overflows.".to_owned(),
+            )
+        })?;
+
+        let mut bad_blocks = vec![];
+        let mut lba = start_lba;
+
+        while lba < end_lba {
+            let length = u64::min(chunk_blocks as u64, end_lba - lba);
+
+            let result = self
+                .verify()
+                .byte_check(0)
+                .logical_block_address(lba)
+                .verification_length(length as u32)
+                .issue_16();
+
+            match result {
+                Ok(()) => lba += length,
+                Err(error) => {
+                    let bad_block = error
+                        .sense_data()
+                        .filter(|sense| matches!(sense.sense_key(), Some(SenseKey::MediumError)))
+                        .and_then(|sense| sense.information())
+                        .filter(|bad_block| (lba..lba + length).contains(bad_block));
+
+                    match bad_block {
+                        Some(bad_block) => {
+                            bad_blocks.push(bad_block);
+                            lba = bad_block + 1;
+                        }
+                        None => return Err(error),
+                    }
+                }
+            }
+        }
+
+        Ok(bad_blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn medium_error(lba: u32) -> [u8; 18] {
+        let mut sense = [0; 18];
+        sense[0] = 0xF0;
+        sense[2] = 0x03;
+        sense[3..7].copy_from_slice(&lba.to_be_bytes());
+        sense[7] = 0x0A;
+        sense[12] = 0x11;
+        sense
+    }
+
+    fn verified_range(cdb: &[u8]) -> (u64, u32) {
+        (
+            u64::from_be_bytes(cdb[2..10].try_into().unwrap()),
+            u32::from_be_bytes(cdb[10..14].try_into().unwrap()),
+        )
+    }
+
+    #[test]
+    fn surface_scan_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&medium_error(0x115));
+
+        let bad_blocks = scsi.surface_scan(0x100, 0x30, 0x10).unwrap();
+        assert_eq!(bad_blocks, [0x115], "bad blocks");
+
+        let ranges: Vec<_> = scsi
+            .mock()
+            .issued()
+            .iter()
+            .map(|command| verified_range(&command.cdb))
+            .collect();
+        assert_eq!(
+            ranges,
+            [(0x100, 0x10), (0x110, 0x10), (0x116, 0x10), (0x126, 0x0A)],
+            "verified ranges"
+        );
+    }
+
+    #[test]
+    fn other_error_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&[
+            0x70, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x24, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+
+        let error = scsi.surface_scan(0, 0x10, 0x10).unwrap_err();
+        assert!(error.is_illegal_request(), "illegal request is returned");
+    }
+}
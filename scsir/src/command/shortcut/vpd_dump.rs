@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use crate::{command::SizedDataIn, shortcut::inquiry::supported_vital_product_data_pages, Scsi};
+
+impl Scsi {
+    /// Reads every page listed in the Supported VPD Pages page, keyed by page code, e.g. to
+    /// attach to a bug report. Each page is read whole with issue_sized, header included.
+    /// Pages the device fails to return are left out.
+    pub fn dump_vpd_pages(&self) -> crate::Result<BTreeMap<u8, Vec<u8>>> {
+        let supported = supported_vital_product_data_pages(&mut self.inquiry())?;
+
+        Ok(supported
+            .supported_pages
+            .into_iter()
+            .filter_map(|page_code| {
+                self.inquiry()
+                    .page_code(Some(page_code))
+                    .issue_sized()
+                    .ok()
+                    .map(|bytes| (page_code, bytes))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_vpd_pages_test() {
+        let supported = [0x00, 0x00, 0x00, 0x03, 0x00, 0x80, 0x83];
+        let serial_number = [0x00, 0x80, 0x00, 0x04, b'S', b'N', b'0', b'1'];
+        let illegal_request = [
+            0x70, 0x00, 0x05, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x24, 0x00, 0, 0, 0, 0,
+        ];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&supported[..4]);
+        scsi.mock().push_data(&supported);
+        scsi.mock().push_data(&supported[..4]);
+        scsi.mock().push_data(&supported);
+        scsi.mock().push_data(&serial_number[..4]);
+        scsi.mock().push_data(&serial_number);
+        scsi.mock().push_sense(&illegal_request);
+
+        let pages = scsi.dump_vpd_pages().unwrap();
+
+        assert_eq!(
+            pages.keys().copied().collect::<Vec<_>>(),
+            [0x00, 0x80],
+            "rejected page skipped"
+        );
+        assert_eq!(pages[&0x00], supported, "supported pages");
+        assert_eq!(pages[&0x80], serial_number, "unit serial number");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 7, "issued commands");
+        assert_eq!(issued[6].cdb[1..3], [0x01, 0x83], "device identification");
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{command::write_same::OPERATION_CODE_16, shortcut::inquiry::block_limits, Scsi};
+
+impl Scsi {
+    /// Fills number_of_blocks blocks starting at logical_block_address with copies of block.
+    ///
+    /// WRITE SAME(16) is used when the device reports support for it, so only one block is
+    /// transferred per command, each covering at most the MAXIMUM WRITE SAME LENGTH of the
+    /// Block Limits VPD page. Otherwise WRITE(16) is repeated with a single pattern buffer
+    /// of up to 1 MiB, or the SG reserved size if smaller, which
+    /// transfers every block over the bus and is much slower. Only a device rejecting
+    /// REPORT SUPPORTED OPERATION CODES with ILLEGAL REQUEST falls back to WRITE(16), any
+    /// other error is returned. confirm must be true, as the whole range is overwritten.
+    pub fn write_pattern(
+        &self,
+        logical_block_address: u64,
+        number_of_blocks: u64,
+        block: &[u8],
+        confirm: bool,
+    ) -> crate::Result<()> {
+        if !confirm {
+            return Err(crate::Error::BadArgument(
+                "write_pattern overwrites the whole range and has to be confirmed".to_owned(),
+            ));
+        }
+
+        if block.is_empty() {
+            return Err(crate::Error::BadArgument(
+                "pattern block can't be empty".to_owned(),
+            ));
+        }
+
+        let last_logical_block_address = logical_block_address
+            .checked_add(number_of_blocks)
+            .ok_or_else(|| {
+                crate::Error::ArgumentOutOfBounds(
+                    "logical block address plus number of blocks overflows".to_owned(),
+                )
+            })?;
+        let mut logical_block_address = logical_block_address;
+
+        let write_same_supported = match self.supports_operation(OPERATION_CODE_16, None) {
+            Err(e) if e.is_illegal_request() => false,
+            result => result?,
+        };
+
+        if write_same_supported {
+            // 0 means the device reports no limit
+            let maximum_write_same_length = match block_limits(&mut self.inquiry()) {
+                Ok(limits) => limits.maximum_write_same_length,
+                Err(e) if e.is_illegal_request() => 0,
+                Err(e) => return Err(e),
+            };
+            let maximum_blocks = match maximum_write_same_length {
+                0 => u32::MAX as u64,
+                length => u64::min(length, u32::MAX as u64),
+            };
+
+            let mut command = self.write_same();
+            command
+                .logical_block_size(block.len() as u32)
+                .parameter(block);
+
+            while logical_block_address < last_logical_block_address {
+                let count = u64::min(
+                    last_logical_block_address - logical_block_address,
+                    maximum_blocks,
+                );
+                command
+                    .logical_block_address(logical_block_address)
+                    .number_of_blocks(count as u32)
+                    .issue_16()?;
+                logical_block_address += count;
+            }
+
+            return Ok(());
+        }
+
+        let chunk_length = match self.sg_reserved_size() {
+            Ok(size) if size > 0 => usize::min(WRITE_PATTERN_CHUNK_LENGTH, size as usize),
+            _ => WRITE_PATTERN_CHUNK_LENGTH,
+        };
+        let chunk_blocks = usize::max(chunk_length / block.len(), 1);
+        let pattern = block.repeat(chunk_blocks);
+        let mut command = self.write();
+        command.logical_block_size(block.len() as u32);
+
+        while logical_block_address < last_logical_block_address {
+            let count = u64::min(
+                last_logical_block_address - logical_block_address,
+                chunk_blocks as u64,
+            );
+            command
+                .logical_block_address(logical_block_address)
+                .parameter(&pattern[..count as usize * block.len()])
+                .issue_16()?;
+            logical_block_address += count;
+        }
+
+        Ok(())
+    }
+}
+
+const WRITE_PATTERN_CHUNK_LENGTH: usize = 1024 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WRITE_16: u8 = 0x8A;
+
+    const ILLEGAL_REQUEST: [u8; 18] = [
+        0x70, 0x00, 0x05, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x20, 0x00, 0, 0, 0, 0,
+    ];
+
+    fn block_limits_page(maximum_write_same_length: u64) -> Vec<u8> {
+        let mut page = vec![0; 64];
+        page[1] = 0xB0;
+        page[3] = 0x3C;
+        page[36..44].copy_from_slice(&maximum_write_same_length.to_be_bytes());
+        page
+    }
+
+    #[test]
+    fn write_pattern_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x01, 0x00, 0x00]);
+
+        assert!(
+            scsi.write_pattern(0, 5000, &[0; 512], false).is_err(),
+            "unconfirmed"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+
+        scsi.write_pattern(100, 5000, &[0; 512], true).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 4, "support query plus three writes");
+        assert!(
+            issued[1..].iter().all(|c| c.cdb[0] == WRITE_16),
+            "write(16)"
+        );
+        assert_eq!(issued[1].data_out.len(), 2048 * 512, "full chunk");
+        assert_eq!(&issued[3].cdb[2..10], &4196u64.to_be_bytes(), "last lba");
+        assert_eq!(&issued[3].cdb[10..14], &904u32.to_be_bytes(), "last length");
+        assert_eq!(issued[3].data_out.len(), 904 * 512, "last chunk");
+    }
+
+    #[test]
+    fn write_pattern_write_same_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x03, 0x00, 0x00]);
+        scsi.mock().push_data(&block_limits_page(0));
+
+        scsi.write_pattern(0, 5000, &[0; 512], true).unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued.len(),
+            3,
+            "support query, block limits, one write same"
+        );
+        assert_eq!(issued[2].cdb[0], OPERATION_CODE_16, "write same(16)");
+        assert_eq!(issued[2].data_out.len(), 512, "single block");
+    }
+
+    #[test]
+    fn write_pattern_maximum_write_same_length_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[0x00, 0x03, 0x00, 0x00]);
+        scsi.mock().push_data(&block_limits_page(2000));
+
+        scsi.write_pattern(0, 5000, &[0; 512], true).unwrap();
+
+        let issued = scsi.mock().issued();
+        let ranges: Vec<(u64, u32)> = issued[2..]
+            .iter()
+            .map(|c| {
+                (
+                    u64::from_be_bytes(c.cdb[2..10].try_into().unwrap()),
+                    u32::from_be_bytes(c.cdb[10..14].try_into().unwrap()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            [(0, 2000), (2000, 2000), (4000, 1000)],
+            "clamped to the maximum write same length"
+        );
+    }
+
+    #[test]
+    fn write_pattern_errors_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&ILLEGAL_REQUEST);
+
+        scsi.write_pattern(0, 8, &[0; 512], true).unwrap();
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "fell back to write");
+        assert_eq!(issued[1].cdb[0], WRITE_16, "write(16)");
+
+        // NOT READY
+        let mut not_ready = ILLEGAL_REQUEST;
+        not_ready[2] = 0x02;
+        not_ready[12] = 0x04;
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_sense(&not_ready);
+
+        assert!(
+            matches!(
+                scsi.write_pattern(0, 8, &[0; 512], true),
+                Err(crate::Error::CheckCondition(_))
+            ),
+            "other errors returned"
+        );
+        assert_eq!(scsi.mock().issued().len(), 1, "nothing written");
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    shortcut::inquiry::{device_identification, Association, Identifier},
+    Scsi,
+};
+
+impl Scsi {
+    /// Reads the device identification VPD page and returns the world wide name of the
+    /// logical unit in its canonical form, e.g. `naa.5000c50012345678`.
+    ///
+    /// An NAA designator is preferred, an EUI-64 designator (`eui.` prefix) is used when
+    /// no NAA designator exists. Only designators associated with the logical unit are
+    /// considered. `None` is returned when the page carries neither.
+    pub fn wwn(&self) -> crate::Result<Option<String>> {
+        let page = device_identification(&mut self.inquiry())?;
+
+        let designator = |identifier_type: u8| {
+            page.descriptors.iter().find_map(|descriptor| {
+                match (&descriptor.association, &descriptor.identifier) {
+                    (
+                        Association::AddressedPhysicalOrLogicalDevice,
+                        Identifier::Binary(identifier),
+                    ) if descriptor.identifier_type == identifier_type
+                        && !identifier.is_empty() =>
+                    {
+                        Some(identifier)
+                    }
+                    _ => None,
+                }
+            })
+        };
+
+        let wwn = if let Some(identifier) = designator(NAA_IDENTIFIER_TYPE) {
+            Some(format!("naa.{}", to_hex(identifier)))
+        } else {
+            designator(EUI64_IDENTIFIER_TYPE)
+                .map(|identifier| format!("eui.{}", to_hex(identifier)))
+        };
+
+        Ok(wwn)
+    }
+}
+
+const EUI64_IDENTIFIER_TYPE: u8 = 0x2;
+const NAA_IDENTIFIER_TYPE: u8 = 0x3;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_page(scsi: &Scsi, page: &[u8]) {
+        // header probe, then the full page
+        scsi.mock().push_data(page);
+        scsi.mock().push_data(page);
+    }
+
+    #[test]
+    fn naa_test() {
+        let scsi = Scsi::test_instance();
+        push_page(
+            &scsi,
+            &[
+                0x00, 0x83, 0x00, 0x20, // page header
+                0x02, 0x01, 0x00, 0x04, // ascii, logical unit, T10 vendor id
+                b'A', b'B', b'C', b'D', //
+                0x61, 0x93, 0x00, 0x08, // binary, port, NAA
+                0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, //
+                0x01, 0x03, 0x00, 0x08, // binary, logical unit, NAA
+                0x50, 0x00, 0xC5, 0x00, 0x12, 0x34, 0x56, 0x78,
+            ],
+        );
+
+        assert_eq!(
+            scsi.wwn().unwrap().as_deref(),
+            Some("naa.5000c50012345678"),
+            "wwn"
+        );
+    }
+
+    #[test]
+    fn eui64_test() {
+        let scsi = Scsi::test_instance();
+        push_page(
+            &scsi,
+            &[
+                0x00, 0x83, 0x00, 0x0C, // page header
+                0x01, 0x02, 0x00, 0x08, // binary, logical unit, EUI-64
+                0x00, 0x25, 0x38, 0xAB, 0xCD, 0xEF, 0x01, 0x23,
+            ],
+        );
+
+        assert_eq!(
+            scsi.wwn().unwrap().as_deref(),
+            Some("eui.002538abcdef0123"),
+            "wwn"
+        );
+    }
+
+    #[test]
+    fn missing_test() {
+        let scsi = Scsi::test_instance();
+        push_page(
+            &scsi,
+            &[
+                0x00, 0x83, 0x00, 0x08, // page header
+                0x02, 0x01, 0x00, 0x04, // ascii, logical unit, T10 vendor id
+                b'A', b'B', b'C', b'D',
+            ],
+        );
+
+        assert_eq!(scsi.wwn().unwrap(), None, "wwn");
+    }
+}
@@ -0,0 +1,36 @@
+/// Data-in commands whose response begins with its own length.
+///
+/// `issue_sized` reads such a response in two steps: a probe with an allocation length
+/// just covering the length field, then one read with the allocation length the response
+/// asked for, clamped to what the CDB can carry. The allocation length set on the command
+/// is ignored and replaced.
+pub trait SizedDataIn {
+    /// bytes from the start of the response needed to know its length
+    fn probe_length(&self) -> usize;
+
+    /// total response length, computed from at least probe_length bytes
+    fn response_length(&self, header: &[u8]) -> usize;
+
+    /// issues the command with this allocation length, clamped to the CDB field
+    fn issue_with_allocation_length(&mut self, allocation_length: usize) -> crate::Result<Vec<u8>>;
+
+    fn issue_sized(&mut self) -> crate::Result<Vec<u8>> {
+        let probe_length = self.probe_length();
+        let probe = self.issue_with_allocation_length(probe_length)?;
+        if probe.len() < probe_length {
+            return Err(crate::Error::BadArgument(
+                "short response, the length field is incomplete.".to_owned(),
+            ));
+        }
+
+        let response_length = self.response_length(&probe);
+        let mut bytes = if response_length <= probe.len() {
+            probe
+        } else {
+            self.issue_with_allocation_length(response_length)?
+        };
+
+        bytes.truncate(response_length);
+        Ok(bytes)
+    }
+}
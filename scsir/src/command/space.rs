@@ -0,0 +1,285 @@
+#![allow(dead_code)]
+
+use modular_bitfield_msb::prelude::*;
+
+use crate::{result_data::ResultData, Command, DataDirection, Scsi};
+
+#[derive(Clone, Debug)]
+pub struct SpaceCommand<'a> {
+    interface: &'a Scsi,
+    code: SpaceCode,
+    count: SpaceCount,
+    control: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpaceCode {
+    LogicalBlocks,
+    Filemarks,
+    SequentialFilemarks,
+    EndOfData,
+}
+
+/// Signed count of a SPACE command, negative values move towards the beginning of the medium.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpaceCount(pub i64);
+
+impl SpaceCount {
+    /// 24-bit two's complement, as in SPACE(6).
+    pub fn encode_24(&self) -> crate::Result<u32> {
+        const MIN: i64 = -(1 << 23);
+        const MAX: i64 = (1 << 23) - 1;
+
+        if !(MIN..=MAX).contains(&self.0) {
+            return Err(crate::Error::ArgumentOutOfBounds(format!(
+                "count is out of bounds. The possible values are {} to {}, but {} was provided.",
+                MIN, MAX, self.0
+            )));
+        }
+
+        Ok(self.0 as u32 & 0xFF_FFFF)
+    }
+
+    /// 64-bit two's complement, as in SPACE(16).
+    pub fn encode_64(&self) -> u64 {
+        self.0 as u64
+    }
+}
+
+impl<'a> SpaceCommand<'a> {
+    fn new(interface: &'a Scsi) -> Self {
+        Self {
+            interface,
+            code: SpaceCode::LogicalBlocks,
+            count: SpaceCount::default(),
+            control: interface.default_control(),
+        }
+    }
+
+    pub fn code(&mut self, value: SpaceCode) -> &mut Self {
+        self.code = value;
+        self
+    }
+
+    // count must be 0 for SpaceCode::EndOfData
+    pub fn count(&mut self, value: i64) -> &mut Self {
+        self.count = SpaceCount(value);
+        self
+    }
+
+    // sets code to EndOfData and count to 0
+    pub fn end_of_data(&mut self) -> &mut Self {
+        self.code = SpaceCode::EndOfData;
+        self.count = SpaceCount(0);
+        self
+    }
+
+    pub fn control(&mut self, value: u8) -> &mut Self {
+        self.control = value;
+        self
+    }
+
+    fn error_check(&self) -> crate::Result<()> {
+        if self.code == SpaceCode::EndOfData && self.count.0 != 0 {
+            return Err(crate::Error::BadArgument(
+                "count must be 0 when spacing to end of data".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn issue_6(&mut self) -> crate::Result<()> {
+        self.error_check()?;
+
+        let command_buffer = CommandBuffer6::new()
+            .with_operation_code(OPERATION_CODE_6)
+            .with_code(self.code.into())
+            .with_count(self.count.encode_24()?)
+            .with_control(self.control);
+
+        self.interface.issue(&ThisCommand { command_buffer })
+    }
+
+    pub fn issue_16(&mut self) -> crate::Result<()> {
+        self.error_check()?;
+
+        let command_buffer = CommandBuffer16::new()
+            .with_operation_code(OPERATION_CODE_16)
+            .with_code(self.code.into())
+            .with_count(self.count.encode_64())
+            .with_control(self.control);
+
+        self.interface.issue(&ThisCommand { command_buffer })
+    }
+}
+
+impl Scsi {
+    pub fn space(&self) -> SpaceCommand<'_> {
+        SpaceCommand::new(self)
+    }
+}
+
+impl From<SpaceCode> for u8 {
+    fn from(value: SpaceCode) -> Self {
+        match value {
+            SpaceCode::LogicalBlocks => 0x00,
+            SpaceCode::Filemarks => 0x01,
+            SpaceCode::SequentialFilemarks => 0x02,
+            SpaceCode::EndOfData => 0x03,
+        }
+    }
+}
+
+const OPERATION_CODE_6: u8 = 0x11;
+const OPERATION_CODE_16: u8 = 0x91;
+
+#[bitfield]
+#[derive(Clone, Copy)]
+struct CommandBuffer6 {
+    operation_code: B8,
+    reserved: B4,
+    code: B4,
+    count: B24,
+    control: B8,
+}
+
+#[bitfield]
+#[derive(Clone, Copy)]
+struct CommandBuffer16 {
+    operation_code: B8,
+    reserved_0: B4,
+    code: B4,
+    reserved_1: B16,
+    count: B64,
+    parameter_length: B16,
+    reserved_2: B8,
+    control: B8,
+}
+
+struct ThisCommand<C> {
+    command_buffer: C,
+}
+
+impl<C: Copy> Command for ThisCommand<C> {
+    type CommandBuffer = C;
+
+    type DataBuffer = ();
+
+    type DataBufferWrapper = ();
+
+    type ReturnType = crate::Result<()>;
+
+    fn direction(&self) -> DataDirection {
+        DataDirection::None
+    }
+
+    fn command(&self) -> Self::CommandBuffer {
+        self.command_buffer
+    }
+
+    fn data(&self) -> Self::DataBufferWrapper {}
+
+    fn data_size(&self) -> u32 {
+        0
+    }
+
+    fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+        result.check_ioctl_error()?;
+        result.check_common_error()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    const COMMAND_LENGTH_6: usize = 6;
+    const COMMAND_LENGTH_16: usize = 16;
+
+    #[test]
+    fn layout_test() {
+        assert_eq!(
+            size_of::<CommandBuffer6>(),
+            COMMAND_LENGTH_6,
+            concat!("Size of: ", stringify!(CommandBuffer6))
+        );
+
+        assert_eq!(
+            size_of::<CommandBuffer16>(),
+            COMMAND_LENGTH_16,
+            concat!("Size of: ", stringify!(CommandBuffer16))
+        );
+    }
+
+    #[test]
+    fn count_test() {
+        assert_eq!(SpaceCount(-1).encode_24().unwrap(), 0xFF_FFFF, "-1");
+        assert_eq!(
+            SpaceCount(-(1 << 23)).encode_24().unwrap(),
+            0x80_0000,
+            "min"
+        );
+        assert_eq!(
+            SpaceCount((1 << 23) - 1).encode_24().unwrap(),
+            0x7F_FFFF,
+            "max"
+        );
+        assert!(SpaceCount(-(1 << 23) - 1).encode_24().is_err(), "below min");
+        assert!(SpaceCount(1 << 23).encode_24().is_err(), "above max");
+
+        assert_eq!(SpaceCount(-1).encode_64(), u64::MAX, "-1");
+        assert_eq!(SpaceCount(i64::MIN).encode_64(), 1 << 63, "min");
+        assert_eq!(SpaceCount(i64::MAX).encode_64(), u64::MAX >> 1, "max");
+    }
+
+    #[test]
+    fn cdb_test() {
+        let scsi = Scsi::test_instance();
+        scsi.space()
+            .code(SpaceCode::Filemarks)
+            .count(-2)
+            .issue_6()
+            .unwrap();
+        scsi.space()
+            .code(SpaceCode::Filemarks)
+            .count(-2)
+            .issue_16()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [0x11, 0x01, 0xFF, 0xFF, 0xFE, 0x00],
+            "space(6)"
+        );
+        assert_eq!(
+            issued[1].cdb,
+            [
+                0x91, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0x00, 0x00,
+                0x00, 0x00
+            ],
+            "space(16)"
+        );
+    }
+
+    #[test]
+    fn end_of_data_test() {
+        let scsi = Scsi::test_instance();
+
+        let result = scsi.space().count(5).code(SpaceCode::EndOfData).issue_16();
+        assert!(
+            matches!(result, Err(crate::Error::BadArgument(_))),
+            "nonzero count"
+        );
+        assert!(scsi.space().count(5).end_of_data().issue_6().is_ok(), "eod");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 1, "issue count");
+        assert_eq!(issued[0].cdb[1], 0x03, "code");
+        assert_eq!(&issued[0].cdb[2..5], &[0, 0, 0], "count");
+    }
+}
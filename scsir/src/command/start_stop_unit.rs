@@ -18,7 +18,9 @@ impl<'a> StartStopUnitCommand<'a> {
             interface,
             power_condition_modifer: 0,
             power_condition: 0,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
         }
     }
 
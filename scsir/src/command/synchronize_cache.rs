@@ -11,6 +11,7 @@ pub struct SynchronizeCacheCommand<'a> {
     group_number: u8,
     logical_block_address: u64,
     number_of_blocks: u32,
+    range_specified: bool,
     control: u8,
 }
 
@@ -22,7 +23,8 @@ impl<'a> SynchronizeCacheCommand<'a> {
             group_number: 0,
             logical_block_address: 0,
             number_of_blocks: 0,
-            control: 0,
+            range_specified: false,
+            control: interface.default_control(),
         }
     }
 
@@ -39,11 +41,22 @@ impl<'a> SynchronizeCacheCommand<'a> {
 
     pub fn logical_block_address(&mut self, value: u64) -> &mut Self {
         self.logical_block_address = value;
+        self.range_specified = true;
         self
     }
 
+    // number_of_blocks == 0 means every block from logical_block_address to the end of the medium
     pub fn number_of_blocks(&mut self, value: u32) -> &mut Self {
         self.number_of_blocks = value;
+        self.range_specified = true;
+        self
+    }
+
+    // sets logical_block_address and number_of_blocks to 0, which synchronizes the whole medium
+    pub fn sync_entire_medium(&mut self) -> &mut Self {
+        self.logical_block_address = 0;
+        self.number_of_blocks = 0;
+        self.range_specified = true;
         self
     }
 
@@ -57,6 +70,12 @@ impl<'a> SynchronizeCacheCommand<'a> {
         logical_block_address_bits: u32,
         number_of_blocks_bits: u32,
     ) -> crate::Result<()> {
+        if !self.range_specified {
+            return Err(crate::Error::BadArgument(
+                "either a block range or sync_entire_medium is required".to_owned(),
+            ));
+        }
+
         bitfield_bound_check!(self.group_number, 5, "group number")?;
         bitfield_bound_check!(
             self.logical_block_address,
@@ -197,4 +216,24 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer16))
         );
     }
+
+    #[test]
+    fn range_check_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.synchronize_cache();
+
+        assert!(
+            matches!(
+                command.error_check(32, 16),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "unspecified range"
+        );
+
+        command.sync_entire_medium();
+        assert!(command.error_check(32, 16).is_ok(), "entire medium");
+
+        command.logical_block_address(0x10).number_of_blocks(0x20);
+        assert!(command.error_check(32, 16).is_ok(), "explicit range");
+    }
 }
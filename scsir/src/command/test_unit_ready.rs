@@ -2,7 +2,7 @@
 
 use modular_bitfield_msb::prelude::*;
 
-use crate::{result_data::ResultData, Command, DataDirection, Scsi};
+use crate::{command::sense::SenseKey, result_data::ResultData, Command, DataDirection, Scsi};
 
 #[derive(Clone, Debug)]
 pub struct TestUnitReadyCommand<'a> {
@@ -14,7 +14,9 @@ impl<'a> TestUnitReadyCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
         }
     }
 
@@ -23,6 +25,11 @@ impl<'a> TestUnitReadyCommand<'a> {
         self
     }
 
+    // returns the cdb issue would send, without sending anything
+    pub fn build_cdb(&self) -> crate::Result<[u8; 6]> {
+        Ok(self.command_buffer.into_bytes())
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         self.interface.issue(&ThisCommand {
             command_buffer: self.command_buffer,
@@ -34,6 +41,15 @@ impl Scsi {
     pub fn test_unit_ready(&self) -> TestUnitReadyCommand<'_> {
         TestUnitReadyCommand::new(self)
     }
+
+    /// Issues TEST UNIT READY and reports NOT READY sense as `Ok(false)` instead of an error.
+    pub fn is_ready(&self) -> crate::Result<bool> {
+        self.issue(&ReadinessCommand {
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(self.default_control()),
+        })
+    }
 }
 
 const OPERATION_CODE: u8 = 0x00;
@@ -81,9 +97,53 @@ impl Command for ThisCommand {
     }
 }
 
+struct ReadinessCommand {
+    command_buffer: CommandBuffer,
+}
+
+impl Command for ReadinessCommand {
+    type CommandBuffer = CommandBuffer;
+
+    type DataBuffer = ();
+
+    type DataBufferWrapper = ();
+
+    type ReturnType = crate::Result<bool>;
+
+    fn direction(&self) -> DataDirection {
+        DataDirection::None
+    }
+
+    fn command(&self) -> Self::CommandBuffer {
+        self.command_buffer
+    }
+
+    fn data(&self) -> Self::DataBufferWrapper {}
+
+    fn data_size(&self) -> u32 {
+        0
+    }
+
+    fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+        result.check_ioctl_error()?;
+
+        if let Some(SenseKey::NotReady) = result.sense_buffer().sense_key() {
+            return Ok(false);
+        }
+
+        result.check_common_error()?;
+
+        Ok(true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
+        result_data::Status,
+    };
     use std::mem::size_of;
 
     const COMMAND_LENGTH: usize = 6;
@@ -96,4 +156,48 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn not_ready_test() {
+        let mut raw = [0u8; MAX_SENSE_BUFFER_LENGTH];
+        raw[0] = 0x70;
+        raw[2] = 0x02;
+        raw[7] = 0x0A;
+        raw[12] = 0x04;
+        raw[13] = 0x01;
+        let sense = SenseData::parse(&raw, 18);
+
+        let result = ResultData {
+            ioctl_result: 0,
+            transfered_data_length: 0,
+            data: &mut (),
+            transfered_sense_length: 18,
+            sense_buffer: &sense,
+            status: Status::CheckCondition,
+            rejection: None,
+            protocol_violation: None,
+            #[cfg(target_os = "linux")]
+            host_status: crate::os::linux::HostStatus::Ok,
+            #[cfg(target_os = "linux")]
+            driver_status: crate::os::linux::DriverStatus::SENSE,
+        };
+
+        let command = ReadinessCommand {
+            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+        };
+
+        assert!(!command.process_result(result).unwrap(), "not ready");
+    }
+
+    #[test]
+    fn build_cdb_test() {
+        let scsi = Scsi::test_instance();
+
+        assert_eq!(
+            scsi.test_unit_ready().control(0x04).build_cdb().unwrap(),
+            [0x00, 0x00, 0x00, 0x00, 0x00, 0x04],
+            "test unit ready"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
 }
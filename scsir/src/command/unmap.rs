@@ -30,7 +30,9 @@ impl<'a> UnmapCommand<'a> {
         Self {
             interface,
             group_number: 0,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             data_buffer: FlexibleStruct::new(),
         }
     }
@@ -175,6 +177,10 @@ impl Command for ThisCommand {
         self.data_buffer.total_size() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.total_size())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()
@@ -3,7 +3,10 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{
+        bitfield_bound_check,
+        sense::{SenseData, SenseKey},
+    },
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -31,7 +34,7 @@ impl<'a> VerifyCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             group_number: 0,
             verify_protect: 0,
             disable_page_out: false,
@@ -42,7 +45,7 @@ impl<'a> VerifyCommand<'a> {
             expected_initial_logical_block_reference_tag: 0,
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
-            logical_block_size: 512,
+            logical_block_size: interface.default_logical_block_size(),
             data_buffer: vec![],
         }
     }
@@ -70,6 +73,10 @@ impl<'a> VerifyCommand<'a> {
     }
 
     // byte_check must be less than 0x04
+    // 0b00: verify the medium only, no parameter
+    // 0b01: compare the medium with the parameter, which covers the whole verification length
+    // 0b10: reserved
+    // 0b11: compare every block with the parameter, which is exactly one logical block
     pub fn byte_check(&mut self, value: u8) -> &mut Self {
         self.byte_check = value;
         self
@@ -112,6 +119,11 @@ impl<'a> VerifyCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(
         &self,
         logical_block_address_bits: u32,
@@ -134,6 +146,31 @@ impl<'a> VerifyCommand<'a> {
             )));
         }
 
+        match self.byte_check {
+            0b00 if !self.data_buffer.is_empty() => {
+                return Err(crate::Error::BadArgument(
+                    "parameter is not allowed when byte check is 0.".to_owned(),
+                ));
+            }
+            0b10 => {
+                return Err(crate::Error::BadArgument(
+                    "byte check 2 is reserved.".to_owned(),
+                ));
+            }
+            0b11 if self.data_buffer.len() != self.logical_block_size as usize => {
+                return Err(crate::Error::BadArgument(format!(
+                    "parameter should be exactly one logical block when byte check is 3, which is {}.",
+                    self.logical_block_size
+                )));
+            }
+            0b11 if !self.manually_set_verification_length => {
+                return Err(crate::Error::BadArgument(
+                    "verification length should be set when byte check is 3.".to_owned(),
+                ));
+            }
+            _ => {}
+        }
+
         if self.manually_set_verification_length {
             bitfield_bound_check!(
                 self.verification_length,
@@ -239,6 +276,32 @@ impl<'a> VerifyCommand<'a> {
         })
     }
 
+    // VERIFY(16) with byte check 1 against expected, which replaces the parameter.
+    // returns None when the medium matches, and on a miscompare the lba of the first
+    // differing byte, from the information field holding its offset into expected.
+    // a miscompare reported without a valid information field is returned as the error
+    pub fn issue_16_compare(&mut self, expected: &[u8]) -> crate::Result<Option<u64>> {
+        self.byte_check(0b01).parameter(expected);
+
+        match self.issue_16() {
+            Ok(()) => Ok(None),
+            Err(e) => {
+                let offset = e
+                    .sense_data()
+                    .filter(|sense| is_miscompare(sense))
+                    .and_then(|sense| sense.information());
+
+                match offset {
+                    Some(offset) => Ok(Some(
+                        self.logical_block_address
+                            .saturating_add(offset / self.logical_block_size as u64),
+                    )),
+                    None => Err(e),
+                }
+            }
+        }
+    }
+
     pub fn issue_32(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, true)?;
 
@@ -280,6 +343,14 @@ impl Scsi {
     }
 }
 
+fn is_miscompare(sense: &SenseData) -> bool {
+    matches!(sense.sense_key(), Some(SenseKey::Miscompare))
+        || sense.additional_sense_code().map(|code| code >> 8) == Some(MISCOMPARE_DURING_VERIFY)
+}
+
+// the additional sense code of MISCOMPARE DURING VERIFY OPERATION and its variants
+const MISCOMPARE_DURING_VERIFY: u16 = 0x1D;
+
 const OPERATION_CODE_10: u8 = 0x2F;
 const OPERATION_CODE_12: u8 = 0xAF;
 const OPERATION_CODE_16: u8 = 0x8F;
@@ -387,6 +458,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -431,4 +506,94 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn byte_check_test() {
+        let scsi = Scsi::test_instance();
+
+        let mut command = scsi.verify();
+        command.byte_check(0b00).verification_length(8);
+        assert!(command.error_check(32, 16, false).is_ok(), "byte check 0");
+        command.parameter(&[0; 512]);
+        assert!(
+            command.error_check(32, 16, false).is_err(),
+            "byte check 0 with parameter"
+        );
+
+        let mut command = scsi.verify();
+        command.byte_check(0b01).parameter(&[0; 1024]);
+        assert!(command.error_check(32, 16, false).is_ok(), "byte check 1");
+        command.parameter(&[0; 1000]);
+        assert!(
+            command.error_check(32, 16, false).is_err(),
+            "byte check 1 with partial block"
+        );
+
+        let mut command = scsi.verify();
+        command.byte_check(0b10);
+        assert!(command.error_check(32, 16, false).is_err(), "byte check 2");
+
+        let mut command = scsi.verify();
+        command.byte_check(0b11).parameter(&[0; 512]);
+        assert!(
+            command.error_check(32, 16, false).is_err(),
+            "byte check 3 without verification length"
+        );
+        command.verification_length(8);
+        assert!(command.error_check(32, 16, false).is_ok(), "byte check 3");
+        command.parameter(&[0; 1024]);
+        assert!(
+            command.error_check(32, 16, false).is_err(),
+            "byte check 3 with two blocks"
+        );
+    }
+
+    #[test]
+    fn issue_16_compare_test() {
+        let scsi = Scsi::test_instance();
+        let mut miscompare = [0; 18];
+        miscompare[0] = 0xF0;
+        miscompare[2] = 0x0E;
+        miscompare[3..7].copy_from_slice(&(2 * 512 + 17u32).to_be_bytes());
+        miscompare[7] = 0x0A;
+        miscompare[12] = 0x1D;
+
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&miscompare);
+
+        let expected = vec![0xA5; 4 * 512];
+        let mut command = scsi.verify();
+        command.logical_block_address(0x1000);
+
+        assert_eq!(command.issue_16_compare(&expected).unwrap(), None, "match");
+        assert_eq!(
+            command.issue_16_compare(&expected).unwrap(),
+            Some(0x1002),
+            "miscompare"
+        );
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[0], 0x8F, "verify(16)");
+        assert_eq!(issued[0].cdb[1], 0x02, "byte check 1");
+        assert_eq!(issued[0].cdb[10..14], [0, 0, 0, 4], "verification length");
+        assert_eq!(issued[0].data_out, expected, "expected data");
+
+        miscompare[0] = 0x70;
+        scsi.mock().push_sense(&miscompare);
+        assert!(
+            command.issue_16_compare(&expected).is_err(),
+            "miscompare without information"
+        );
+
+        let mut medium_error = [0; 18];
+        medium_error[0] = 0xF0;
+        medium_error[2] = 0x03;
+        medium_error[7] = 0x0A;
+        medium_error[12] = 0x11;
+        scsi.mock().push_sense(&medium_error);
+        assert!(
+            command.issue_16_compare(&expected).is_err(),
+            "other check condition"
+        );
+    }
 }
@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, clear_reserved_fields, reserved_fields, ReservedFields},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -17,6 +17,7 @@ pub struct WriteCommand<'a> {
     write_protect: u8,
     disable_page_out: bool,
     force_unit_access: bool,
+    verify_dpo_fua_support: bool,
     logical_block_address: u64,
     expected_initial_logical_block_reference_tag: u32,
     expected_logical_block_application_tag: u16,
@@ -32,11 +33,12 @@ impl<'a> WriteCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             group_number: 0,
             write_protect: 0,
             disable_page_out: false,
             force_unit_access: false,
+            verify_dpo_fua_support: false,
             logical_block_address: 0,
             expected_initial_logical_block_reference_tag: 0,
             expected_logical_block_application_tag: 0,
@@ -44,7 +46,7 @@ impl<'a> WriteCommand<'a> {
             dld_0: false,
             dld_1: false,
             dld_2: false,
-            logical_block_size: 512,
+            logical_block_size: interface.default_logical_block_size(),
             data_buffer: vec![],
         }
     }
@@ -61,16 +63,44 @@ impl<'a> WriteCommand<'a> {
         self
     }
 
+    // group_number must be less than 0x40, issue_10, issue_12 and issue_32 further require less than 0x20
+    pub fn group_number_checked(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 6, "group number")?;
+        Ok(self.group_number(value))
+    }
+
+    // write_protect must be less than 0x08
+    pub fn write_protect_checked(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 3, "write protect")?;
+        Ok(self.write_protect(value))
+    }
+
+    // DPO asks the device not to keep the blocks written in its cache
     pub fn disable_page_out(&mut self, value: bool) -> &mut Self {
         self.disable_page_out = value;
         self
     }
 
+    // FUA makes the device complete the write only once the blocks are on the medium,
+    // which is redundant while the write cache is disabled in the Caching mode page
     pub fn force_unit_access(&mut self, value: bool) -> &mut Self {
         self.force_unit_access = value;
         self
     }
 
+    // sets DPO and FUA together, the device cache is neither relied on nor filled
+    pub fn cache_bypass(&mut self) -> &mut Self {
+        self.disable_page_out(true).force_unit_access(true)
+    }
+
+    // when DPO or FUA is set, issue first checks with Scsi::dpo_fua_supported that the
+    // device honours them and fails with BadArgument otherwise, instead of the device
+    // silently ignoring them; this costs a MODE SENSE per issue
+    pub fn verify_dpo_fua_support(&mut self, value: bool) -> &mut Self {
+        self.verify_dpo_fua_support = value;
+        self
+    }
+
     pub fn logical_block_address(&mut self, value: u64) -> &mut Self {
         self.logical_block_address = value;
         self
@@ -122,6 +152,11 @@ impl<'a> WriteCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(
         &self,
         group_number_bits: u32,
@@ -131,7 +166,7 @@ impl<'a> WriteCommand<'a> {
         expect_tag: bool,
     ) -> crate::Result<()> {
         bitfield_bound_check!(self.group_number, group_number_bits, "group number")?;
-        bitfield_bound_check!(self.write_protect, 3, "verify protect")?;
+        bitfield_bound_check!(self.write_protect, 3, "write protect")?;
         bitfield_bound_check!(
             self.logical_block_address,
             logical_block_address_bits,
@@ -175,10 +210,105 @@ impl<'a> WriteCommand<'a> {
         Ok(())
     }
 
+    // queries the device, so it runs from the issue methods only and not from error_check
+    fn check_dpo_fua_support(&self) -> crate::Result<()> {
+        if self.verify_dpo_fua_support
+            && (self.disable_page_out || self.force_unit_access)
+            && !self.interface.dpo_fua_supported()?
+        {
+            return Err(crate::Error::BadArgument(
+                "DPO and FUA are not supported by the device.".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // with type 1 or 2 protection, write(32) takes the starting reference tag from the cdb and
+    // leaving every tag zero for a nonzero lba fails the check on the first block. only then
+    // READ CAPACITY(16) is sent to learn the protection type, so this runs from issue_32 only
+    fn check_reference_tag(&self) -> crate::Result<()> {
+        let tags_are_zero = self.expected_initial_logical_block_reference_tag == 0
+            && self.expected_logical_block_application_tag == 0
+            && self.logical_block_application_tag_mask == 0;
+        if self.write_protect == 0 || !tags_are_zero || self.logical_block_address as u32 == 0 {
+            return Ok(());
+        }
+
+        let capacity = self.interface.read_capacity().issue_16()?;
+        // P_TYPE 000b is type 1 and 001b type 2, type 3 leaves the reference tag unchecked
+        if capacity.protection_enabled && capacity.protection_type < 2 {
+            return Err(crate::Error::BadArgument(
+                "expected tags should be set when write protect is nonzero".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn lba_range(&self) -> (u64, u64) {
+        (
+            self.logical_block_address,
+            (self.data_buffer.len() / self.logical_block_size as usize) as u64,
+        )
+    }
+
     pub fn issue_10(&mut self) -> crate::Result<()> {
+        let command_buffer = self.command_buffer_10()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    pub fn issue_12(&mut self) -> crate::Result<()> {
+        let command_buffer = self.command_buffer_12()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    pub fn issue_16(&mut self) -> crate::Result<()> {
+        let command_buffer = self.command_buffer_16()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    pub fn issue_32(&mut self) -> crate::Result<()> {
+        let command_buffer = self.command_buffer_32()?;
+        self.check_reference_tag()?;
+        self.check_dpo_fua_support()?;
+        self.issue_buffer(command_buffer)
+    }
+
+    // the build_cdb methods run the checks of the matching issue method and return the cdb
+    // it would send, without sending anything
+
+    pub fn build_cdb_10(&self) -> crate::Result<[u8; 10]> {
+        Ok(self.command_buffer_10()?.into_bytes())
+    }
+
+    pub fn build_cdb_12(&self) -> crate::Result<[u8; 12]> {
+        Ok(self.command_buffer_12()?.into_bytes())
+    }
+
+    pub fn build_cdb_16(&self) -> crate::Result<[u8; 16]> {
+        Ok(self.command_buffer_16()?.into_bytes())
+    }
+
+    pub fn build_cdb_32(&self) -> crate::Result<[u8; 32]> {
+        Ok(self.command_buffer_32()?.into_bytes())
+    }
+
+    fn issue_buffer<C: ReservedFields>(&self, command_buffer: C) -> crate::Result<()> {
+        self.interface.issue(&ThisCommand {
+            command_buffer,
+            data_buffer: self.data_buffer.clone().into(),
+            lba_range: self.lba_range(),
+        })
+    }
+
+    fn command_buffer_10(&self) -> crate::Result<CommandBuffer10> {
         self.error_check(5, 32, 16, false, false)?;
 
-        let command_buffer = CommandBuffer10::new()
+        Ok(CommandBuffer10::new()
             .with_operation_code(OPERATION_CODE_10)
             .with_write_protect(self.write_protect)
             .with_disable_page_out(self.disable_page_out.into())
@@ -188,18 +318,13 @@ impl<'a> WriteCommand<'a> {
             .with_transfer_length(
                 (self.data_buffer.len() / self.logical_block_size as usize) as u16,
             )
-            .with_control(self.control);
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
-        })
+            .with_control(self.control))
     }
 
-    pub fn issue_12(&mut self) -> crate::Result<()> {
+    fn command_buffer_12(&self) -> crate::Result<CommandBuffer12> {
         self.error_check(5, 32, 32, false, false)?;
 
-        let command_buffer = CommandBuffer12::new()
+        Ok(CommandBuffer12::new()
             .with_operation_code(OPERATION_CODE_12)
             .with_write_protect(self.write_protect)
             .with_disable_page_out(self.disable_page_out.into())
@@ -209,18 +334,13 @@ impl<'a> WriteCommand<'a> {
                 (self.data_buffer.len() / self.logical_block_size as usize) as u32,
             )
             .with_group_number(self.group_number)
-            .with_control(self.control);
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
-        })
+            .with_control(self.control))
     }
 
-    pub fn issue_16(&mut self) -> crate::Result<()> {
+    fn command_buffer_16(&self) -> crate::Result<CommandBuffer16> {
         self.error_check(6, 64, 32, true, false)?;
 
-        let command_buffer = CommandBuffer16::new()
+        Ok(CommandBuffer16::new()
             .with_operation_code(OPERATION_CODE_16)
             .with_write_protect(self.write_protect)
             .with_disable_page_out(self.disable_page_out.into())
@@ -233,18 +353,13 @@ impl<'a> WriteCommand<'a> {
             .with_dld_1(self.dld_1.into())
             .with_dld_2(self.dld_2.into())
             .with_group_number(self.group_number)
-            .with_control(self.control);
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
-        })
+            .with_control(self.control))
     }
 
-    pub fn issue_32(&mut self) -> crate::Result<()> {
+    fn command_buffer_32(&self) -> crate::Result<CommandBuffer32> {
         self.error_check(5, 64, 32, false, true)?;
 
-        let command_buffer = CommandBuffer32::new()
+        Ok(CommandBuffer32::new()
             .with_operation_code(OPERATION_CODE_32)
             .with_control(self.control)
             .with_group_number(self.group_number)
@@ -263,12 +378,7 @@ impl<'a> WriteCommand<'a> {
             .with_logical_block_application_tag_mask(self.logical_block_application_tag_mask)
             .with_transfer_length(
                 (self.data_buffer.len() / self.logical_block_size as usize) as u32,
-            );
-
-        self.interface.issue(&ThisCommand {
-            command_buffer,
-            data_buffer: self.data_buffer.clone().into(),
-        })
+            ))
     }
 }
 
@@ -278,6 +388,33 @@ impl Scsi {
     }
 }
 
+// builds the cdb alone, without needing a Scsi, all other fields are zero
+pub fn build_cdb_10(logical_block_address: u32, transfer_length: u16) -> [u8; 10] {
+    CommandBuffer10::new()
+        .with_operation_code(OPERATION_CODE_10)
+        .with_logical_block_address(logical_block_address)
+        .with_transfer_length(transfer_length)
+        .into_bytes()
+}
+
+// builds the cdb alone, without needing a Scsi, all other fields are zero
+pub fn build_cdb_12(logical_block_address: u32, transfer_length: u32) -> [u8; 12] {
+    CommandBuffer12::new()
+        .with_operation_code(OPERATION_CODE_12)
+        .with_logical_block_address(logical_block_address)
+        .with_transfer_length(transfer_length)
+        .into_bytes()
+}
+
+// builds the cdb alone, without needing a Scsi, all other fields are zero
+pub fn build_cdb_16(logical_block_address: u64, transfer_length: u32) -> [u8; 16] {
+    CommandBuffer16::new()
+        .with_operation_code(OPERATION_CODE_16)
+        .with_logical_block_address(logical_block_address)
+        .with_transfer_length(transfer_length)
+        .into_bytes()
+}
+
 const OPERATION_CODE_10: u8 = 0x2A;
 const OPERATION_CODE_12: u8 = 0xAA;
 const OPERATION_CODE_16: u8 = 0x8A;
@@ -358,12 +495,40 @@ struct CommandBuffer32 {
     transfer_length: B32,
 }
 
+reserved_fields!(
+    CommandBuffer10,
+    reserved_0 / with_reserved_0,
+    obsolete / with_obsolete,
+    reserved_1 / with_reserved_1
+);
+reserved_fields!(
+    CommandBuffer12,
+    reserved_0 / with_reserved_0,
+    obsolete / with_obsolete,
+    reserved_1 / with_reserved_1
+);
+reserved_fields!(
+    CommandBuffer16,
+    reserved / with_reserved,
+    obsolete / with_obsolete
+);
+reserved_fields!(
+    CommandBuffer32,
+    reserved_0 / with_reserved_0,
+    reserved_1 / with_reserved_1,
+    reserved_2 / with_reserved_2,
+    obsolete / with_obsolete,
+    reserved_3 / with_reserved_3,
+    reserved_4 / with_reserved_4
+);
+
 struct ThisCommand<C> {
     command_buffer: C,
     data_buffer: VecBufferWrapper,
+    lba_range: (u64, u64),
 }
 
-impl<C: Copy> Command for ThisCommand<C> {
+impl<C: ReservedFields> Command for ThisCommand<C> {
     type CommandBuffer = C;
 
     type DataBuffer = AnyType;
@@ -377,7 +542,7 @@ impl<C: Copy> Command for ThisCommand<C> {
     }
 
     fn command(&self) -> Self::CommandBuffer {
-        self.command_buffer
+        clear_reserved_fields(self.command_buffer)
     }
 
     fn data(&self) -> Self::DataBufferWrapper {
@@ -388,12 +553,20 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
 
         Ok(())
     }
+
+    fn lba_range(&self) -> Option<(u64, u64)> {
+        Some(self.lba_range)
+    }
 }
 
 #[cfg(test)]
@@ -432,4 +605,223 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn build_cdb_test() {
+        assert_eq!(
+            build_cdb_10(0x12345678, 0x0008),
+            [0x2A, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x08, 0x00],
+            "write(10)"
+        );
+
+        assert_eq!(
+            build_cdb_12(0x12345678, 0x00010000),
+            [0xAA, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00],
+            "write(12)"
+        );
+
+        assert_eq!(
+            build_cdb_16(0x0102030405060708, 0x20),
+            [
+                0x8A, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x00, 0x00, 0x00, 0x20,
+                0x00, 0x00
+            ],
+            "write(16)"
+        );
+    }
+
+    #[test]
+    fn checked_setter_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.write();
+
+        assert!(command.write_protect_checked(0x07).is_ok(), "write protect");
+        assert!(
+            command.write_protect_checked(0x08).is_err(),
+            "write protect"
+        );
+        assert!(command.group_number_checked(0x3F).is_ok(), "group number");
+        assert!(command.group_number_checked(0x40).is_err(), "group number");
+    }
+
+    #[test]
+    fn parameter_vec_test() {
+        let scsi = Scsi::test_instance();
+        let data: Vec<u8> = (0..1024).map(|n| n as u8).collect();
+
+        scsi.write().parameter(&data).issue_16().unwrap();
+        scsi.write().parameter_vec(data.clone()).issue_16().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb, issued[1].cdb, "cdb");
+        assert_eq!(issued[0].data_out, data, "parameter");
+        assert_eq!(issued[1].data_out, data, "parameter_vec");
+    }
+
+    #[test]
+    fn dld_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.write();
+        command.dld_1(true).parameter(&[0; 512]);
+
+        assert!(
+            command.error_check(5, 32, 16, false, false).is_err(),
+            "write(10)"
+        );
+        assert!(
+            command.error_check(5, 32, 32, false, false).is_err(),
+            "write(12)"
+        );
+        assert!(
+            command.error_check(6, 64, 32, true, false).is_ok(),
+            "write(16)"
+        );
+
+        command.issue_16().unwrap();
+        assert_eq!(
+            scsi.mock().issued()[0].cdb[14],
+            0b1000_0000,
+            "dld_1 in write(16)"
+        );
+    }
+
+    #[test]
+    fn expected_tag_test() {
+        // READ CAPACITY(16) data with PROT_EN set and the given P_TYPE
+        let capacity = |protection_type: u8| {
+            let mut data = vec![0; 32];
+            data[8..12].copy_from_slice(&512u32.to_be_bytes());
+            data[12] = (protection_type << 1) | 0x01;
+            data
+        };
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&capacity(0));
+        let mut command = scsi.write();
+        command
+            .write_protect(0b001)
+            .logical_block_address(0x1000)
+            .parameter(&[0; 512]);
+        assert!(
+            matches!(command.issue_32(), Err(crate::Error::BadArgument(_))),
+            "type 1 protection without tags"
+        );
+        assert_eq!(scsi.mock().issued().len(), 1, "only read capacity issued");
+
+        command.expected_initial_logical_block_reference_tag(0x1000);
+        command.issue_32().unwrap();
+        assert_eq!(scsi.mock().issued().len(), 2, "no query with tags set");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&capacity(2));
+        let mut command = scsi.write();
+        command
+            .write_protect(0b001)
+            .logical_block_address(0x1000)
+            .parameter(&[0; 512]);
+        command.issue_32().unwrap();
+        assert_eq!(
+            scsi.mock().issued()[1].cdb[20..24],
+            [0; 4],
+            "type 3 protection sends a zero reference tag"
+        );
+
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.write();
+        command.write_protect(0b001).parameter(&[0; 512]);
+        command.issue_32().unwrap();
+        assert_eq!(
+            scsi.mock().issued().len(),
+            1,
+            "lba 0 matches a zero reference tag"
+        );
+    }
+
+    #[test]
+    fn cache_bypass_test() {
+        let scsi = Scsi::test_instance();
+        scsi.write()
+            .parameter(&[0; 512])
+            .cache_bypass()
+            .issue_16()
+            .unwrap();
+
+        assert_eq!(scsi.mock().issued()[0].cdb[1], 0x18, "DPO and FUA");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock()
+            .push_data(&[0x00, 0x1A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        let result = scsi
+            .write()
+            .parameter(&[0; 512])
+            .force_unit_access(true)
+            .verify_dpo_fua_support(true)
+            .issue_16();
+
+        assert!(
+            matches!(result, Err(crate::Error::BadArgument(_))),
+            "unsupported"
+        );
+        assert_eq!(scsi.mock().issued().len(), 1, "only mode sense issued");
+    }
+
+    #[test]
+    fn reserved_fields_test() {
+        let scsi = Scsi::test_instance();
+
+        let mut command = scsi.write();
+        command
+            .write_protect(0x07)
+            .disable_page_out(true)
+            .force_unit_access(true)
+            .group_number(0x1F)
+            .logical_block_address(u32::MAX.into())
+            .logical_block_size(512)
+            .parameter(&[0; 512]);
+        command.issue_10().unwrap();
+        command
+            .dld_0(true)
+            .dld_1(true)
+            .dld_2(true)
+            .issue_16()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb[1] & 0x07,
+            0,
+            "write(10) reserved and obsolete"
+        );
+        assert_eq!(issued[0].cdb[6] & 0xE0, 0, "write(10) reserved");
+        assert_eq!(
+            issued[1].cdb[1] & 0x06,
+            0,
+            "write(16) reserved and obsolete"
+        );
+    }
+
+    #[test]
+    fn builder_build_cdb_test() {
+        let scsi = Scsi::test_instance();
+        let mut command = scsi.write();
+        command
+            .logical_block_address(0x0100)
+            .force_unit_access(true)
+            .logical_block_size(512)
+            .parameter(&[0; 1024]);
+
+        assert_eq!(
+            command.build_cdb_10().unwrap(),
+            [0x2A, 0x08, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00],
+            "write(10)"
+        );
+        assert!(
+            matches!(
+                command.parameter(&[0; 100]).build_cdb_16(),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "partial block"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
 }
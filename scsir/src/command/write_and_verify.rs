@@ -29,7 +29,7 @@ impl<'a> WriteAndVerifyCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             group_number: 0,
             write_protect: 0,
             disable_page_out: false,
@@ -38,7 +38,7 @@ impl<'a> WriteAndVerifyCommand<'a> {
             expected_initial_logical_block_reference_tag: 0,
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
-            logical_block_size: 512,
+            logical_block_size: interface.default_logical_block_size(),
             data_buffer: vec![],
         }
     }
@@ -66,6 +66,11 @@ impl<'a> WriteAndVerifyCommand<'a> {
     }
 
     // byte_check must be less than 0x04
+    // the parameter is always the data to write, there is no separate compare buffer
+    // 0b00: verify the written blocks on the medium only
+    // 0b01: compare the written blocks with the parameter
+    // 0b10: reserved
+    // 0b11: check the written blocks against their protection information
     pub fn byte_check(&mut self, value: u8) -> &mut Self {
         self.byte_check = value;
         self
@@ -102,6 +107,11 @@ impl<'a> WriteAndVerifyCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(
         &self,
         logical_block_address_bits: u32,
@@ -117,6 +127,12 @@ impl<'a> WriteAndVerifyCommand<'a> {
             "logical block address"
         )?;
 
+        if self.byte_check == 0b10 {
+            return Err(crate::Error::BadArgument(
+                "byte check 2 is reserved.".to_owned(),
+            ));
+        }
+
         if self.data_buffer.len() % self.logical_block_size as usize != 0 {
             return Err(crate::Error::BadArgument(format!(
                 "parameter length should be a multiple of logical block size, which is {}.",
@@ -355,6 +371,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -399,4 +419,30 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn byte_check_test() {
+        let scsi = Scsi::test_instance();
+
+        for byte_check in [0b00, 0b01, 0b11] {
+            let mut command = scsi.write_and_verify();
+            command.byte_check(byte_check).parameter(&[0; 1024]);
+            assert!(
+                command.error_check(32, 16, false).is_ok(),
+                "byte check {}",
+                byte_check
+            );
+        }
+
+        let mut command = scsi.write_and_verify();
+        command.byte_check(0b10).parameter(&[0; 1024]);
+        assert!(command.error_check(32, 16, false).is_err(), "byte check 2");
+
+        let mut command = scsi.write_and_verify();
+        command.byte_check(0b11).parameter(&[0; 1000]);
+        assert!(
+            command.error_check(32, 16, false).is_err(),
+            "byte check 3 with partial block"
+        );
+    }
 }
@@ -30,7 +30,7 @@ impl<'a> WriteAtomicCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             atomic_boundary: 0,
             group_number: 0,
             write_protect: 0,
@@ -40,7 +40,7 @@ impl<'a> WriteAtomicCommand<'a> {
             expected_initial_logical_block_reference_tag: 0,
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
-            logical_block_size: 512,
+            logical_block_size: interface.default_logical_block_size(),
             data_buffer: vec![],
         }
     }
@@ -108,6 +108,11 @@ impl<'a> WriteAtomicCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(
         &self,
         logical_block_address_bits: u32,
@@ -288,6 +293,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -3,7 +3,7 @@
 use modular_bitfield_msb::prelude::*;
 
 use crate::{
-    command::bitfield_bound_check,
+    command::{bitfield_bound_check, read_buffer},
     data_wrapper::{AnyType, VecBufferWrapper},
     result_data::ResultData,
     Command, DataDirection, Scsi,
@@ -26,7 +26,9 @@ impl<'a> ReadBufferCommand<'a> {
             mode_specific: 0,
             mode: 0,
             buffer_offset: 0,
-            command_buffer: CommandBuffer::new().with_operation_code(OPERATION_CODE),
+            command_buffer: CommandBuffer::new()
+                .with_operation_code(OPERATION_CODE)
+                .with_control(interface.default_control()),
             data_buffer: vec![],
         }
     }
@@ -65,6 +67,11 @@ impl<'a> ReadBufferCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     pub fn issue(&mut self) -> crate::Result<()> {
         bitfield_bound_check!(self.mode_specific, 3, "mode specific")?;
         bitfield_bound_check!(self.mode, 5, "mode")?;
@@ -89,8 +96,35 @@ impl Scsi {
     pub fn write_buffer(&self) -> ReadBufferCommand<'_> {
         ReadBufferCommand::new(self)
     }
+
+    /// Writes pattern to the echo buffer and reads it back, without touching the medium.
+    /// Returns whether the data read back matches pattern.
+    pub fn echo_buffer_test(&self, pattern: &[u8]) -> crate::Result<bool> {
+        if pattern.is_empty() {
+            return Err(crate::Error::BadArgument(
+                "pattern can't be empty".to_owned(),
+            ));
+        }
+
+        self.write_buffer()
+            .mode(WRITE_ECHO_BUFFER_MODE)
+            .parameter(pattern)
+            .issue()?;
+
+        let echo = self
+            .read_buffer()
+            .mode(read_buffer::ECHO_BUFFER_MODE)
+            .allocation_length(pattern.len() as u32)
+            .issue_10()?;
+
+        Ok(echo == pattern)
+    }
 }
 
+pub const WRITE_ECHO_BUFFER_MODE: u8 = 0x0A;
+pub const DOWNLOAD_MICROCODE_DEFER_ACTIVATE_MODE: u8 = 0x0E;
+pub const ACTIVATE_DEFERRED_MICROCODE_MODE: u8 = 0x0F;
+
 const OPERATION_CODE: u8 = 0x3B;
 
 #[bitfield]
@@ -135,6 +169,10 @@ impl Command for ThisCommand {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -158,4 +196,24 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer))
         );
     }
+
+    #[test]
+    fn echo_buffer_test() {
+        let scsi = Scsi::test_instance();
+        let pattern = [0x5A, 0xA5, 0x00, 0xFF];
+
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_data(&pattern);
+        assert!(scsi.echo_buffer_test(&pattern).unwrap(), "match");
+
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_data(&[0x5A, 0xA5, 0x01, 0xFF]);
+        assert!(!scsi.echo_buffer_test(&pattern).unwrap(), "mismatch");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb[..2], [0x3B, 0x0A], "write echo buffer");
+        assert_eq!(issued[0].data_out, pattern, "written pattern");
+        assert_eq!(issued[1].cdb[..2], [0x3C, 0x0A], "read echo buffer");
+        assert_eq!(issued[1].cdb[6..9], [0x00, 0x00, 0x04], "allocation length");
+    }
 }
@@ -12,7 +12,9 @@ use crate::{
 #[derive(Clone, Debug)]
 pub struct WriteLongCommand<'a> {
     interface: &'a Scsi,
+    cor_dis: bool,
     wr_uncor: bool,
+    pblock: bool,
     logical_block_address: u64,
     control: u8,
     data_buffer: Vec<u8>,
@@ -22,18 +24,31 @@ impl<'a> WriteLongCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
+            cor_dis: false,
             wr_uncor: false,
+            pblock: false,
             logical_block_address: 0,
-            control: 0,
+            control: interface.default_control(),
             data_buffer: vec![],
         }
     }
 
+    pub fn cor_dis(&mut self, value: bool) -> &mut Self {
+        self.cor_dis = value;
+        self
+    }
+
+    // no data is transferred when wr_uncor is set, so parameter must be empty
     pub fn wr_uncor(&mut self, value: bool) -> &mut Self {
         self.wr_uncor = value;
         self
     }
 
+    pub fn pblock(&mut self, value: bool) -> &mut Self {
+        self.pblock = value;
+        self
+    }
+
     pub fn logical_block_address(&mut self, value: u64) -> &mut Self {
         self.logical_block_address = value;
         self
@@ -50,13 +65,36 @@ impl<'a> WriteLongCommand<'a> {
         self
     }
 
-    pub fn issue_10(&mut self) -> crate::Result<()> {
-        bitfield_bound_check!(self.logical_block_address, 32, "logical block address")?;
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
+    fn error_check(&self, logical_block_address_bits: u32) -> crate::Result<()> {
+        bitfield_bound_check!(
+            self.logical_block_address,
+            logical_block_address_bits,
+            "logical block address"
+        )?;
         bitfield_bound_check!(self.data_buffer.len(), 16, "parameter length")?;
 
+        if self.wr_uncor && !self.data_buffer.is_empty() {
+            return Err(crate::Error::BadArgument(
+                "parameter is not allowed when wr_uncor is set".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn issue_10(&mut self) -> crate::Result<()> {
+        self.error_check(32)?;
+
         let command_buffer = CommandBuffer10::new()
             .with_operation_code(OPERATION_CODE_10)
+            .with_cor_dis(self.cor_dis.into())
             .with_wr_uncor(self.wr_uncor.into())
+            .with_pblock(self.pblock.into())
             .with_logical_block_address(self.logical_block_address as u32)
             .with_byte_transfer_length(self.data_buffer.len() as u16)
             .with_control(self.control);
@@ -68,11 +106,13 @@ impl<'a> WriteLongCommand<'a> {
     }
 
     pub fn issue_16(&mut self) -> crate::Result<()> {
-        bitfield_bound_check!(self.data_buffer.len(), 16, "parameter length")?;
+        self.error_check(64)?;
 
         let command_buffer = CommandBuffer16::new()
             .with_operation_code(OPERATION_CODE_16)
+            .with_cor_dis(self.cor_dis.into())
             .with_wr_uncor(self.wr_uncor.into())
+            .with_pblock(self.pblock.into())
             .with_service_action(SERVICE_ACTION_16)
             .with_logical_block_address(self.logical_block_address)
             .with_byte_transfer_length(self.data_buffer.len() as u16)
@@ -99,11 +139,11 @@ const SERVICE_ACTION_16: u8 = 0x11;
 #[derive(Clone, Copy)]
 struct CommandBuffer10 {
     operation_code: B8,
-    obsolete_0: B1,
+    cor_dis: B1,
     wr_uncor: B1,
-    obsolete_1: B1,
+    pblock: B1,
     reserved_0: B4,
-    obsolete_2: B1,
+    obsolete: B1,
     logical_block_address: B32,
     reserved_1: B8,
     byte_transfer_length: B16,
@@ -114,9 +154,9 @@ struct CommandBuffer10 {
 #[derive(Clone, Copy)]
 struct CommandBuffer16 {
     operation_code: B8,
-    obsolete_0: B1,
+    cor_dis: B1,
     wr_uncor: B1,
-    obsolete_1: B1,
+    pblock: B1,
     service_action: B5,
     logical_block_address: B64,
     reserved_1: B16,
@@ -155,6 +195,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -185,4 +229,49 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer16))
         );
     }
+
+    #[test]
+    fn correction_bits_test() {
+        let scsi = Scsi::test_instance();
+
+        scsi.write_long()
+            .cor_dis(true)
+            .pblock(true)
+            .parameter(&[0; 4])
+            .issue_10()
+            .unwrap();
+        scsi.write_long().wr_uncor(true).issue_16().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb[1], 0b1010_0000,
+            "write long(10) cor_dis and pblock"
+        );
+        assert_eq!(issued[0].cdb[7..9], [0x00, 0x04], "byte transfer length");
+        assert_eq!(issued[1].cdb[1], 0b0101_0001, "write long(16) wr_uncor");
+        assert_eq!(issued[1].cdb[12..14], [0x00, 0x00], "byte transfer length");
+    }
+
+    #[test]
+    fn wr_uncor_length_test() {
+        let scsi = Scsi::test_instance();
+
+        assert!(
+            scsi.write_long()
+                .wr_uncor(true)
+                .parameter(&[0; 512])
+                .issue_10()
+                .is_err(),
+            "write long(10)"
+        );
+        assert!(
+            scsi.write_long()
+                .wr_uncor(true)
+                .parameter(&[0; 512])
+                .issue_16()
+                .is_err(),
+            "write long(16)"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+    }
 }
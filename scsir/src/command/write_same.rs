@@ -23,6 +23,7 @@ pub struct WriteSameCommand<'a> {
     expected_logical_block_application_tag: u16,
     logical_block_application_tag_mask: u16,
     number_of_blocks: u32,
+    logical_block_size: u32,
     data_buffer: Vec<u8>,
 }
 
@@ -30,7 +31,7 @@ impl<'a> WriteSameCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             group_number: 0,
             write_protect: 0,
             anchor: false,
@@ -41,6 +42,7 @@ impl<'a> WriteSameCommand<'a> {
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
             number_of_blocks: 0,
+            logical_block_size: interface.default_logical_block_size(),
             data_buffer: vec![],
         }
     }
@@ -102,12 +104,24 @@ impl<'a> WriteSameCommand<'a> {
         self
     }
 
+    // the parameter has to be exactly one logical block unless no_data_out_buffer is set,
+    // followed by its 8 bytes of protection information when write_protect is nonzero
+    pub fn logical_block_size(&mut self, value: u32) -> &mut Self {
+        self.logical_block_size = value;
+        self
+    }
+
     pub fn parameter(&mut self, value: &[u8]) -> &mut Self {
         self.data_buffer.clear();
         self.data_buffer.extend_from_slice(value);
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(
         &self,
         logical_block_address_bits: u32,
@@ -134,6 +148,19 @@ impl<'a> WriteSameCommand<'a> {
             ));
         }
 
+        let expected_length = if self.write_protect != 0 {
+            self.logical_block_size as usize + PROTECTION_INFORMATION_LENGTH
+        } else {
+            self.logical_block_size as usize
+        };
+        if !self.no_data_out_buffer && self.data_buffer.len() != expected_length {
+            return Err(crate::Error::BadArgument(format!(
+                "parameter should be exactly one logical block with its protection information if any, which is {}, but {} bytes were provided.",
+                expected_length,
+                self.data_buffer.len()
+            )));
+        }
+
         if !expect_tag
             && (self.expected_initial_logical_block_reference_tag != 0
                 || self.expected_logical_block_application_tag != 0
@@ -186,6 +213,27 @@ impl<'a> WriteSameCommand<'a> {
         })
     }
 
+    /// Deallocates number_of_blocks blocks starting at logical_block_address with UNMAP and
+    /// NDOB set, so no data is transferred.
+    ///
+    /// The device has to set LBPWS in the logical block provisioning VPD page and support the
+    /// NDOB bit of WRITE SAME(16), which REPORT SUPPORTED OPERATION CODES shows in its cdb usage
+    /// data. provisioning_confirmed must be true once the caller has checked both.
+    pub fn issue_16_unmap_only(&mut self, provisioning_confirmed: bool) -> crate::Result<()> {
+        if !provisioning_confirmed {
+            return Err(crate::Error::BadArgument(
+                "unmap only write same needs confirmed logical block provisioning support"
+                    .to_owned(),
+            ));
+        }
+
+        self.unmap = true;
+        self.no_data_out_buffer = true;
+        self.data_buffer.clear();
+
+        self.issue_16()
+    }
+
     pub fn issue_32(&mut self) -> crate::Result<()> {
         self.error_check(64, 32, true, true)?;
 
@@ -223,10 +271,12 @@ impl Scsi {
 }
 
 const OPERATION_CODE_10: u8 = 0x41;
-const OPERATION_CODE_16: u8 = 0x93;
+pub(super) const OPERATION_CODE_16: u8 = 0x93;
 const OPERATION_CODE_32: u8 = 0x7F;
 const SERVICE_ACTION_32: u16 = 0x000D;
 
+const PROTECTION_INFORMATION_LENGTH: usize = 8;
+
 #[bitfield]
 #[derive(Clone, Copy)]
 struct CommandBuffer10 {
@@ -311,6 +361,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
@@ -348,4 +402,108 @@ mod tests {
             concat!("Size of: ", stringify!(CommandBuffer32))
         );
     }
+
+    #[test]
+    fn issue_16_unmap_only_test() {
+        let scsi = Scsi::test_instance();
+
+        let result = scsi.write_same().issue_16_unmap_only(false);
+        assert!(
+            matches!(result, Err(crate::Error::BadArgument(_))),
+            "unconfirmed"
+        );
+        assert!(scsi.mock().issued().is_empty(), "nothing issued");
+
+        scsi.write_same()
+            .logical_block_address(0x1000)
+            .number_of_blocks(0x800)
+            .parameter(&[0; 512])
+            .issue_16_unmap_only(true)
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [
+                0x93, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x08, 0x00,
+                0x00, 0x00
+            ],
+            "unmap and ndob"
+        );
+        assert!(issued[0].data_out.is_empty(), "no data out");
+    }
+
+    #[test]
+    fn parameter_length_test() {
+        let scsi = Scsi::test_instance();
+
+        let mut command = scsi.write_same();
+        command.parameter(&[0xA5; 512]);
+        assert!(
+            command.error_check(64, 32, true, false).is_ok(),
+            "one block"
+        );
+
+        command.parameter(&[0xA5; 1024]);
+        assert!(
+            matches!(
+                command.error_check(64, 32, true, false),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "two blocks"
+        );
+
+        command.parameter(&[]);
+        assert!(
+            command.error_check(64, 32, true, false).is_err(),
+            "no parameter"
+        );
+
+        command.logical_block_size(4096).parameter(&[0xA5; 4096]);
+        assert!(
+            command.error_check(64, 32, true, false).is_ok(),
+            "logical block size"
+        );
+
+        command.no_data_out_buffer(true).parameter(&[]);
+        assert!(
+            command.error_check(64, 32, true, false).is_ok(),
+            "no data out buffer"
+        );
+
+        scsi.set_cached_block_size(4096);
+        let mut command = scsi.write_same();
+        command.parameter(&[0xA5; 512]);
+        assert!(
+            command.error_check(64, 32, true, false).is_err(),
+            "cached block size"
+        );
+    }
+
+    #[test]
+    fn protection_information_length_test() {
+        let scsi = Scsi::test_instance();
+
+        let mut command = scsi.write_same();
+        command.write_protect(0b001).parameter(&[0xA5; 512]);
+        assert!(
+            matches!(
+                command.error_check(64, 32, true, false),
+                Err(crate::Error::BadArgument(_))
+            ),
+            "block without protection information"
+        );
+
+        command.parameter(&[0xA5; 520]);
+        assert!(
+            command.error_check(64, 32, true, false).is_ok(),
+            "block with protection information"
+        );
+
+        command.write_protect(0).parameter(&[0xA5; 520]);
+        assert!(
+            command.error_check(64, 32, true, false).is_err(),
+            "protection information without write protect"
+        );
+    }
 }
@@ -30,7 +30,7 @@ impl<'a> WriteSameCommand<'a> {
     fn new(interface: &'a Scsi) -> Self {
         Self {
             interface,
-            control: 0,
+            control: interface.default_control(),
             stream_identifier: 0,
             group_number: 0,
             write_protect: 0,
@@ -41,7 +41,7 @@ impl<'a> WriteSameCommand<'a> {
             expected_logical_block_application_tag: 0,
             logical_block_application_tag_mask: 0,
             data_buffer: vec![],
-            logical_block_size: 512,
+            logical_block_size: interface.default_logical_block_size(),
         }
     }
 
@@ -103,6 +103,11 @@ impl<'a> WriteSameCommand<'a> {
         self
     }
 
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
     fn error_check(&self, transfer_length_bits: u32, expect_tag: bool) -> crate::Result<()> {
         bitfield_bound_check!(self.group_number, 6, "group number")?;
         bitfield_bound_check!(self.write_protect, 3, "write protect")?;
@@ -272,6 +277,10 @@ impl<C: Copy> Command for ThisCommand<C> {
         self.data_buffer.len() as u32
     }
 
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
     fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
         result.check_ioctl_error()?;
         result.check_common_error()?;
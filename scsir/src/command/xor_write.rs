@@ -0,0 +1,243 @@
+#![allow(dead_code)]
+
+use std::marker::PhantomData;
+
+use modular_bitfield_msb::prelude::*;
+
+use crate::{
+    command::bitfield_bound_check,
+    data_wrapper::{AnyType, VecBufferWrapper},
+    result_data::ResultData,
+    Command, DataDirection, Scsi,
+};
+
+/// XDWRITE(10) and XPWRITE(10), which only differ in their operation code and in the flag
+/// kept next to FUA, see XdWriteCommand and XpWriteCommand.
+#[derive(Clone, Debug)]
+pub struct XorWriteCommand<'a, K> {
+    interface: &'a Scsi,
+    control: u8,
+    group_number: u8,
+    write_protect: u8,
+    disable_page_out: bool,
+    force_unit_access: bool,
+    // disable_write of XDWRITE, xor_protection_information of XPWRITE
+    pub(super) flag: bool,
+    logical_block_address: u32,
+    logical_block_size: u32,
+    data_buffer: Vec<u8>,
+    kind: PhantomData<K>,
+}
+
+/// Selects the command a XorWriteCommand issues.
+pub trait XorWriteKind {
+    const OPERATION_CODE: u8;
+    // the bit of the flag within the low 3 bits of cdb byte 1
+    const FLAG_MASK: u8;
+}
+
+impl<'a, K: XorWriteKind> XorWriteCommand<'a, K> {
+    pub(super) fn new(interface: &'a Scsi) -> Self {
+        Self {
+            interface,
+            control: interface.default_control(),
+            group_number: 0,
+            write_protect: 0,
+            disable_page_out: false,
+            force_unit_access: false,
+            flag: false,
+            logical_block_address: 0,
+            logical_block_size: interface.default_logical_block_size(),
+            data_buffer: vec![],
+            kind: PhantomData,
+        }
+    }
+
+    pub fn control(&mut self, value: u8) -> &mut Self {
+        self.control = value;
+        self
+    }
+
+    // group_number must be less than 0x20
+    pub fn group_number(&mut self, value: u8) -> &mut Self {
+        self.group_number = value;
+        self
+    }
+
+    // write_protect must be less than 0x08
+    pub fn write_protect(&mut self, value: u8) -> &mut Self {
+        self.write_protect = value;
+        self
+    }
+
+    pub fn disable_page_out(&mut self, value: bool) -> &mut Self {
+        self.disable_page_out = value;
+        self
+    }
+
+    pub fn force_unit_access(&mut self, value: bool) -> &mut Self {
+        self.force_unit_access = value;
+        self
+    }
+
+    pub fn logical_block_address(&mut self, value: u32) -> &mut Self {
+        self.logical_block_address = value;
+        self
+    }
+
+    pub fn logical_block_size(&mut self, value: u32) -> &mut Self {
+        self.logical_block_size = value;
+        self
+    }
+
+    pub fn parameter(&mut self, value: &[u8]) -> &mut Self {
+        self.data_buffer.clear();
+        self.data_buffer.extend_from_slice(value);
+        self
+    }
+
+    pub fn parameter_vec(&mut self, value: Vec<u8>) -> &mut Self {
+        self.data_buffer = value;
+        self
+    }
+
+    fn error_check(&self) -> crate::Result<()> {
+        bitfield_bound_check!(self.group_number, 5, "group number")?;
+        bitfield_bound_check!(self.write_protect, 3, "write protect")?;
+
+        if !self
+            .data_buffer
+            .len()
+            .is_multiple_of(self.logical_block_size as usize)
+        {
+            return Err(crate::Error::BadArgument(format!(
+                "parameter length should be a multiple of logical block size, which is {}.",
+                self.logical_block_size
+            )));
+        }
+
+        if (self.data_buffer.len() / self.logical_block_size as usize) > u16::MAX as usize {
+            return Err(crate::Error::ArgumentOutOfBounds(format!(
+                "parameter length is out of bounds. The maximum possible value is {}, but {} was provided.",
+                u16::MAX as u128 * self.logical_block_size as u128,
+                self.data_buffer.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn issue(&mut self) -> crate::Result<()> {
+        self.error_check()?;
+
+        let command_buffer = CommandBuffer::new()
+            .with_operation_code(K::OPERATION_CODE)
+            .with_write_protect(self.write_protect)
+            .with_disable_page_out(self.disable_page_out.into())
+            .with_force_unit_access(self.force_unit_access.into())
+            .with_flags(if self.flag { K::FLAG_MASK } else { 0 })
+            .with_logical_block_address(self.logical_block_address)
+            .with_group_number(self.group_number)
+            .with_transfer_length(
+                (self.data_buffer.len() / self.logical_block_size as usize) as u16,
+            )
+            .with_control(self.control);
+
+        self.interface.issue(&ThisCommand {
+            command_buffer,
+            data_buffer: self.data_buffer.clone().into(),
+        })
+    }
+}
+
+#[bitfield]
+#[derive(Clone, Copy, Debug)]
+struct CommandBuffer {
+    operation_code: B8,
+    write_protect: B3,
+    disable_page_out: B1,
+    force_unit_access: B1,
+    // the command specific flag, an obsolete bit and a reserved one
+    flags: B3,
+    logical_block_address: B32,
+    reserved_1: B3,
+    group_number: B5,
+    transfer_length: B16,
+    control: B8,
+}
+
+struct ThisCommand {
+    command_buffer: CommandBuffer,
+    data_buffer: VecBufferWrapper,
+}
+
+impl Command for ThisCommand {
+    type CommandBuffer = CommandBuffer;
+
+    type DataBuffer = AnyType;
+
+    type DataBufferWrapper = VecBufferWrapper;
+
+    type ReturnType = crate::Result<()>;
+
+    fn direction(&self) -> DataDirection {
+        DataDirection::ToDevice
+    }
+
+    fn command(&self) -> Self::CommandBuffer {
+        self.command_buffer
+    }
+
+    fn data(&self) -> Self::DataBufferWrapper {
+        self.data_buffer.clone()
+    }
+
+    fn data_size(&self) -> u32 {
+        self.data_buffer.len() as u32
+    }
+
+    fn data_length(&self, data: &Self::DataBufferWrapper) -> Option<usize> {
+        Some(data.len())
+    }
+
+    fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+        result.check_ioctl_error()?;
+        result.check_common_error()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    const COMMAND_LENGTH: usize = 10;
+
+    #[test]
+    fn layout_test() {
+        assert_eq!(
+            size_of::<CommandBuffer>(),
+            COMMAND_LENGTH,
+            concat!("Size of: ", stringify!(CommandBuffer))
+        );
+    }
+
+    #[test]
+    fn parameter_length_test() {
+        let scsi = Scsi::test_instance();
+
+        let mut command = scsi.xdwrite();
+        command.logical_block_size(512).parameter(&[0; 1000]);
+        assert!(command.error_check().is_err(), "partial block");
+
+        let mut command = scsi.xdwrite();
+        command.logical_block_size(1).parameter(&[0; 0x10000]);
+        assert!(command.error_check().is_err(), "too many blocks");
+
+        let mut command = scsi.xdwrite();
+        command.logical_block_size(512).parameter(&[0; 4096]);
+        assert!(command.error_check().is_ok(), "whole blocks");
+    }
+}
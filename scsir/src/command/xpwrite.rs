@@ -0,0 +1,69 @@
+use crate::{
+    command::xor_write::{XorWriteCommand, XorWriteKind},
+    Scsi,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct XpWrite;
+
+pub type XpWriteCommand<'a> = XorWriteCommand<'a, XpWrite>;
+
+impl XorWriteKind for XpWrite {
+    const OPERATION_CODE: u8 = 0x51;
+    const FLAG_MASK: u8 = 0b001;
+}
+
+impl XpWriteCommand<'_> {
+    // when set, the protection information is XORed along with the data
+    pub fn xor_protection_information(&mut self, value: bool) -> &mut Self {
+        self.flag = value;
+        self
+    }
+}
+
+impl Scsi {
+    pub fn xpwrite(&self) -> XpWriteCommand<'_> {
+        XpWriteCommand::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cdb_test() {
+        let scsi = Scsi::test_instance();
+
+        scsi.xpwrite()
+            .write_protect(0b101)
+            .disable_page_out(true)
+            .force_unit_access(true)
+            .xor_protection_information(true)
+            .logical_block_address(0x12345678)
+            .group_number(0x11)
+            .logical_block_size(512)
+            .parameter(&[0xA5; 1024])
+            .issue()
+            .unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(
+            issued[0].cdb,
+            [
+                0x51,
+                0b1011_1001,
+                0x12,
+                0x34,
+                0x56,
+                0x78,
+                0x11,
+                0x00,
+                0x02,
+                0x00
+            ],
+            "cdb"
+        );
+        assert_eq!(issued[0].data_out, [0xA5; 1024], "data out");
+    }
+}
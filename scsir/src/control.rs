@@ -0,0 +1,95 @@
+use crate::command::bitfield_bound_check;
+
+/// CONTROL byte carried as the last byte of a CDB.
+///
+/// Bit 2 is NACA, bits 0 and 1 are the obsolete LINK and FLAG bits and bits 6 and 7
+/// are vendor specific. A NACA bit set by accident makes the device hold an ACA
+/// condition after CHECK CONDITION, which most initiators never clear.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Control(u8);
+
+impl Control {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn naca(&mut self, value: bool) -> &mut Self {
+        self.set_bit(NACA_BIT, value);
+        self
+    }
+
+    pub fn link(&mut self, value: bool) -> &mut Self {
+        self.set_bit(LINK_BIT, value);
+        self
+    }
+
+    pub fn flag(&mut self, value: bool) -> &mut Self {
+        self.set_bit(FLAG_BIT, value);
+        self
+    }
+
+    // vendor_specific must be less than 0x04
+    pub fn vendor_specific(&mut self, value: u8) -> crate::Result<&mut Self> {
+        bitfield_bound_check!(value, 2, "vendor specific")?;
+        self.0 = (self.0 & !VENDOR_SPECIFIC_MASK) | (value << VENDOR_SPECIFIC_SHIFT);
+        Ok(self)
+    }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    fn set_bit(&mut self, bit: u8, value: bool) {
+        if value {
+            self.0 |= bit;
+        } else {
+            self.0 &= !bit;
+        }
+    }
+}
+
+impl From<Control> for u8 {
+    fn from(value: Control) -> Self {
+        value.0
+    }
+}
+
+impl From<u8> for Control {
+    fn from(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+const LINK_BIT: u8 = 0x01;
+const FLAG_BIT: u8 = 0x02;
+const NACA_BIT: u8 = 0x04;
+const VENDOR_SPECIFIC_SHIFT: u8 = 6;
+const VENDOR_SPECIFIC_MASK: u8 = 0b1100_0000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_test() {
+        assert_eq!(Control::new().value(), 0x00, "default");
+        assert_eq!(Control::new().naca(true).value(), 0x04, "naca");
+        assert_eq!(Control::new().link(true).value(), 0x01, "link");
+        assert_eq!(Control::new().flag(true).value(), 0x02, "flag");
+        assert_eq!(
+            Control::new().vendor_specific(0b10).unwrap().value(),
+            0x80,
+            "vendor specific"
+        );
+        assert!(
+            Control::new().vendor_specific(0b100).is_err(),
+            "vendor specific bound"
+        );
+
+        let mut control = Control::new();
+        control.naca(true).link(true).vendor_specific(0b11).unwrap();
+        assert_eq!(u8::from(control), 0xC5, "combined");
+        control.naca(false);
+        assert_eq!(u8::from(control), 0xC1, "naca cleared");
+    }
+}
@@ -12,7 +12,11 @@ pub enum DataDirection {
     /// treated like FromDevice with the
     /// additional property than during indirect
     /// IO the user buffer is copied into the
-    /// kernel buffers before the transfer
+    /// kernel buffers before the transfer,
+    /// so a single data buffer is sent and then
+    /// overwritten in place by the response.
+    /// No built-in command uses it, it's meant for
+    /// custom Command implementations
     ToFromDevice = -4,
     Unknown = -5,
 }
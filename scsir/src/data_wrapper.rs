@@ -89,6 +89,22 @@ impl<Body, Element> FlexibleStruct<Body, Element> {
         }
     }
 
+    // drops the elements from len onwards, the capacity is kept
+    pub fn truncate(&mut self, len: usize) {
+        if std::mem::needs_drop::<Element>() {
+            while self.length > len {
+                self.pop();
+            }
+        } else {
+            self.length = usize::min(self.length, len);
+        }
+    }
+
+    // makes room for at least additional more elements, so pushing them doesn't reallocate
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_grow_to(self.length.saturating_add(additional));
+    }
+
     pub unsafe fn body_as_ref(&self) -> &Body {
         &*ptr::addr_of!((*self.ptr).body)
     }
@@ -150,8 +166,9 @@ impl<Body, Element> FlexibleStruct<Body, Element> {
             return;
         }
 
+        let old_size = mem::size_of::<Body>() + mem::size_of::<Element>() * self.capacity;
         let new_size = mem::size_of::<Body>() + mem::size_of::<Element>() * new_capacity;
-        let layout = Layout::from_size_align(new_size, mem::align_of::<Body>()).unwrap();
+        let layout = Layout::from_size_align(old_size, mem::align_of::<Body>()).unwrap();
         let memory = unsafe {
             std::alloc::realloc(self.ptr.cast(), layout, new_size) as *mut Raw<Body, Element>
         };
@@ -230,6 +247,12 @@ impl<B, E> BorrowMut<AnyType> for FlexibleStruct<B, E> {
     }
 }
 
+impl<B, E> AsRef<[u8]> for FlexibleStruct<B, E> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
 impl<B: Clone, E: Clone> Clone for FlexibleStruct<B, E> {
     fn clone(&self) -> Self {
         let mut new_struct = Self::with_body_capacity(self.get_body(), self.length);
@@ -344,6 +367,14 @@ impl VecBufferWrapper {
 
         temp
     }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
 }
 
 impl Borrow<AnyType> for VecBufferWrapper {
@@ -372,6 +403,12 @@ impl DerefMut for VecBufferWrapper {
     }
 }
 
+impl AsRef<[u8]> for VecBufferWrapper {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl From<Vec<u8>> for VecBufferWrapper {
     fn from(value: Vec<u8>) -> Self {
         Self(value)
@@ -480,4 +517,56 @@ mod tests {
 
         assert_eq!(body_marker, true, "body marker dropped");
     }
+
+    #[test]
+    fn reserve_test() {
+        let mut tester = FlexibleStruct::<[u8; 4], u32>::new();
+        tester.reserve(10);
+        assert!(tester.capacity() >= 10, "capacity after reserve");
+
+        let capacity = tester.capacity();
+        let pointer = tester.as_bytes().as_ptr();
+        for n in 0..10 {
+            tester.push(n);
+        }
+
+        assert_eq!(tester.capacity(), capacity, "capacity after push");
+        assert_eq!(tester.as_bytes().as_ptr(), pointer, "no reallocation");
+        assert_eq!(tester.get_element(9), Some(9), "last element");
+
+        tester.reserve(0);
+        assert_eq!(tester.capacity(), capacity, "reserving nothing");
+    }
+
+    #[test]
+    fn truncate_test() {
+        let mut markers = [false; 3];
+        let mut tester = FlexibleStruct::<[u8; 4], Dropper>::new();
+        for marker in markers.iter_mut() {
+            tester.push(Dropper { marker });
+        }
+
+        tester.truncate(5);
+        assert_eq!(tester.length(), 3, "truncating beyond the length");
+
+        tester.truncate(1);
+        assert_eq!(tester.length(), 1, "length after truncate");
+        assert_eq!(markers, [false, true, true], "truncated elements dropped");
+        drop(tester);
+        assert_eq!(markers, [true; 3], "every element dropped");
+
+        let mut tester = FlexibleStruct::<[u8; 4], u8>::new();
+        tester.push(1);
+        tester.push(2);
+        tester.truncate(1);
+        assert_eq!(tester.length(), 1, "length after truncate without drop");
+        assert_eq!(tester.get_element(1), None, "truncated element");
+    }
+
+    #[test]
+    fn vec_buffer_wrapper_test() {
+        let wrapper = VecBufferWrapper::from(vec![1, 2, 3]);
+        assert_eq!(wrapper.as_slice(), [1, 2, 3], "as slice");
+        assert_eq!(wrapper.into_inner(), vec![1, 2, 3], "into inner");
+    }
 }
@@ -2,10 +2,12 @@ use std::{any::Any, io, path::PathBuf};
 
 use thiserror::Error;
 
+use crate::command::sense::{SenseData, SenseKey};
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
-pub enum Error<T = Box<dyn Any>> {
+pub enum Error<T = Box<dyn Any + Send + Sync>> {
     #[error("{0} is not a block device.")]
     NotBlockDevice(PathBuf),
     #[error("{0} is not an SCSI Generic device, or old SCSI Generic driver.")]
@@ -14,10 +16,92 @@ pub enum Error<T = Box<dyn Any>> {
     ArgumentOutOfBounds(String),
     #[error("Bad argument: {0}")]
     BadArgument(String),
+    #[error("Device is busy.")]
+    DeviceBusy,
+    #[error("Task set is full.")]
+    TaskSetFull,
+    #[error("Command was cancelled.")]
+    Cancelled,
+    #[error("Allocation length is too small, {required} bytes are required.")]
+    AllocationTooSmall { required: u32 },
     #[error("Check condition: {0:?}")]
     CheckCondition(T),
+    // CHECK CONDITION reporting the failure of an earlier command, e.g. one issued with IMMED
+    #[error("Deferred error: {0:?}")]
+    DeferredError(T),
+    #[error("Reassignment stopped at lba {first_not_reassigned:?}: {sense:?}")]
+    ReassignStopped {
+        // None when the device does not report it
+        first_not_reassigned: Option<u64>,
+        sense: T,
+    },
+    #[error("{0} is not supported on this platform.")]
+    Unsupported(String),
+    // a feature the device, or the translation layer in front of it, does not provide
+    #[error("{0} is not supported by the device.")]
+    DeviceUnsupported(String),
+    // refused by the type guards before reaching the device, see Scsi::enable_type_guards
+    #[error("Command rejected: {0}.")]
+    Rejected(String),
+    #[error("Protocol violation: {0}")]
+    ProtocolViolation(String),
     #[error("{0:?}")]
     Other(String),
     #[error("{0}")]
     IO(#[from] io::Error),
 }
+
+impl<T> Error<T> {
+    pub fn raw_os_error(&self) -> Option<i32> {
+        match self {
+            Error::IO(e) => e.raw_os_error(),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    // the sense data of a CHECK CONDITION, see crate::command::sense
+    pub fn sense_data(&self) -> Option<&SenseData> {
+        match self {
+            Error::CheckCondition(sense)
+            | Error::DeferredError(sense)
+            | Error::ReassignStopped { sense, .. } => sense.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    // whether the sense data reports ILLEGAL REQUEST, e.g. for a command or page the device
+    // does not implement
+    pub fn is_illegal_request(&self) -> bool {
+        matches!(
+            self.sense_data().and_then(|sense| sense.sense_key()),
+            Some(SenseKey::IllegalRequest)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    const ENODEV: i32 = 19;
+
+    #[test]
+    fn io_error_test() {
+        let error: Error = io::Error::from_raw_os_error(ENODEV).into();
+
+        assert_eq!(error.raw_os_error(), Some(ENODEV), "raw os error");
+
+        let source = error
+            .source()
+            .and_then(|e| e.downcast_ref::<io::Error>())
+            .expect("source should be the io error");
+        assert_eq!(source.raw_os_error(), Some(ENODEV), "source raw os error");
+
+        let error: Error = Error::Other("other".to_owned());
+        assert_eq!(error.raw_os_error(), None, "non io error");
+        assert!(error.source().is_none(), "non io source");
+    }
+}
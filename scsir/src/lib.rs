@@ -1,19 +1,38 @@
 // modular_bitfield_msb generates fields that trip unused_parens; keep this crate clean.
 #![allow(unused_parens)]
 
+mod batch_policy;
+mod block_writer;
+mod byte_reader;
+mod cancel_token;
 pub mod command;
+mod control;
 mod data_direction;
 mod data_wrapper;
 mod error;
 mod file_descriptor;
+#[cfg(test)]
+mod mock;
 mod os;
+mod peripheral_device_type;
+mod range_lock;
+mod reset_level;
 mod result_data;
 mod scsi;
+mod scsi_options;
 
+pub use batch_policy::{BatchPolicy, BatchStep};
+pub use block_writer::ScsiBlockWriter;
+pub use cancel_token::CancelToken;
 pub use command::shortcut;
 pub use command::Command;
+pub use command::SizedDataIn;
+pub use control::Control;
 pub use data_direction::DataDirection;
 pub use error::{Error, Result};
-pub use result_data::ResultData;
+pub use peripheral_device_type::PeripheralDeviceType;
+pub use reset_level::ResetLevel;
+pub use result_data::{CommandOutcome, ResultData, Status};
 
 pub use scsi::Scsi;
+pub use scsi_options::ScsiOptions;
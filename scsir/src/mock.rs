@@ -0,0 +1,169 @@
+#![allow(dead_code)]
+
+use std::{borrow::BorrowMut, collections::VecDeque, io, slice, sync::Mutex};
+
+use crate::{
+    command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
+    result_data::{transferred_length, ResultData, Status},
+    Command, DataDirection, ResetLevel,
+};
+
+/// Stands in for the ioctl in unit tests: replays queued responses and records what was issued.
+#[derive(Debug, Default)]
+pub(crate) struct MockTransport {
+    responses: Mutex<VecDeque<MockResponse>>,
+    issued: Mutex<Vec<IssuedCommand>>,
+    // what sg_reserved_size returns, None fails like a file that isn't an sg device
+    reserved_size: Mutex<Option<u32>>,
+    // errno the next resets fail with in turn, resets succeed once it is empty
+    reset_errors: Mutex<VecDeque<i32>>,
+    resets: Mutex<Vec<ResetLevel>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MockResponse {
+    pub data: Vec<u8>,
+    pub status: u8,
+    pub sense: Vec<u8>,
+    // Some to report this residual count as SG_IO would, instead of deriving it from data
+    pub residual_count: Option<i32>,
+    // Some to report these driver status bits, by default SENSE is set whenever sense data
+    // is returned, as SG_IO does
+    pub driver_status: Option<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct IssuedCommand {
+    pub cdb: Vec<u8>,
+    pub data_out: Vec<u8>,
+    pub pack_id: i32,
+}
+
+impl MockTransport {
+    pub fn push_response(&self, response: MockResponse) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    pub fn push_data(&self, data: &[u8]) {
+        self.push_response(MockResponse {
+            data: data.to_vec(),
+            ..Default::default()
+        });
+    }
+
+    pub fn push_sense(&self, sense: &[u8]) {
+        self.push_response(MockResponse {
+            status: 0x02,
+            sense: sense.to_vec(),
+            ..Default::default()
+        });
+    }
+
+    pub fn set_reserved_size(&self, size: u32) {
+        *self.reserved_size.lock().unwrap() = Some(size);
+    }
+
+    pub fn reserved_size(&self) -> Option<u32> {
+        *self.reserved_size.lock().unwrap()
+    }
+
+    pub fn push_reset_error(&self, errno: i32) {
+        self.reset_errors.lock().unwrap().push_back(errno);
+    }
+
+    pub fn reset(&self, level: ResetLevel) -> io::Result<()> {
+        self.resets.lock().unwrap().push(level);
+
+        match self.reset_errors.lock().unwrap().pop_front() {
+            Some(errno) => Err(io::Error::from_raw_os_error(errno)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn resets(&self) -> Vec<ResetLevel> {
+        self.resets.lock().unwrap().clone()
+    }
+
+    pub fn issued(&self) -> Vec<IssuedCommand> {
+        self.issued.lock().unwrap().clone()
+    }
+
+    pub fn issue<T: Command>(&self, command: &T, pack_id: i32) -> T::ReturnType {
+        let mut data_buffer = command.data();
+        crate::command::debug_check_data_size(command, &data_buffer);
+        let raw_pointer_to_data_buffer = &mut data_buffer as *mut _;
+
+        let cdb = command.cdb_bytes();
+
+        let data_size = command.data_size() as usize;
+        let data = if data_size == 0 {
+            &mut [][..]
+        } else {
+            unsafe {
+                slice::from_raw_parts_mut(data_buffer.borrow_mut() as *mut _ as *mut u8, data_size)
+            }
+        };
+
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_default();
+
+        let (data_out, transfered_data_length) = match command.direction() {
+            DataDirection::ToDevice => (data.to_vec(), data_size),
+            DataDirection::FromDevice => {
+                let length = usize::min(response.data.len(), data_size);
+                data[..length].copy_from_slice(&response.data[..length]);
+                (vec![], length)
+            }
+            DataDirection::ToFromDevice => {
+                let data_out = data.to_vec();
+                let length = usize::min(response.data.len(), data_size);
+                data[..length].copy_from_slice(&response.data[..length]);
+                (data_out, length)
+            }
+            DataDirection::None | DataDirection::Unknown => (vec![], 0),
+        };
+
+        let (transfered_data_length, protocol_violation) = match response.residual_count {
+            Some(residual_count) => transferred_length(data_size as u32, residual_count),
+            None => (transfered_data_length, None),
+        };
+
+        self.issued.lock().unwrap().push(IssuedCommand {
+            cdb,
+            data_out,
+            pack_id,
+        });
+
+        let mut sense_buffer = [0u8; MAX_SENSE_BUFFER_LENGTH];
+        let sense_length = usize::min(response.sense.len(), MAX_SENSE_BUFFER_LENGTH);
+        sense_buffer[..sense_length].copy_from_slice(&response.sense[..sense_length]);
+        let sense_data = SenseData::parse(&sense_buffer, sense_length);
+
+        let result_data = ResultData {
+            ioctl_result: 0,
+            transfered_data_length,
+            data: unsafe { &mut *raw_pointer_to_data_buffer },
+            transfered_sense_length: sense_length,
+            sense_buffer: &sense_data,
+            status: Status::from(response.status),
+            rejection: None,
+            protocol_violation,
+            #[cfg(target_os = "linux")]
+            host_status: crate::os::linux::HostStatus::Ok,
+            #[cfg(target_os = "linux")]
+            driver_status: crate::os::linux::DriverStatus::from_bits_retain(
+                response.driver_status.unwrap_or(if sense_length != 0 {
+                    crate::os::linux::DriverStatus::SENSE.bits()
+                } else {
+                    0
+                }),
+            ),
+        };
+
+        command.process_result(result_data)
+    }
+}
@@ -23,3 +23,14 @@ bitflags! {
         const SUGGEST_SENSE = 0x80;
     }
 }
+
+impl DriverStatus {
+    // the low nibble holds one status and the high nibble suggestions. SENSE only says sense
+    // data was returned, which SG_IO sets on every CHECK CONDITION, and suggestions alone
+    // are no failure either
+    pub(crate) fn is_error(&self) -> bool {
+        !matches!(self.bits() & DRIVER_STATUS_MASK, 0x00 | 0x08)
+    }
+}
+
+const DRIVER_STATUS_MASK: c_ushort = 0x0F;
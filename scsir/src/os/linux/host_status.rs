@@ -1,6 +1,6 @@
 use std::ffi::c_ushort;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum HostStatus {
     /// NO error
     Ok,
@@ -0,0 +1,134 @@
+use std::fmt::Display;
+
+/// PERIPHERAL DEVICE TYPE of the standard INQUIRY data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PeripheralDeviceType {
+    DirectAccessBlock,
+    SequentialAccess,
+    Printer,
+    Processor,
+    WriteOnce,
+    CdDvd,
+    OpticalMemory,
+    MediumChanger,
+    StorageArrayController,
+    EnclosureServices,
+    SimplifiedDirectAccess,
+    OpticalCardReaderWriter,
+    ObjectBasedStorage,
+    AutomationDriveInterface,
+    HostManagedZonedBlock,
+    WellKnownLogicalUnit,
+    Unknown,
+    Other(u8),
+}
+
+impl PeripheralDeviceType {
+    /// Whether operation_code only exists for other device types, e.g. MOVE MEDIUM on a disk.
+    ///
+    /// Only opcodes that no standard reuses for this type are listed, so a false result does
+    /// not mean the device supports the command.
+    pub fn rejects_operation_code(&self, operation_code: u8) -> bool {
+        // MOVE MEDIUM, EXCHANGE MEDIUM, READ ELEMENT STATUS
+        const CHANGER: &[u8] = &[0xA5, 0xA6, 0xB8];
+        // WRITE FILEMARKS(6), SPACE(6), ERASE(6)
+        const TAPE: &[u8] = &[0x10, 0x11, 0x19];
+        // READ(10), WRITE(10), READ(12), WRITE(12), READ(16), WRITE(16)
+        const BLOCK_DATA: &[u8] = &[0x28, 0x2A, 0xA8, 0xAA, 0x88, 0x8A];
+
+        let rejected: &[&[u8]] = match self {
+            PeripheralDeviceType::DirectAccessBlock
+            | PeripheralDeviceType::SimplifiedDirectAccess
+            | PeripheralDeviceType::HostManagedZonedBlock => &[CHANGER, TAPE],
+            PeripheralDeviceType::SequentialAccess => &[CHANGER],
+            PeripheralDeviceType::MediumChanger => &[TAPE, BLOCK_DATA],
+            _ => &[],
+        };
+
+        rejected.iter().any(|list| list.contains(&operation_code))
+    }
+}
+
+impl From<u8> for PeripheralDeviceType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => PeripheralDeviceType::DirectAccessBlock,
+            0x01 => PeripheralDeviceType::SequentialAccess,
+            0x02 => PeripheralDeviceType::Printer,
+            0x03 => PeripheralDeviceType::Processor,
+            0x04 => PeripheralDeviceType::WriteOnce,
+            0x05 => PeripheralDeviceType::CdDvd,
+            0x07 => PeripheralDeviceType::OpticalMemory,
+            0x08 => PeripheralDeviceType::MediumChanger,
+            0x0C => PeripheralDeviceType::StorageArrayController,
+            0x0D => PeripheralDeviceType::EnclosureServices,
+            0x0E => PeripheralDeviceType::SimplifiedDirectAccess,
+            0x0F => PeripheralDeviceType::OpticalCardReaderWriter,
+            0x11 => PeripheralDeviceType::ObjectBasedStorage,
+            0x12 => PeripheralDeviceType::AutomationDriveInterface,
+            0x14 => PeripheralDeviceType::HostManagedZonedBlock,
+            0x1E => PeripheralDeviceType::WellKnownLogicalUnit,
+            0x1F => PeripheralDeviceType::Unknown,
+            other => PeripheralDeviceType::Other(other),
+        }
+    }
+}
+
+// names as printed by sg_inq
+impl Display for PeripheralDeviceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeripheralDeviceType::DirectAccessBlock => write!(f, "disk"),
+            PeripheralDeviceType::SequentialAccess => write!(f, "tape"),
+            PeripheralDeviceType::Printer => write!(f, "printer"),
+            PeripheralDeviceType::Processor => write!(f, "processor"),
+            PeripheralDeviceType::WriteOnce => write!(f, "write once optical disk"),
+            PeripheralDeviceType::CdDvd => write!(f, "cd/dvd"),
+            PeripheralDeviceType::OpticalMemory => write!(f, "optical memory device"),
+            PeripheralDeviceType::MediumChanger => write!(f, "medium changer"),
+            PeripheralDeviceType::StorageArrayController => write!(f, "storage array controller"),
+            PeripheralDeviceType::EnclosureServices => write!(f, "enclosure services device"),
+            PeripheralDeviceType::SimplifiedDirectAccess => {
+                write!(f, "simplified direct access device")
+            }
+            PeripheralDeviceType::OpticalCardReaderWriter => {
+                write!(f, "optical card reader/writer device")
+            }
+            PeripheralDeviceType::ObjectBasedStorage => write!(f, "object based storage"),
+            PeripheralDeviceType::AutomationDriveInterface => {
+                write!(f, "automation/drive interface")
+            }
+            PeripheralDeviceType::HostManagedZonedBlock => write!(f, "host managed zoned block"),
+            PeripheralDeviceType::WellKnownLogicalUnit => write!(f, "well known logical unit"),
+            PeripheralDeviceType::Unknown => write!(f, "unknown or no device type"),
+            PeripheralDeviceType::Other(value) => write!(f, "reserved [0x{:02x}]", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_operation_code_test() {
+        let disk = PeripheralDeviceType::from(0x00);
+        assert!(disk.rejects_operation_code(0xA5), "move medium on disk");
+        assert!(!disk.rejects_operation_code(0x28), "read(10) on disk");
+
+        let changer = PeripheralDeviceType::from(0x08);
+        assert!(changer.rejects_operation_code(0x2A), "write(10) on changer");
+        assert!(
+            !changer.rejects_operation_code(0xA5),
+            "move medium on changer"
+        );
+
+        let other = PeripheralDeviceType::from(0x13);
+        assert_eq!(other, PeripheralDeviceType::Other(0x13), "other");
+        assert!(
+            !other.rejects_operation_code(0xA5),
+            "unknown types allow all"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Condvar, Mutex,
+};
+
+/// Client side lock over lba ranges, so overlapping writes issued from different threads
+/// reach the device one after another. Does nothing until enabled.
+#[derive(Debug, Default)]
+pub(crate) struct RangeLock {
+    enabled: AtomicBool,
+    // (first lba, number of blocks) of every write in flight
+    active: Mutex<Vec<(u64, u64)>>,
+    released: Condvar,
+}
+
+pub(crate) struct RangeGuard<'a> {
+    lock: &'a RangeLock,
+    range: (u64, u64),
+}
+
+impl RangeLock {
+    pub(crate) fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    // blocks while range overlaps a range held by another guard
+    pub(crate) fn acquire(&self, range: Option<(u64, u64)>) -> Option<RangeGuard<'_>> {
+        let range = range.filter(|_| self.is_enabled())?;
+
+        let mut active = self.active.lock().unwrap();
+        while active.iter().any(|held| overlaps(*held, range)) {
+            active = self.released.wait(active).unwrap();
+        }
+        active.push(range);
+
+        Some(RangeGuard { lock: self, range })
+    }
+}
+
+impl Drop for RangeGuard<'_> {
+    fn drop(&mut self) {
+        let mut active = self.lock.active.lock().unwrap();
+        if let Some(index) = active.iter().position(|held| *held == self.range) {
+            active.swap_remove(index);
+        }
+        self.lock.released.notify_all();
+    }
+}
+
+fn overlaps((start_a, length_a): (u64, u64), (start_b, length_b): (u64, u64)) -> bool {
+    length_a != 0
+        && length_b != 0
+        && start_a < start_b.saturating_add(length_b)
+        && start_b < start_a.saturating_add(length_a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn overlaps_test() {
+        assert!(overlaps((0, 8), (7, 1)), "last block");
+        assert!(!overlaps((0, 8), (8, 8)), "adjacent");
+        assert!(!overlaps((0, 8), (4, 0)), "empty");
+        assert!(overlaps((u64::MAX - 8, 16), (u64::MAX - 4, 1)), "saturated");
+    }
+
+    #[test]
+    fn disabled_test() {
+        let lock = RangeLock::default();
+        let _guard = lock.acquire(Some((0, 8)));
+        assert!(lock.acquire(Some((0, 8))).is_none(), "nothing is held");
+    }
+
+    #[test]
+    fn overlapping_range_test() {
+        let lock = RangeLock::default();
+        lock.enable();
+        let acquired = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            let guard = lock.acquire(Some((0, 8))).unwrap();
+            let waiter = scope.spawn(|| {
+                let _guard = lock.acquire(Some((4, 4))).unwrap();
+                acquired.store(true, Ordering::Relaxed);
+            });
+
+            let _other = lock.acquire(Some((8, 8))).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            assert!(!acquired.load(Ordering::Relaxed), "blocked by overlap");
+
+            drop(guard);
+            waiter.join().unwrap();
+        });
+
+        assert!(acquired.load(Ordering::Relaxed), "acquired after release");
+    }
+}
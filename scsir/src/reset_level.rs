@@ -0,0 +1,60 @@
+use std::ffi::c_int;
+
+/// Levels for Scsi::reset, in the order the kernel escalates through them.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetLevel {
+    /// logical unit reset
+    Device = 1,
+    /// target reset, affects every logical unit of the target
+    Target = 4,
+    /// bus reset, affects every target on the bus
+    Bus = 2,
+    /// host adapter reset, affects every device on the host
+    Host = 3,
+}
+
+impl ResetLevel {
+    pub(crate) fn escalation(self) -> &'static [ResetLevel] {
+        const ORDER: [ResetLevel; 4] = [
+            ResetLevel::Device,
+            ResetLevel::Target,
+            ResetLevel::Bus,
+            ResetLevel::Host,
+        ];
+
+        let start = ORDER.iter().position(|l| *l == self).unwrap_or(0);
+        &ORDER[start..]
+    }
+}
+
+impl From<ResetLevel> for c_int {
+    fn from(value: ResetLevel) -> Self {
+        value as i32 as c_int
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escalation_test() {
+        assert_eq!(
+            ResetLevel::Device.escalation(),
+            [
+                ResetLevel::Device,
+                ResetLevel::Target,
+                ResetLevel::Bus,
+                ResetLevel::Host
+            ],
+            "device"
+        );
+        assert_eq!(
+            ResetLevel::Bus.escalation(),
+            [ResetLevel::Bus, ResetLevel::Host],
+            "bus"
+        );
+        assert_eq!(c_int::from(ResetLevel::Target), 4, "target flag");
+    }
+}
@@ -1,6 +1,10 @@
 use std::io;
 
-use crate::{command::sense::SenseData, error};
+use crate::{
+    command::sense::{SenseData, SenseKey},
+    data_wrapper::VecBufferWrapper,
+    error,
+};
 
 #[cfg(target_os = "linux")]
 use crate::os::linux::{DriverStatus, HostStatus};
@@ -13,6 +17,10 @@ pub struct ResultData<'a, D> {
     pub(crate) transfered_sense_length: usize,
     pub(crate) sense_buffer: &'a SenseData,
     pub(crate) status: Status,
+    // Some when the command was refused before reaching the device
+    pub(crate) rejection: Option<String>,
+    // Some when the transport reported an impossible transfer, see transferred_length
+    pub(crate) protocol_violation: Option<String>,
     #[cfg(target_os = "linux")]
     pub(crate) host_status: HostStatus,
     #[cfg(target_os = "linux")]
@@ -21,6 +29,7 @@ pub struct ResultData<'a, D> {
 
 #[allow(dead_code)]
 #[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
 pub enum Status {
     Good,
     CheckCondition,
@@ -33,17 +42,84 @@ pub enum Status {
     Unknown(u8),
 }
 
+/// How a command ended, from the transport status, the SCSI status and the sense key.
+#[derive(Clone, Debug)]
+pub enum CommandOutcome {
+    Good,
+    /// CHECK CONDITION with a RECOVERED ERROR sense key, the command succeeded
+    Recovered(SenseData),
+    CheckCondition(SenseData),
+    /// CHECK CONDITION with a NOT READY sense key
+    NotReady(SenseData),
+    Busy,
+    TaskSetFull,
+    /// the host status, or the driver status bits when the host status is ok
+    TransportError(i32),
+    /// any other SCSI status, such as RESERVATION CONFLICT
+    Other(Status),
+}
+
 impl<D> ResultData<'_, D> {
+    pub fn classify(&self) -> CommandOutcome {
+        #[cfg(target_os = "linux")]
+        {
+            if !matches!(self.host_status, HostStatus::Ok) {
+                return CommandOutcome::TransportError(self.host_status as i32);
+            }
+
+            if self.driver_status.is_error() {
+                return CommandOutcome::TransportError(self.driver_status.bits() as i32);
+            }
+        }
+
+        match self.status {
+            Status::Good | Status::ConditionMet => CommandOutcome::Good,
+            Status::CheckCondition => {
+                let sense = self.sense_buffer.clone();
+                match sense.sense_key() {
+                    Some(SenseKey::RecoveredError) => CommandOutcome::Recovered(sense),
+                    Some(SenseKey::NotReady) => CommandOutcome::NotReady(sense),
+                    _ => CommandOutcome::CheckCondition(sense),
+                }
+            }
+            Status::Busy => CommandOutcome::Busy,
+            Status::TaskSetFull => CommandOutcome::TaskSetFull,
+            status => CommandOutcome::Other(status),
+        }
+    }
+
+    /// Ok when the command completed with GOOD status and no sense data. CHECK CONDITION
+    /// with sense data, including RECOVERED ERROR and NOT READY, is CheckCondition carrying
+    /// the parsed SenseData, or DeferredError when the sense reports an earlier command,
+    /// so callers can inspect it through Error::sense_data. BUSY and TASK SET FULL have
+    /// their own variants, anything else is Other describing the statuses.
     pub fn check_common_error(&self) -> crate::Result<()> {
+        match self.classify() {
+            CommandOutcome::Good if self.transfered_sense_length == 0 => return Ok(()),
+            CommandOutcome::Busy => return Err(crate::Error::DeviceBusy),
+            CommandOutcome::TaskSetFull => return Err(crate::Error::TaskSetFull),
+            CommandOutcome::Recovered(sense)
+            | CommandOutcome::CheckCondition(sense)
+            | CommandOutcome::NotReady(sense)
+                if self.transfered_sense_length != 0 =>
+            {
+                if sense.is_deferred() {
+                    return Err(crate::Error::DeferredError(Box::new(sense)));
+                }
+                return Err(crate::Error::CheckCondition(Box::new(sense)));
+            }
+            _ => {}
+        }
+
         let mut result = String::new();
 
         #[cfg(target_os = "linux")]
         {
-            if !matches!(self.host_status, crate::os::linux::HostStatus::Ok) {
+            if !matches!(self.host_status, HostStatus::Ok) {
                 result.push_str(&format!("host status: {:?}. ", self.host_status));
             }
 
-            if !self.driver_status.is_empty() {
+            if self.driver_status.is_error() {
                 result.push_str(&format!("driver status: {:?}. ", self.driver_status));
             }
         }
@@ -56,14 +132,18 @@ impl<D> ResultData<'_, D> {
             result.push_str(&format!("Sense data: {:02X?}", self.sense_buffer));
         }
 
-        if !result.is_empty() {
-            return Err(crate::Error::Other(result));
-        }
-
-        Ok(())
+        Err(crate::Error::Other(result))
     }
 
     pub fn check_ioctl_error(&self) -> crate::Result<()> {
+        if let Some(reason) = &self.rejection {
+            return Err(error::Error::Rejected(reason.clone()));
+        }
+
+        if let Some(reason) = &self.protocol_violation {
+            return Err(error::Error::ProtocolViolation(reason.clone()));
+        }
+
         match self.ioctl_result {
             0 => Ok(()),
             _ => Err(error::Error::IO(io::Error::last_os_error())),
@@ -89,6 +169,45 @@ impl<D> ResultData<'_, D> {
     pub fn sense_buffer(&self) -> &SenseData {
         self.sense_buffer
     }
+
+    /// the sense bytes as the device returned them, unparsed, see SenseData::raw_bytes
+    pub fn raw_sense(&self) -> &[u8] {
+        self.sense_buffer.raw_bytes()
+    }
+}
+
+impl<D: AsRef<[u8]>> ResultData<'_, D> {
+    /// the part of data actually transferred by the device, without the residual
+    pub fn transferred_data(&self) -> &[u8] {
+        let data = self.data.as_ref();
+        &data[..usize::min(self.transfered_data_length, data.len())]
+    }
+}
+
+impl ResultData<'_, VecBufferWrapper> {
+    /// moves the whole data buffer out of the result, residual included
+    pub(crate) fn into_data(self) -> Vec<u8> {
+        std::mem::take(self.data).into_inner()
+    }
+}
+
+// the bytes transferred out of data_length, given the residual reported by the driver.
+// a negative residual or one larger than data_length, e.g. a device sending data for a
+// command without any, counts as nothing transferred and is returned as the violation
+pub(crate) fn transferred_length(data_length: u32, residual_count: i32) -> (usize, Option<String>) {
+    match u32::try_from(residual_count)
+        .ok()
+        .and_then(|residual_count| data_length.checked_sub(residual_count))
+    {
+        Some(length) => (length as usize, None),
+        None => (
+            0,
+            Some(format!(
+                "residual count {} does not fit the data length {}.",
+                residual_count, data_length
+            )),
+        ),
+    }
 }
 
 impl From<u8> for Status {
@@ -106,3 +225,287 @@ impl From<u8> for Status {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::sense::MAX_SENSE_BUFFER_LENGTH;
+
+    fn sense(sense_key: u8) -> SenseData {
+        sense_with_response_code(0x70, sense_key)
+    }
+
+    fn sense_with_response_code(response_code: u8, sense_key: u8) -> SenseData {
+        let mut raw = [0; MAX_SENSE_BUFFER_LENGTH];
+        raw[0] = response_code;
+        raw[2] = sense_key;
+        raw[7] = 0x0A;
+        SenseData::parse(&raw, 18)
+    }
+
+    fn classify(status: u8, sense: &SenseData) -> CommandOutcome {
+        let mut data = ();
+        ResultData {
+            ioctl_result: 0,
+            transfered_data_length: 0,
+            data: &mut data,
+            transfered_sense_length: if matches!(sense, SenseData::None) {
+                0
+            } else {
+                18
+            },
+            sense_buffer: sense,
+            status: Status::from(status),
+            rejection: None,
+            protocol_violation: None,
+            #[cfg(target_os = "linux")]
+            host_status: HostStatus::Ok,
+            #[cfg(target_os = "linux")]
+            driver_status: DriverStatus::OK,
+        }
+        .classify()
+    }
+
+    #[test]
+    fn classify_test() {
+        assert!(
+            matches!(classify(0x00, &SenseData::None), CommandOutcome::Good),
+            "good"
+        );
+        assert!(
+            matches!(classify(0x04, &SenseData::None), CommandOutcome::Good),
+            "condition met"
+        );
+        assert!(
+            matches!(classify(0x02, &sense(0x01)), CommandOutcome::Recovered(_)),
+            "recovered error"
+        );
+        assert!(
+            matches!(classify(0x02, &sense(0x02)), CommandOutcome::NotReady(_)),
+            "not ready"
+        );
+        assert!(
+            matches!(
+                classify(0x02, &sense(0x03)),
+                CommandOutcome::CheckCondition(SenseData::Fixed(_))
+            ),
+            "medium error"
+        );
+        assert!(
+            matches!(
+                classify(0x02, &SenseData::None),
+                CommandOutcome::CheckCondition(SenseData::None)
+            ),
+            "check condition without sense"
+        );
+        assert!(
+            matches!(classify(0x08, &SenseData::None), CommandOutcome::Busy),
+            "busy"
+        );
+        assert!(
+            matches!(
+                classify(0x28, &SenseData::None),
+                CommandOutcome::TaskSetFull
+            ),
+            "task set full"
+        );
+        assert!(
+            matches!(
+                classify(0x18, &SenseData::None),
+                CommandOutcome::Other(Status::ReservationConflict)
+            ),
+            "reservation conflict"
+        );
+    }
+
+    fn check_common_error(status: u8, sense: &SenseData) -> crate::Result<()> {
+        let mut data = ();
+        ResultData {
+            ioctl_result: 0,
+            transfered_data_length: 0,
+            data: &mut data,
+            transfered_sense_length: if matches!(sense, SenseData::None) {
+                0
+            } else {
+                18
+            },
+            sense_buffer: sense,
+            status: Status::from(status),
+            rejection: None,
+            protocol_violation: None,
+            #[cfg(target_os = "linux")]
+            host_status: HostStatus::Ok,
+            #[cfg(target_os = "linux")]
+            driver_status: DriverStatus::OK,
+        }
+        .check_common_error()
+    }
+
+    #[test]
+    fn check_condition_test() {
+        assert!(check_common_error(0x00, &SenseData::None).is_ok(), "good");
+
+        let error = check_common_error(0x02, &sense(0x03)).unwrap_err();
+        assert!(
+            matches!(error, crate::Error::CheckCondition(_)),
+            "medium error"
+        );
+        assert!(
+            matches!(
+                error.sense_data().and_then(|sense| sense.sense_key()),
+                Some(SenseKey::MediumError)
+            ),
+            "sense data"
+        );
+
+        for (sense_key, name) in [(0x01, "recovered error"), (0x02, "not ready")] {
+            assert!(
+                matches!(
+                    check_common_error(0x02, &sense(sense_key)),
+                    Err(crate::Error::CheckCondition(_))
+                ),
+                "{name}"
+            );
+        }
+
+        assert!(
+            matches!(
+                check_common_error(0x02, &SenseData::None),
+                Err(crate::Error::Other(_))
+            ),
+            "check condition without sense"
+        );
+        assert!(
+            matches!(
+                check_common_error(0x08, &SenseData::None),
+                Err(crate::Error::DeviceBusy)
+            ),
+            "busy"
+        );
+        assert!(
+            matches!(
+                check_common_error(0x28, &SenseData::None),
+                Err(crate::Error::TaskSetFull)
+            ),
+            "task set full"
+        );
+    }
+
+    #[test]
+    fn deferred_error_test() {
+        let deferred = sense_with_response_code(0x71, 0x03);
+        assert!(deferred.is_deferred(), "deferred");
+        assert!(!sense(0x03).is_deferred(), "current");
+
+        let mut data = ();
+        let result = ResultData {
+            ioctl_result: 0,
+            transfered_data_length: 0,
+            data: &mut data,
+            transfered_sense_length: 18,
+            sense_buffer: &deferred,
+            status: Status::CheckCondition,
+            rejection: None,
+            protocol_violation: None,
+            #[cfg(target_os = "linux")]
+            host_status: HostStatus::Ok,
+            #[cfg(target_os = "linux")]
+            driver_status: DriverStatus::OK,
+        };
+
+        let error = result.check_common_error().unwrap_err();
+        assert!(
+            matches!(error, crate::Error::DeferredError(_)),
+            "deferred error"
+        );
+        assert!(
+            matches!(
+                error.sense_data().and_then(|sense| sense.sense_key()),
+                Some(SenseKey::MediumError)
+            ),
+            "sense data"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn transport_error_test() {
+        let mut data = ();
+        let result = ResultData {
+            ioctl_result: 0,
+            transfered_data_length: 0,
+            data: &mut data,
+            transfered_sense_length: 0,
+            sense_buffer: &SenseData::None,
+            status: Status::Good,
+            rejection: None,
+            protocol_violation: None,
+            host_status: HostStatus::from(0x03),
+            driver_status: DriverStatus::OK,
+        };
+
+        assert!(
+            matches!(result.classify(), CommandOutcome::TransportError(0x03)),
+            "host time out"
+        );
+        assert!(result.check_common_error().is_err(), "check common error");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn driver_sense_test() {
+        fn result_data<'a>(
+            data: &'a mut (),
+            sense: &'a SenseData,
+            driver_status: DriverStatus,
+        ) -> ResultData<'a, ()> {
+            ResultData {
+                ioctl_result: 0,
+                transfered_data_length: 0,
+                data,
+                transfered_sense_length: 18,
+                sense_buffer: sense,
+                status: Status::CheckCondition,
+                rejection: None,
+                protocol_violation: None,
+                host_status: HostStatus::Ok,
+                driver_status,
+            }
+        }
+
+        // SG_IO reports DRIVER_SENSE along with every CHECK CONDITION that returns sense
+        let medium_error = sense(0x03);
+        let mut data = ();
+        let result = result_data(&mut data, &medium_error, DriverStatus::SENSE);
+        assert!(
+            matches!(result.classify(), CommandOutcome::CheckCondition(_)),
+            "driver sense"
+        );
+        assert!(
+            matches!(
+                result.check_common_error(),
+                Err(crate::Error::CheckCondition(_))
+            ),
+            "check common error"
+        );
+
+        let not_ready = sense(0x02);
+        let mut data = ();
+        let result = result_data(
+            &mut data,
+            &not_ready,
+            DriverStatus::SENSE | DriverStatus::SUGGEST_SENSE,
+        );
+        assert!(
+            matches!(result.classify(), CommandOutcome::NotReady(_)),
+            "driver sense with a suggestion"
+        );
+
+        let mut data = ();
+        let result = result_data(&mut data, &medium_error, DriverStatus::TIMEOUT);
+        assert!(
+            matches!(result.classify(), CommandOutcome::TransportError(0x06)),
+            "driver time out"
+        );
+    }
+}
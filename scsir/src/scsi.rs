@@ -2,51 +2,89 @@
 
 use std::{
     borrow::BorrowMut,
-    fs::OpenOptions,
     io,
     mem::size_of_val,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicI32, AtomicU32, Ordering},
+        OnceLock,
+    },
     time::Duration,
 };
 
-use crate::{file_descriptor::FileDescriptor, Command};
+use crate::{
+    file_descriptor::FileDescriptor, range_lock::RangeLock, BatchPolicy, BatchStep, CancelToken,
+    Command, Control, PeripheralDeviceType, ResetLevel, ScsiOptions,
+};
 
+/// An open SCSI device.
+///
+/// Scsi is Send and Sync, so one instance can be shared between threads, e.g. in an Arc,
+/// and issue commands from all of them at once. Every command is one self-contained SG_IO
+/// call on the file descriptor, and the driver queues concurrent calls. Settings applied to
+/// every command, set_timeout, set_default_control and set_logical_block_size, take &mut self
+/// and belong before the instance is shared. What a shared instance changes on its own, such as the cached block
+/// size, pack ids, range locking and type guards, is kept in atomics and locks.
 #[derive(Debug)]
 pub struct Scsi {
     path: PathBuf,
     file_descriptor: FileDescriptor,
     timeout: Duration,
+    default_control: Control,
+    // set by set_logical_block_size, None falls back to the cached block size
+    logical_block_size: Option<u32>,
+    // 0 means not cached yet
+    cached_block_size: AtomicU32,
+    // 0 means pack ids are not enabled
+    next_pack_id: AtomicI32,
+    range_lock: RangeLock,
+    // set by enable_type_guards
+    type_guard: OnceLock<PeripheralDeviceType>,
+    #[cfg(test)]
+    mock: Option<crate::mock::MockTransport>,
 }
 
 impl Scsi {
     pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> crate::Result<Scsi> {
-        let mut options = OpenOptions::new();
-        options.read(true).write(true);
-        let file_descriptor = FileDescriptor::open(&path, options)?;
-        Self::from_descriptor(path, file_descriptor)
+        Self::open(path, &ScsiOptions::new())
     }
 
     pub fn new_readonly<P: AsRef<Path> + ?Sized>(path: &P) -> crate::Result<Scsi> {
-        let mut options = OpenOptions::new();
-        options.read(true);
-        let file_descriptor = FileDescriptor::open(&path, options)?;
+        Self::open(path, ScsiOptions::new().read_only(true))
+    }
+
+    /// Opens the device with the given flags, then checks it is an SCSI block device.
+    pub fn open<P: AsRef<Path> + ?Sized>(path: &P, options: &ScsiOptions) -> crate::Result<Scsi> {
+        let file_descriptor = FileDescriptor::open(&path, options.open_options())?;
         Self::from_descriptor(path, file_descriptor)
     }
 
     #[cfg(target_os = "linux")]
     pub fn issue<T: Command>(&self, command: &T) -> T::ReturnType {
+        if let Some(result) = self.type_guard_issue(command) {
+            return result;
+        }
+
+        let _range_guard = self.range_lock.acquire(command.lba_range());
+
+        #[cfg(test)]
+        if let Some(mock) = &self.mock {
+            return mock.issue(command, self.next_pack_id());
+        }
+
         use nix::libc;
 
         use crate::{
             command::sense::{SenseData, MAX_SENSE_BUFFER_LENGTH},
             os::linux::{AccessFlags, AuxiliaryInfo, DriverStatus, SgIoHeader},
-            result_data::{ResultData, Status},
+            result_data::{transferred_length, ResultData, Status},
         };
 
         const SG_IO: u32 = 0x2285;
 
         let command_buffer = command.command();
         let mut data_buffer = command.data();
+        crate::command::debug_check_data_size(command, &data_buffer);
         let raw_pointer_to_data_buffer = &mut data_buffer as *mut _;
         let mut sense_buffer = [0u8; MAX_SENSE_BUFFER_LENGTH];
 
@@ -74,12 +112,13 @@ impl Scsi {
             data: pointer_of_data_buffer,
             command: pointer_of_command_buffer,
             sense_buffer: pointer_of_sense_buffer,
-            timeout: self
-                .timeout
+            timeout: command
+                .timeout()
+                .unwrap_or(self.timeout)
                 .as_millis()
                 .clamp(u32::MIN as u128, u32::MAX as u128) as u32,
             flags: AccessFlags::DEFAULT,
-            pack_id: 0,
+            pack_id: self.next_pack_id(),
             user_pointer: 0,
             status: 0,
             masked_status: 0,
@@ -106,14 +145,18 @@ impl Scsi {
             .map(|b| SenseData::parse(b, sense_buffer_written))
             .unwrap_or(SenseData::None);
 
+        let (transfered_data_length, protocol_violation) =
+            transferred_length(sg_header.data_length, sg_header.residual_count);
+
         let result_data = ResultData {
             ioctl_result,
-            transfered_data_length: sg_header.data_length as usize
-                - sg_header.residual_count as usize,
+            transfered_data_length,
             data: unsafe { &mut *raw_pointer_to_data_buffer },
             transfered_sense_length: sense_buffer_written,
             sense_buffer: &sense_data,
             status: Status::from(sg_header.status),
+            rejection: None,
+            protocol_violation,
             host_status: sg_header.host_status.into(),
             driver_status: sg_header.driver_status,
         };
@@ -123,6 +166,17 @@ impl Scsi {
 
     #[cfg(target_os = "windows")]
     pub fn issue<T: Command>(&self, command: &T) -> T::ReturnType {
+        if let Some(result) = self.type_guard_issue(command) {
+            return result;
+        }
+
+        let _range_guard = self.range_lock.acquire(command.lba_range());
+
+        #[cfg(test)]
+        if let Some(mock) = &self.mock {
+            return mock.issue(command, self.next_pack_id());
+        }
+
         use std::slice;
 
         use windows::Win32::{
@@ -144,6 +198,7 @@ impl Scsi {
 
         let command_buffer = command.command();
         let mut data_buffer = command.data();
+        crate::command::debug_check_data_size(command, &data_buffer);
 
         let size_of_command_buffer = size_of_val(&command_buffer) as u8;
         let size_of_data_buffer = command.data_size();
@@ -174,8 +229,9 @@ impl Scsi {
 
         spt.DataTransferLength = size_of_data_buffer;
 
-        spt.TimeOutValue = match self
-            .timeout
+        spt.TimeOutValue = match command
+            .timeout()
+            .unwrap_or(self.timeout)
             .as_secs()
             .clamp(u32::MIN as u64, u32::MAX as u64)
         {
@@ -222,6 +278,8 @@ impl Scsi {
             transfered_sense_length: header.scsi_pass_through.SenseInfoLength as usize,
             sense_buffer: &sense_data,
             status: Status::from(header.scsi_pass_through.ScsiStatus),
+            rejection: None,
+            protocol_violation: None,
         };
 
         command.process_result(result_data)
@@ -231,6 +289,7 @@ impl Scsi {
         &self.path
     }
 
+    /// Like set_default_control, this takes &mut self, so it is set before sharing the Scsi.
     pub fn set_timeout(&mut self, timeout: Duration) {
         self.timeout = timeout;
     }
@@ -239,6 +298,335 @@ impl Scsi {
         self.timeout
     }
 
+    /// Sets the CONTROL byte commands start out with, the per command control setters
+    /// still override it.
+    pub fn set_default_control(&mut self, control: Control) {
+        self.default_control = control;
+    }
+
+    pub(crate) fn default_control(&self) -> u8 {
+        self.default_control.into()
+    }
+
+    /// Calls f up to attempts times, sleeping delay in between, for as long as
+    /// it fails with DeviceBusy or TaskSetFull.
+    pub fn retry_while_busy<T, F>(
+        &self,
+        attempts: u32,
+        delay: Duration,
+        mut f: F,
+    ) -> crate::Result<T>
+    where
+        F: FnMut(&Scsi) -> crate::Result<T>,
+    {
+        let mut remaining = attempts;
+        loop {
+            remaining = remaining.saturating_sub(1);
+            match f(self) {
+                Err(crate::Error::DeviceBusy | crate::Error::TaskSetFull) if remaining > 0 => {
+                    std::thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Runs steps one after another and returns their results in the same order.
+    /// With StopOnError the result of the failed step is the last one returned.
+    ///
+    /// Each step is a closure issuing one command, usually through a builder, so a batch
+    /// can mix any commands as long as the steps map their results to the same type.
+    pub fn batch<R>(
+        &self,
+        steps: &mut [&mut BatchStep<'_, R>],
+        policy: BatchPolicy,
+    ) -> Vec<crate::Result<R>> {
+        let mut results = Vec::with_capacity(steps.len());
+
+        for step in steps {
+            let result = step(self);
+            let failed = result.is_err();
+            results.push(result);
+
+            if failed && policy == BatchPolicy::StopOnError {
+                break;
+            }
+        }
+
+        results
+    }
+
+    /// Makes issue wait while a write to an overlapping lba range is still in flight on
+    /// another thread, so parallel writers can't have the host reorder them. Only commands
+    /// reporting Command::lba_range take part, which currently are the WRITE commands.
+    pub fn enable_range_locking(&self) {
+        self.range_lock.enable();
+    }
+
+    /// Stamps an incrementing PACK ID into the SG_IO header of every command issued from
+    /// now on, starting at 1, so they can be told apart in kernel traces. Without it the
+    /// PACK ID stays 0. Only used by the Linux SG driver.
+    pub fn enable_pack_ids(&self) {
+        let _ = self
+            .next_pack_id
+            .compare_exchange(0, 1, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// Reads the peripheral device type with INQUIRY and from then on refuses commands
+    /// whose operation code only exists for other device types with Rejected, before
+    /// they reach the device. See PeripheralDeviceType::rejects_operation_code.
+    pub fn enable_type_guards(&self) -> crate::Result<PeripheralDeviceType> {
+        if let Some(device_type) = self.type_guard.get() {
+            return Ok(*device_type);
+        }
+
+        let inquiry = crate::shortcut::inquiry::standard_inquiry(&mut self.inquiry())?;
+        let device_type = PeripheralDeviceType::from(inquiry.peripheral_device_type);
+
+        Ok(*self.type_guard.get_or_init(|| device_type))
+    }
+
+    fn type_guard_issue<T: Command>(&self, command: &T) -> Option<T::ReturnType> {
+        let device_type = self.type_guard.get()?;
+        let operation_code = *command.cdb_bytes().first()?;
+
+        if !device_type.rejects_operation_code(operation_code) {
+            return None;
+        }
+
+        Some(self.synthesized_result(
+            command,
+            Some(format!(
+                "operation code {:#04X} is not available for {:?}",
+                operation_code, device_type
+            )),
+        ))
+    }
+
+    // result of a command that never reached the device
+    fn synthesized_result<T: Command>(
+        &self,
+        command: &T,
+        rejection: Option<String>,
+    ) -> T::ReturnType {
+        use crate::{command::sense::SenseData, result_data::ResultData, result_data::Status};
+
+        let mut data_buffer = command.data();
+        let result_data = ResultData {
+            ioctl_result: 0,
+            transfered_data_length: 0,
+            data: &mut data_buffer,
+            transfered_sense_length: 0,
+            sense_buffer: &SenseData::None,
+            status: Status::Good,
+            rejection,
+            protocol_violation: None,
+            #[cfg(target_os = "linux")]
+            host_status: crate::os::linux::HostStatus::Ok,
+            #[cfg(target_os = "linux")]
+            driver_status: crate::os::linux::DriverStatus::OK,
+        };
+
+        command.process_result(result_data)
+    }
+
+    /// Issues command on a helper thread while watching token. Once cancelled, the
+    /// logical unit alone is reset to abort the command, which is then waited for.
+    /// Cancelled is returned unless the command completed successfully anyway, in which
+    /// case its result is returned as usual.
+    ///
+    /// The reset needs the same privileges as Scsi::reset and is not available on
+    /// Windows, where a cancelled command still runs to completion. A device may also
+    /// ignore the reset, and it aborts every other command outstanding on the logical unit.
+    pub fn issue_cancellable<T, R>(&self, command: &T, token: &CancelToken) -> crate::Result<R>
+    where
+        T: Command<ReturnType = crate::Result<R>> + Sync,
+        R: Send,
+    {
+        if token.is_cancelled() {
+            return Err(crate::Error::Cancelled);
+        }
+
+        std::thread::scope(|scope| {
+            let worker = scope.spawn(|| self.issue(command));
+
+            while !worker.is_finished() {
+                if token.is_cancelled() {
+                    // the command may have completed while the token was checked
+                    if worker.is_finished() {
+                        break;
+                    }
+
+                    let _ = self.reset(ResetLevel::Device);
+
+                    return match worker.join() {
+                        Ok(Ok(result)) => Ok(result),
+                        _ => Err(crate::Error::Cancelled),
+                    };
+                }
+
+                std::thread::sleep(CANCEL_POLL_INTERVAL);
+            }
+
+            worker
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e))
+        })
+    }
+
+    // 0 while pack ids are not enabled, wraps around to 1 after i32::MAX
+    pub(crate) fn next_pack_id(&self) -> i32 {
+        self.next_pack_id
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| {
+                (id != 0).then(|| id.checked_add(1).unwrap_or(1))
+            })
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn cached_block_size(&self) -> Option<u32> {
+        match self.cached_block_size.load(Ordering::Relaxed) {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub(crate) fn set_cached_block_size(&self, value: u32) {
+        self.cached_block_size.store(value, Ordering::Relaxed);
+    }
+
+    /// Sets the logical block size that read, write, verify and the other block command
+    /// builders start with. Builders created afterwards use it unless their own
+    /// logical_block_size is set. It is kept apart from the device block size that
+    /// cache_block_size and block_size remember, which builders use while this is not set.
+    /// 0 unsets it, and without either builders fall back to 512 bytes.
+    pub fn set_logical_block_size(&mut self, value: u32) {
+        self.logical_block_size = (value != 0).then_some(value);
+    }
+
+    // the logical block size builders start with, see set_logical_block_size
+    pub(crate) fn default_logical_block_size(&self) -> u32 {
+        self.logical_block_size
+            .or_else(|| self.cached_block_size())
+            .unwrap_or(DEFAULT_LOGICAL_BLOCK_SIZE)
+    }
+
+    /// Resets the device at level through the SG driver, outside of any SCSI command.
+    /// Only that level is reset, see reset_escalating to also try wider ones.
+    /// Requires CAP_SYS_ADMIN and CAP_SYS_RAWIO.
+    #[cfg(target_os = "linux")]
+    pub fn reset(&self, level: ResetLevel) -> crate::Result<()> {
+        Ok(self.reset_ioctl(level)?)
+    }
+
+    /// Like reset, but while the driver fails to reset at a level (EIO), the next wider
+    /// level is tried, up to resetting the whole host adapter. The level that succeeded is
+    /// returned. Any other error, such as missing privileges or a busy host, is returned
+    /// right away without escalating.
+    #[cfg(target_os = "linux")]
+    pub fn reset_escalating(&self, level: ResetLevel) -> crate::Result<ResetLevel> {
+        let mut last_error = None;
+        for level in level.escalation() {
+            match self.reset_ioctl(*level) {
+                Ok(()) => return Ok(*level),
+                Err(e) if e.raw_os_error() == Some(nix::libc::EIO) => last_error = Some(e),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(last_error.unwrap())?
+    }
+
+    #[cfg(target_os = "linux")]
+    fn reset_ioctl(&self, level: ResetLevel) -> io::Result<()> {
+        use nix::libc;
+
+        const SG_SCSI_RESET: libc::Ioctl = 0x2284;
+        const SG_SCSI_RESET_NO_ESCALATE: i32 = 0x100;
+
+        #[cfg(test)]
+        if let Some(mock) = &self.mock {
+            return mock.reset(level);
+        }
+
+        let mut value: libc::c_int = libc::c_int::from(level) | SG_SCSI_RESET_NO_ESCALATE;
+        let result = unsafe { libc::ioctl(self.file_descriptor.raw(), SG_SCSI_RESET, &mut value) };
+
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn reset(&self, _level: ResetLevel) -> crate::Result<()> {
+        Err(crate::Error::Unsupported("reset".to_owned()))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn reset_escalating(&self, _level: ResetLevel) -> crate::Result<ResetLevel> {
+        Err(crate::Error::Unsupported("reset".to_owned()))
+    }
+
+    /// The SG driver's reserved buffer size, which caps a single transfer.
+    #[cfg(target_os = "linux")]
+    pub fn sg_reserved_size(&self) -> crate::Result<u32> {
+        use nix::libc;
+
+        const SG_GET_RESERVED_SIZE: libc::Ioctl = 0x2272;
+
+        #[cfg(test)]
+        if let Some(mock) = &self.mock {
+            return mock
+                .reserved_size()
+                .ok_or_else(|| crate::Error::Unsupported("sg_reserved_size".to_owned()));
+        }
+
+        let mut size: libc::c_int = 0;
+        let result =
+            unsafe { libc::ioctl(self.file_descriptor.raw(), SG_GET_RESERVED_SIZE, &mut size) };
+
+        if result != 0 {
+            Err(io::Error::last_os_error())?;
+        }
+
+        Ok(size as u32)
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn sg_reserved_size(&self) -> crate::Result<u32> {
+        Err(crate::Error::Unsupported("sg_reserved_size".to_owned()))
+    }
+
+    /// The driver may clamp the requested size, read it back with sg_reserved_size.
+    #[cfg(target_os = "linux")]
+    pub fn set_sg_reserved_size(&self, size: u32) -> crate::Result<()> {
+        use nix::libc;
+
+        const SG_SET_RESERVED_SIZE: libc::Ioctl = 0x2275;
+
+        let size: libc::c_int = size.try_into().map_err(|_| {
+            crate::Error::ArgumentOutOfBounds(format!(
+                "reserved size is out of bounds. The maximum possible value is {}, but {} was provided.",
+                libc::c_int::MAX,
+                size
+            ))
+        })?;
+        let result =
+            unsafe { libc::ioctl(self.file_descriptor.raw(), SG_SET_RESERVED_SIZE, &size) };
+
+        if result != 0 {
+            Err(io::Error::last_os_error())?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn set_sg_reserved_size(&self, _size: u32) -> crate::Result<()> {
+        Err(crate::Error::Unsupported("set_sg_reserved_size".to_owned()))
+    }
+
     fn from_descriptor<P: AsRef<Path> + ?Sized>(
         path: &P,
         file_descriptor: FileDescriptor,
@@ -255,6 +643,14 @@ impl Scsi {
             path: path.as_ref().to_owned(),
             file_descriptor,
             timeout: Duration::from_millis(SG_DEFAULT_TIMEOUT),
+            default_control: Control::default(),
+            logical_block_size: None,
+            cached_block_size: AtomicU32::new(0),
+            next_pack_id: AtomicI32::new(0),
+            range_lock: RangeLock::default(),
+            type_guard: OnceLock::new(),
+            #[cfg(test)]
+            mock: None,
         })
     }
 
@@ -319,4 +715,576 @@ impl Scsi {
     }
 }
 
+#[cfg(test)]
+impl Scsi {
+    /// An instance backed by a regular file whose commands go to a mock transport.
+    pub(crate) fn test_instance() -> Scsi {
+        let path = std::env::current_exe().unwrap();
+        let options = ScsiOptions::new().read_only(true).open_options();
+        let file_descriptor = FileDescriptor::open(&path, options).unwrap();
+
+        Scsi {
+            path,
+            file_descriptor,
+            timeout: Duration::from_millis(SG_DEFAULT_TIMEOUT),
+            default_control: Control::default(),
+            logical_block_size: None,
+            cached_block_size: AtomicU32::new(0),
+            next_pack_id: AtomicI32::new(0),
+            range_lock: RangeLock::default(),
+            type_guard: OnceLock::new(),
+            mock: Some(Default::default()),
+        }
+    }
+
+    pub(crate) fn mock(&self) -> &crate::mock::MockTransport {
+        self.mock.as_ref().unwrap()
+    }
+}
+
 const SG_DEFAULT_TIMEOUT: u64 = 60_000;
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const DEFAULT_LOGICAL_BLOCK_SIZE: u32 = 512;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockResponse, result_data::ResultData, DataDirection};
+
+    struct OversizedCommand;
+
+    impl Command for OversizedCommand {
+        type CommandBuffer = [u8; 6];
+
+        type DataBuffer = [u8; 4];
+
+        type DataBufferWrapper = [u8; 4];
+
+        type ReturnType = crate::Result<()>;
+
+        fn direction(&self) -> DataDirection {
+            DataDirection::FromDevice
+        }
+
+        fn command(&self) -> Self::CommandBuffer {
+            [0x1C, 0, 0, 0, 8, 0]
+        }
+
+        fn data(&self) -> Self::DataBufferWrapper {
+            [0; 4]
+        }
+
+        fn data_size(&self) -> u32 {
+            8
+        }
+
+        fn process_result(&self, _result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+            Ok(())
+        }
+    }
+
+    struct ToFromDeviceCommand;
+
+    impl Command for ToFromDeviceCommand {
+        type CommandBuffer = [u8; 6];
+
+        type DataBuffer = [u8; 4];
+
+        type DataBufferWrapper = [u8; 4];
+
+        type ReturnType = crate::Result<[u8; 4]>;
+
+        fn direction(&self) -> DataDirection {
+            DataDirection::ToFromDevice
+        }
+
+        fn command(&self) -> Self::CommandBuffer {
+            [0x1D, 0, 0, 0, 4, 0]
+        }
+
+        fn data(&self) -> Self::DataBufferWrapper {
+            [0x01, 0x02, 0x03, 0x04]
+        }
+
+        fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+            result.check_ioctl_error()?;
+            result.check_common_error()?;
+
+            Ok(*result.data)
+        }
+    }
+
+    #[test]
+    fn to_from_device_test() {
+        // XDWRITEREAD(10) of one block: the block goes out, the XOR result comes back
+        let cdb = [0x53, 0, 0, 0, 0, 0x10, 0, 0, 0x01, 0];
+        let written: Vec<u8> = (0..512).map(|i| i as u8).collect();
+        let xor_result = [0xA5; 512];
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&xor_result);
+
+        let mut data = written.clone();
+        let outcome = scsi
+            .execute(&cdb, DataDirection::ToFromDevice, &mut data, None)
+            .unwrap();
+
+        assert!(
+            matches!(outcome.status, crate::result_data::Status::Good),
+            "status"
+        );
+        assert_eq!(outcome.transferred_length, 512, "transferred length");
+        assert_eq!(data, xor_result, "received data");
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb, cdb, "cdb");
+        assert_eq!(issued[0].data_out, written, "sent data");
+    }
+
+    #[test]
+    fn batch_test() {
+        let check_condition = [
+            0x70, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x24, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut test_unit_ready = |scsi: &Scsi| scsi.test_unit_ready().issue().map(|_| Vec::new());
+        let mut read = |scsi: &Scsi| {
+            scsi.read()
+                .logical_block_address(8)
+                .transfer_length(1)
+                .issue_16()
+        };
+        let mut inquiry = |scsi: &Scsi| scsi.inquiry().allocation_length(4).issue();
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&check_condition);
+        scsi.mock().push_data(&[0x03; 4]);
+
+        let results = scsi.batch(
+            &mut [&mut test_unit_ready, &mut read, &mut inquiry],
+            BatchPolicy::StopOnError,
+        );
+
+        assert_eq!(results.len(), 2, "stopped after the failure");
+        assert!(results[0].as_ref().unwrap().is_empty(), "test unit ready");
+        assert!(
+            matches!(results[1], Err(crate::Error::CheckCondition(_))),
+            "read"
+        );
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 2, "issued");
+        assert_eq!(issued[1].cdb[0], 0x88, "read(16)");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_data(&[]);
+        scsi.mock().push_sense(&check_condition);
+        scsi.mock().push_data(&[0x03; 4]);
+
+        let results = scsi.batch(
+            &mut [&mut test_unit_ready, &mut read, &mut inquiry],
+            BatchPolicy::Continue,
+        );
+
+        assert_eq!(results.len(), 3, "every step run");
+        assert!(results[1].is_err(), "read");
+        assert_eq!(results[2].as_ref().unwrap(), &[0x03; 4], "inquiry");
+        let issued = scsi.mock().issued();
+        assert_eq!(issued.len(), 3, "issued");
+        assert_eq!(issued[2].cdb[0], 0x12, "inquiry");
+    }
+
+    #[test]
+    fn pack_id_test() {
+        let scsi = Scsi::test_instance();
+        scsi.is_ready().unwrap();
+        scsi.enable_pack_ids();
+        scsi.is_ready().unwrap();
+        scsi.is_ready().unwrap();
+        scsi.enable_pack_ids();
+        scsi.is_ready().unwrap();
+
+        let pack_ids: Vec<i32> = scsi
+            .mock()
+            .issued()
+            .iter()
+            .map(|command| command.pack_id)
+            .collect();
+        assert_eq!(pack_ids, [0, 1, 2, 3], "pack ids");
+
+        scsi.next_pack_id.store(i32::MAX, Ordering::Relaxed);
+        assert_eq!(scsi.next_pack_id(), i32::MAX, "last pack id");
+        assert_eq!(scsi.next_pack_id(), 1, "wrapped around");
+    }
+
+    #[test]
+    fn busy_test() {
+        let scsi = Scsi::test_instance();
+        let busy = MockResponse {
+            status: 0x08,
+            ..Default::default()
+        };
+        let task_set_full = MockResponse {
+            status: 0x28,
+            ..Default::default()
+        };
+
+        scsi.mock().push_response(busy.clone());
+        assert!(
+            matches!(
+                scsi.test_unit_ready().issue(),
+                Err(crate::Error::DeviceBusy)
+            ),
+            "busy"
+        );
+
+        scsi.mock().push_response(task_set_full);
+        assert!(
+            matches!(
+                scsi.test_unit_ready().issue(),
+                Err(crate::Error::TaskSetFull)
+            ),
+            "task set full"
+        );
+
+        scsi.mock().push_response(busy.clone());
+        let result = scsi.retry_while_busy(3, Duration::ZERO, |s| s.test_unit_ready().issue());
+        assert!(result.is_ok(), "busy then good");
+        assert_eq!(scsi.mock().issued().len(), 4, "issued commands");
+
+        scsi.mock().push_response(busy.clone());
+        scsi.mock().push_response(busy);
+        let result = scsi.retry_while_busy(2, Duration::ZERO, |s| s.test_unit_ready().issue());
+        assert!(
+            matches!(result, Err(crate::Error::DeviceBusy)),
+            "out of attempts"
+        );
+    }
+
+    #[test]
+    fn residual_overflow_test() {
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_response(MockResponse {
+            data: vec![0xAA; 512],
+            residual_count: Some(4096),
+            ..Default::default()
+        });
+        scsi.mock().push_response(MockResponse {
+            residual_count: Some(-1),
+            ..Default::default()
+        });
+        scsi.mock().push_response(MockResponse {
+            data: vec![0xAA; 512],
+            residual_count: Some(256),
+            ..Default::default()
+        });
+
+        let result = scsi.read().transfer_length(1).issue_16();
+        assert!(
+            matches!(result, Err(crate::Error::ProtocolViolation(_))),
+            "residual larger than data length"
+        );
+
+        let result = scsi.test_unit_ready().issue();
+        assert!(
+            matches!(result, Err(crate::Error::ProtocolViolation(_))),
+            "negative residual"
+        );
+
+        let data = scsi.read().transfer_length(1).issue_16().unwrap();
+        assert_eq!(data.len(), 256, "residual within data length");
+    }
+
+    // needs a real SG device, e.g. SCSIR_TEST_DEVICE=/dev/sg0 cargo test -- --ignored
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore]
+    fn sg_reserved_size_test() {
+        let path = std::env::var("SCSIR_TEST_DEVICE").expect("SCSIR_TEST_DEVICE is not set");
+        let scsi = Scsi::new(&path).unwrap();
+        let original = scsi.sg_reserved_size().unwrap();
+
+        scsi.set_sg_reserved_size(64 * 1024).unwrap();
+        assert_eq!(scsi.sg_reserved_size().unwrap(), 64 * 1024, "reserved size");
+
+        scsi.set_sg_reserved_size(original).unwrap();
+    }
+
+    #[test]
+    fn reset_test() {
+        use nix::libc::{EACCES, EIO};
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_reset_error(EIO);
+        assert!(
+            scsi.reset(ResetLevel::Device).is_err(),
+            "device reset failed"
+        );
+        assert_eq!(scsi.mock().resets(), [ResetLevel::Device], "no escalation");
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_reset_error(EIO);
+        scsi.mock().push_reset_error(EIO);
+        assert_eq!(
+            scsi.reset_escalating(ResetLevel::Device).unwrap(),
+            ResetLevel::Bus,
+            "escalated"
+        );
+        assert_eq!(
+            scsi.mock().resets(),
+            [ResetLevel::Device, ResetLevel::Target, ResetLevel::Bus],
+            "escalation order"
+        );
+
+        let scsi = Scsi::test_instance();
+        scsi.mock().push_reset_error(EACCES);
+        assert!(
+            matches!(
+                scsi.reset_escalating(ResetLevel::Device),
+                Err(crate::Error::IO(ref e)) if e.raw_os_error() == Some(EACCES)
+            ),
+            "permission error"
+        );
+        assert_eq!(
+            scsi.mock().resets(),
+            [ResetLevel::Device],
+            "no escalation on a permission error"
+        );
+    }
+
+    #[test]
+    fn issue_cancellable_test() {
+        let scsi = Scsi::test_instance();
+        let token = CancelToken::new();
+
+        let result = scsi.issue_cancellable(&ToFromDeviceCommand, &token);
+        assert!(result.is_ok(), "not cancelled");
+        assert_eq!(scsi.mock().issued().len(), 1, "issued");
+
+        token.clone().cancel();
+        let result = scsi.issue_cancellable(&ToFromDeviceCommand, &token);
+        assert!(
+            matches!(result, Err(crate::Error::Cancelled)),
+            "cancelled before issue"
+        );
+        assert_eq!(scsi.mock().issued().len(), 1, "nothing more issued");
+        assert!(scsi.mock().resets().is_empty(), "no reset");
+    }
+
+    // cancels the token from within the command, then keeps running long enough to be reset
+    struct CancellingCommand<'a> {
+        token: &'a CancelToken,
+    }
+
+    impl Command for CancellingCommand<'_> {
+        type CommandBuffer = [u8; 6];
+
+        type DataBuffer = ();
+
+        type DataBufferWrapper = ();
+
+        type ReturnType = crate::Result<()>;
+
+        fn direction(&self) -> DataDirection {
+            DataDirection::None
+        }
+
+        fn command(&self) -> Self::CommandBuffer {
+            [0; 6]
+        }
+
+        fn data(&self) -> Self::DataBufferWrapper {}
+
+        fn process_result(&self, result: ResultData<Self::DataBufferWrapper>) -> Self::ReturnType {
+            self.token.clone().cancel();
+            std::thread::sleep(CANCEL_POLL_INTERVAL * 5);
+
+            result.check_common_error()
+        }
+    }
+
+    #[test]
+    fn issue_cancellable_reset_test() {
+        let scsi = Scsi::test_instance();
+        let token = CancelToken::new();
+        scsi.mock().push_data(&[]);
+
+        let result = scsi.issue_cancellable(&CancellingCommand { token: &token }, &token);
+        assert!(result.is_ok(), "completed despite the cancellation");
+        assert_eq!(
+            scsi.mock().resets(),
+            [ResetLevel::Device],
+            "device reset only"
+        );
+
+        let scsi = Scsi::test_instance();
+        let token = CancelToken::new();
+        scsi.mock().push_sense(&[
+            0x70, 0, 0x06, 0, 0, 0, 0, 0x0A, 0, 0, 0, 0, 0x29, 0x00, 0, 0, 0, 0,
+        ]);
+
+        let result = scsi.issue_cancellable(&CancellingCommand { token: &token }, &token);
+        assert!(
+            matches!(result, Err(crate::Error::Cancelled)),
+            "aborted by the reset"
+        );
+        assert_eq!(
+            scsi.mock().resets(),
+            [ResetLevel::Device],
+            "device reset only"
+        );
+    }
+
+    // needs a real SCSI block device that is not mounted, e.g.
+    // SCSIR_TEST_DEVICE=/dev/sdb cargo test -- --ignored
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore]
+    fn exclusive_open_test() {
+        let path = std::env::var("SCSIR_TEST_DEVICE").expect("SCSIR_TEST_DEVICE is not set");
+        let mut options = ScsiOptions::new();
+        options.read_only(true).exclusive(true).nonblocking(true);
+
+        let _scsi = Scsi::open(&path, &options).unwrap();
+        let result = Scsi::open(&path, &options);
+        assert!(
+            matches!(result, Err(crate::Error::IO(ref e)) if e.raw_os_error() == Some(nix::libc::EBUSY)),
+            "second exclusive open"
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "exceeds the data buffer length")]
+    fn oversized_data_size_test() {
+        let scsi = Scsi::test_instance();
+        let _ = scsi.issue(&OversizedCommand);
+    }
+
+    #[test]
+    fn range_locking_test() {
+        let scsi = Scsi::test_instance();
+        scsi.enable_range_locking();
+
+        std::thread::scope(|scope| {
+            let guard = scsi.range_lock.acquire(Some((0, 8))).unwrap();
+            let writer = scope.spawn(|| {
+                scsi.write()
+                    .logical_block_address(4)
+                    .parameter(&[0; 4096])
+                    .issue_16()
+            });
+
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(scsi.mock().issued().is_empty(), "overlapping write waits");
+
+            drop(guard);
+            assert!(writer.join().unwrap().is_ok(), "write after release");
+        });
+
+        assert_eq!(scsi.mock().issued().len(), 1, "issued");
+    }
+
+    #[test]
+    fn type_guard_test() {
+        let scsi = Scsi::test_instance();
+        let mut inquiry = vec![0; 96];
+        inquiry[4] = 91;
+        scsi.mock().push_data(&inquiry);
+
+        let device_type = scsi.enable_type_guards().unwrap();
+        assert_eq!(
+            device_type,
+            PeripheralDeviceType::DirectAccessBlock,
+            "device type"
+        );
+
+        let move_medium = [0xA5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let result = scsi.execute(&move_medium, DataDirection::None, &mut [], None);
+        assert!(
+            matches!(result, Err(crate::Error::Rejected(_))),
+            "changer command on a disk"
+        );
+        assert_eq!(scsi.mock().issued().len(), 1, "only inquiry issued");
+
+        assert!(scsi.test_unit_ready().issue().is_ok(), "disk command");
+        assert_eq!(scsi.mock().issued().len(), 2, "disk command issued");
+    }
+
+    #[test]
+    fn default_control_test() {
+        let mut scsi = Scsi::test_instance();
+        scsi.set_default_control(*Control::new().naca(true));
+
+        scsi.test_unit_ready().issue().unwrap();
+        scsi.test_unit_ready().control(0x00).issue().unwrap();
+        scsi.read_capacity().issue_10().unwrap();
+
+        let issued = scsi.mock().issued();
+        assert_eq!(issued[0].cdb, [0x00, 0, 0, 0, 0, 0x04], "inherited");
+        assert_eq!(issued[1].cdb, [0x00, 0, 0, 0, 0, 0x00], "overridden");
+        assert_eq!(issued[2].cdb[9], 0x04, "read capacity");
+    }
+
+    #[test]
+    fn send_sync_test() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+        assert_send::<Scsi>();
+        assert_sync::<Scsi>();
+
+        let scsi = std::sync::Arc::new(Scsi::test_instance());
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let scsi = scsi.clone();
+                std::thread::spawn(move || scsi.test_unit_ready().issue())
+            })
+            .collect();
+
+        for worker in workers {
+            assert!(worker.join().unwrap().is_ok(), "shared issue");
+        }
+        assert_eq!(scsi.mock().issued().len(), 4, "issued commands");
+    }
+
+    #[test]
+    fn set_logical_block_size_test() {
+        let mut scsi = Scsi::test_instance();
+        assert_eq!(scsi.default_logical_block_size(), 512, "fallback");
+
+        scsi.set_cached_block_size(520);
+        assert_eq!(scsi.default_logical_block_size(), 520, "device block size");
+
+        scsi.set_logical_block_size(4096);
+        assert_eq!(scsi.default_logical_block_size(), 4096, "own setting first");
+        assert_eq!(scsi.cached_block_size(), Some(520), "cache untouched");
+
+        let data = [0; 2 * 4096];
+        scsi.mock().push_data(&data);
+        assert_eq!(
+            scsi.read().transfer_length(2).issue_16().unwrap().len(),
+            data.len(),
+            "read"
+        );
+        scsi.write().parameter(&data).issue_16().unwrap();
+        scsi.verify()
+            .byte_check(1)
+            .parameter(&data)
+            .issue_16()
+            .unwrap();
+        scsi.write_and_verify().parameter(&data).issue_16().unwrap();
+        scsi.write_atomic().parameter(&data).issue_16().unwrap();
+        scsi.write_stream().parameter(&data).issue_16().unwrap();
+
+        // every transfer length is 2 blocks of 4096 bytes, not 16 blocks of 512
+        for command in scsi.mock().issued() {
+            assert_eq!(
+                command.cdb[10..14],
+                [0, 0, 0, 2],
+                "transfer length of {:02X}",
+                command.cdb[0]
+            );
+        }
+    }
+}
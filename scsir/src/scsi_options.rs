@@ -0,0 +1,86 @@
+use std::fs::OpenOptions;
+
+/// Flags used by Scsi::open, read-write and shared by default.
+#[derive(Clone, Debug, Default)]
+pub struct ScsiOptions {
+    read_only: bool,
+    exclusive: bool,
+    direct: bool,
+    nonblocking: bool,
+}
+
+impl ScsiOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read_only(&mut self, value: bool) -> &mut Self {
+        self.read_only = value;
+        self
+    }
+
+    /// O_EXCL on Linux, no sharing on Windows.
+    pub fn exclusive(&mut self, value: bool) -> &mut Self {
+        self.exclusive = value;
+        self
+    }
+
+    /// O_DIRECT on Linux, FILE_FLAG_NO_BUFFERING and FILE_FLAG_WRITE_THROUGH on Windows.
+    pub fn direct(&mut self, value: bool) -> &mut Self {
+        self.direct = value;
+        self
+    }
+
+    /// O_NONBLOCK on Linux, ignored on Windows.
+    pub fn nonblocking(&mut self, value: bool) -> &mut Self {
+        self.nonblocking = value;
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn open_options(&self) -> OpenOptions {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        use nix::libc;
+
+        let mut flags = 0;
+        if self.exclusive {
+            flags |= libc::O_EXCL;
+        }
+        if self.direct {
+            flags |= libc::O_DIRECT;
+        }
+        if self.nonblocking {
+            flags |= libc::O_NONBLOCK;
+        }
+
+        let mut options = OpenOptions::new();
+        options
+            .read(true)
+            .write(!self.read_only)
+            .custom_flags(flags);
+        options
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn open_options(&self) -> OpenOptions {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        use windows::Win32::Storage::FileSystem::{
+            FILE_FLAG_NO_BUFFERING, FILE_FLAG_WRITE_THROUGH,
+        };
+
+        let mut options = OpenOptions::new();
+        options.read(true).write(!self.read_only);
+
+        if self.exclusive {
+            options.share_mode(0);
+        }
+
+        if self.direct {
+            options.custom_flags(FILE_FLAG_NO_BUFFERING.0 | FILE_FLAG_WRITE_THROUGH.0);
+        }
+
+        options
+    }
+}